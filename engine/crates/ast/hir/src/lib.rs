@@ -6,6 +6,7 @@
 use ctrl::Control;
 use expr::{Comp, Expr, Primitive};
 use fnc::Func;
+use lexer::Token;
 use mcr::Unknown;
 
 #[derive(Debug, Clone)]
@@ -19,6 +20,9 @@ pub enum Hir {
     // To be determined via Macro-Matching
     Unknown(Unknown),
 
+    // Tokens which could not be parsed, the error is reported separately
+    Error(Vec<Token>),
+
     // No Operation
     NoOp,
 }
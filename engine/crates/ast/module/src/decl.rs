@@ -1,4 +1,34 @@
+use crate::mcr::MacroDecl;
+use expr::Primitive;
+use hir::Hir;
+use variants::LineNo;
+
+// fn ident(params) -> ret decl terms end
+#[derive(Debug, Clone)]
+pub struct FuncDecl {
+    pub lno: LineNo,
+    pub public: bool,
+
+    pub ident: Primitive,
+    pub params: Vec<Primitive>,
+    pub ret: Primitive,
+
+    pub terms: Box<Hir>,
+}
+
+// const ident = value
+#[derive(Debug, Clone)]
+pub struct ConstDecl {
+    pub lno: LineNo,
+    pub public: bool,
+
+    pub ident: Primitive,
+    pub value: Primitive,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct Decl {
-    macr: Vec<MacroDecl>,
-    func: Vec<FuncDecl>,
+    pub macr: Vec<MacroDecl>,
+    pub func: Vec<FuncDecl>,
+    pub consts: Vec<ConstDecl>,
 }
@@ -1,21 +1,38 @@
 use either::Either;
 use variants::LineNo;
 
+#[derive(Debug, Clone)]
 pub struct Wildcard {}
+
+#[derive(Debug, Clone)]
 pub struct Alias {
     pub ident: String,
     pub alias: Option<String>,
 }
 
+#[derive(Debug, Clone)]
 pub struct Stmt {
     pub lno: LineNo,
+    // pub from x import y, re-exports the imports
+    pub public: bool,
 
     pub path: Vec<String>,
 
     pub imports: Either<Vec<Alias>, Wildcard>,
 }
 
+// import x::y [as z], the functions of the module are called through the alias or path
+#[derive(Debug, Clone)]
+pub struct Namespace {
+    pub lno: LineNo,
+
+    pub path: Vec<String>,
+    pub alias: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct Imp {
-    macr: Vec<Stmt>,
-    func: Vec<Stmt>,
+    pub macr: Vec<Stmt>,
+    pub func: Vec<Stmt>,
+    pub namespace: Vec<Namespace>,
 }
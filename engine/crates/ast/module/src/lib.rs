@@ -1,11 +1,15 @@
-mod decl;
-mod imp;
+pub mod decl;
+pub mod imp;
+pub mod mcr;
 
-use crate::imp::Imp;
 pub use decl::Decl;
+use hir::Hir;
+pub use imp::Imp;
+pub use mcr::MacroDecl;
 
+#[derive(Debug, Clone)]
 pub struct Module {
-    pub imp: Vec<Imp>,
+    pub imp: Imp,
 
     pub decl: Decl,
 
@@ -0,0 +1,158 @@
+use lexer::{
+    Comp, GroupQuantifier, Keyword, Kind, MacroModifier, Placeholder, PlaceholderVariant, Token,
+};
+use variants::LineNo;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Class {
+    // a single number
+    Value,
+    // a single identifier
+    Ident,
+}
+
+// compile-time expression of @if, evaluated against the matched tokens while expanding,
+// the operands are either placeholders or tokens.
+#[derive(Debug, Clone)]
+pub enum Condition {
+    Comp {
+        lhs: Box<Segment>,
+        verb: Comp,
+        rhs: Box<Segment>,
+    },
+    Is {
+        operand: Box<Segment>,
+        class: Class,
+    },
+
+    Not(Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+impl Condition {
+    // all operands of the expression
+    pub fn operands(&self) -> Vec<&Segment> {
+        match self {
+            Condition::Comp { lhs, rhs, .. } => vec![lhs.as_ref(), rhs.as_ref()],
+            Condition::Is { operand, .. } => vec![operand.as_ref()],
+            Condition::Not(cond) => cond.operands(),
+            Condition::And(lhs, rhs) | Condition::Or(lhs, rhs) => {
+                [lhs.operands(), rhs.operands()].concat()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Segment {
+    Token(Token),
+    Placeholder {
+        lno: LineNo,
+
+        placeholder: Placeholder,
+    },
+    // @( <segments> @)<quantifier>, in the substitution the group is repeated
+    // as often as the group in the pattern, which declared the placeholders of the group.
+    Group {
+        lno: LineNo,
+
+        segments: Vec<Segment>,
+        quantifier: GroupQuantifier,
+    },
+    // @if <condition> <then> [@else <otherwise>] @end, only valid in the substitution
+    Conditional {
+        lno: LineNo,
+
+        condition: Condition,
+        then: Vec<Segment>,
+        otherwise: Vec<Segment>,
+    },
+}
+
+impl Segment {
+    pub fn lno(&self) -> LineNo {
+        match self {
+            Segment::Token(token) => token.lno,
+            Segment::Placeholder { lno, .. } => *lno,
+            Segment::Group { lno, .. } => *lno,
+            Segment::Conditional { lno, .. } => *lno,
+        }
+    }
+}
+
+// Constructs spanning multiple lines need to be delimited while parsing,
+// otherwise the END of the construct would be taken as the END of the enclosing block.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Delimiter {
+    // IF ... END, the construct itself is closed by an END
+    End,
+    // WHILE ... DO ... END, the construct is closed by the block it contains
+    Block,
+}
+
+#[derive(Debug, Clone)]
+pub struct MacroDecl {
+    pub lno: LineNo,
+
+    pub ident: String,
+    pub modifier: MacroModifier,
+    pub priority: u32,
+
+    // separators are not part of the pattern
+    pub pattern: Vec<Segment>,
+    pub sub: Vec<Segment>,
+}
+
+// the number of tokens which need to be matched literally, groups which can be skipped
+// do not count, as they might not be matched at all.
+fn literals(segments: &[Segment]) -> usize {
+    segments
+        .iter()
+        .map(|segment| match segment {
+            Segment::Token(_) => 1,
+            Segment::Group {
+                segments,
+                quantifier,
+                ..
+            } if quantifier.bounds().0 > 0 => literals(segments),
+            _ => 0,
+        })
+        .sum()
+}
+
+impl MacroDecl {
+    // used to choose between macros with the same priority, more literal tokens are more specific
+    pub fn specificity(&self) -> usize {
+        literals(&self.pattern)
+    }
+
+    // the first token and delimiter of a macro which spans multiple lines
+    pub fn delimiter(&self) -> Option<(&Token, Delimiter)> {
+        let first = match self.pattern.first() {
+            Some(Segment::Token(token)) => token,
+            _ => return None,
+        };
+
+        let end = self.pattern.iter().any(|segment| {
+            matches!(segment, Segment::Token(token) if token.kind == Kind::Keyword(Keyword::End))
+        });
+        let block = self.pattern.iter().any(|segment| {
+            matches!(
+                segment,
+                Segment::Placeholder {
+                    placeholder: Placeholder::Match { variant, .. },
+                    ..
+                } if variant.contains(PlaceholderVariant::BLOCK)
+            )
+        });
+
+        if end {
+            Some((first, Delimiter::End))
+        } else if block {
+            Some((first, Delimiter::Block))
+        } else {
+            None
+        }
+    }
+}
@@ -20,6 +20,27 @@ pub enum GroupQuantifier {
     Repetition(char),
 }
 
+impl GroupQuantifier {
+    // the minimum and maximum number of repetitions, None means unbounded
+    pub fn bounds(&self) -> (usize, Option<usize>) {
+        match self {
+            GroupQuantifier::None => (1, Some(1)),
+            GroupQuantifier::Optional => (0, Some(1)),
+            GroupQuantifier::ZeroOrMore(_) => (0, None),
+            GroupQuantifier::OneOrMore(_) | GroupQuantifier::Repetition(_) => (1, None),
+        }
+    }
+
+    // the character between two repetitions
+    pub fn separator(&self) -> Option<char> {
+        match self {
+            GroupQuantifier::ZeroOrMore(sep) | GroupQuantifier::OneOrMore(sep) => *sep,
+            GroupQuantifier::Repetition(sep) => Some(*sep),
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for GroupQuantifier {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(
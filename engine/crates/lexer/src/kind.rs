@@ -189,7 +189,7 @@ pub enum Kind {
     #[token("@else", | _ | Directive::Else)]
     #[token("@sep", | _ | Directive::Sep)]
     #[token("@(", | _ | Directive::GroupStart)]
-    #[regex(r"@\)(\?|.?[*+]?)?", directive_group_end)]
+    #[regex(r"@\)(\?|[^\s]?[*+]?)?", directive_group_end)]
     #[regex(r"%[0-9]+\.[inpebtco_]+", placeholder)]
     #[regex(r"\$[0-9]+\.[i]+", placeholder)]
     Directive(Directive),
@@ -236,6 +236,14 @@ impl Kind {
     pub fn is_trivia(&self) -> bool {
         matches!(self, Self::Whitespace | Self::Comment)
     }
+
+    // keywords are always case-insensitive, idents only if requested
+    pub fn matches(&self, other: &Kind, case_insensitive: bool) -> bool {
+        match (self, other) {
+            (Self::Ident(a), Self::Ident(b)) if case_insensitive => a.eq_ignore_ascii_case(b),
+            (a, b) => a == b,
+        }
+    }
 }
 
 impl fmt::Display for Kind {
@@ -493,6 +501,14 @@ mod tests {
                 quantifier: GroupQuantifier::Repetition(','),
             }),
         );
+
+        // whitespace is never a separator
+        assert_eq!(
+            crate::Lexer::new("@) END").next().map(|token| token.kind),
+            Some(Kind::Directive(Directive::GroupEnd {
+                quantifier: GroupQuantifier::None,
+            }))
+        );
     }
 
     #[test]
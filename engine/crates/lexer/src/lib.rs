@@ -7,7 +7,7 @@ use logos::{Logos, Span};
 use text_size::{TextRange, TextSize};
 
 pub use crate::comp::Comp;
-pub use crate::dir::{Directive, MacroModifier, Placeholder};
+pub use crate::dir::{Directive, GroupQuantifier, MacroModifier, Placeholder, PlaceholderVariant};
 pub use crate::kind::Kind;
 pub use crate::kw::Keyword;
 pub use crate::op::Op;
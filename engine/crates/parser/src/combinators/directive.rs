@@ -7,10 +7,12 @@ macro_rules! create_directive {
     };
 }
 
-create_directive!(macro, Kind::Directive(Directive::Macro(_)));
+create_directive!(macro, Kind::Directive(Directive::Macro { .. }));
 create_directive!(sub, Kind::Directive(Directive::Sub));
 create_directive!(end, Kind::Directive(Directive::End));
 create_directive!(if, Kind::Directive(Directive::If));
 create_directive!(else, Kind::Directive(Directive::Else));
 create_directive!(sep, Kind::Directive(Directive::Sep));
 create_directive!(placeholder, Kind::Directive(Directive::Placeholder(_)));
+create_directive!(group_start, Kind::Directive(Directive::GroupStart));
+create_directive!(group_end, Kind::Directive(Directive::GroupEnd { .. }));
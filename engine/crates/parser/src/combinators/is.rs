@@ -21,6 +21,7 @@ create_is!(newline, Kind::Newline);
 
 create_is!(ident, Kind::Ident(_));
 create_is!(number, Kind::Number(_));
+create_is!(path, Kind::Path(_));
 
 //region Tests
 #[cfg(test)]
@@ -60,6 +61,7 @@ mod tests {
     fn combine_primitive() {
         check_single_kind("abc", is_ident);
         check_single_kind("123", is_number);
+        check_single_kind("std::macros", is_path);
     }
 }
 //endregion
@@ -14,6 +14,7 @@ create_kw!(decl, Kind::Keyword(Keyword::Decl));
 create_kw!(import, Kind::Keyword(Keyword::Import));
 create_kw!(from, Kind::Keyword(Keyword::From));
 create_kw!(as, Kind::Keyword(Keyword::As));
+create_kw!(macro, Kind::Keyword(Keyword::Macro));
 
 // idk why, but this needs to be done separately
 pub(crate) fn kw_do<Input>() -> impl ::combine::Parser<Input, Output = Token, PartialState = ()>
@@ -26,6 +27,25 @@ where
     ::combine::Parser::expected(::combine::satisfy(f), "do")
 }
 
+// words like pub or const are only keywords at specific positions,
+// everywhere else they can be used as identifiers.
+pub(crate) fn is_soft(token: &Token, word: &str) -> bool {
+    matches!(&token.kind, Kind::Ident(ident) if ident.eq_ignore_ascii_case(word))
+}
+
+pub(crate) fn kw_soft<Input>(
+    word: &'static str,
+) -> impl ::combine::Parser<Input, Output = Token, PartialState = ()>
+where
+    Input: ::combine::Stream<Token = Token>,
+    Input::Error: ::combine::ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    ::combine::Parser::expected(
+        ::combine::satisfy(move |token: Token| is_soft(&token, word)),
+        word,
+    )
+}
+
 //region Tests
 #[cfg(test)]
 mod tests {
@@ -51,6 +71,13 @@ mod tests {
         check_single_kind("import", kw_import);
         check_single_kind("from", kw_from);
         check_single_kind("as", kw_as);
+        check_single_kind("macro", kw_macro);
+    }
+
+    #[test]
+    fn combine_soft() {
+        check_single_kind("PUB", || kw_soft("pub"));
+        check_single_kind("const", || kw_soft("const"));
     }
 }
 //endregion
@@ -0,0 +1,441 @@
+// A lossless concrete syntax tree. Every token of the input is kept, including the trivia which
+// is dropped by the LexerStream, printing the tree therefore reproduces the source exactly.
+//
+// Trivia is attached to the token it belongs to: whitespace and comments on the same line after
+// a token are trailing trivia, everything else is leading trivia of the next token. Lines without
+// any token, like empty lines or comments on their own, are leading trivia as a whole.
+// Trivia after the last line of the file has no token to attach to and is kept separately.
+
+use crate::parsers::unknown::Openers;
+use lexer::{Directive, Keyword, Kind, Lexer, Token};
+use module::mcr::Delimiter;
+use std::fmt;
+use std::iter::Peekable;
+use std::vec::IntoIter;
+use text_size::{TextRange, TextSize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    // the whole file, consisting of statements and the separators between them
+    File,
+    // a single statement, constructs spanning multiple lines are part of the statement
+    Statement,
+    // the statements between the opening token of a construct and its END
+    Block,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Leaf {
+    pub leading: Vec<Token>,
+    pub token: Token,
+    pub trailing: Vec<Token>,
+}
+
+impl Leaf {
+    pub fn span(&self) -> TextRange {
+        let start = self.leading.first().unwrap_or(&self.token).span.start();
+        let end = self.trailing.last().unwrap_or(&self.token).span.end();
+
+        TextRange::new(start, end)
+    }
+
+    pub fn comments(&self) -> impl Iterator<Item = &Token> {
+        self.leading
+            .iter()
+            .chain(self.trailing.iter())
+            .filter(|token| matches!(token.kind, Kind::Comment))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Element {
+    Node(Node),
+    Leaf(Leaf),
+}
+
+impl Element {
+    pub fn span(&self) -> Option<TextRange> {
+        match self {
+            Element::Node(node) => node.span(),
+            Element::Leaf(leaf) => Some(leaf.span()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub children: Vec<Element>,
+}
+
+impl Node {
+    fn new(kind: NodeKind) -> Self {
+        Self {
+            kind,
+            children: vec![],
+        }
+    }
+
+    // the span including all trivia, None if the node is empty
+    pub fn span(&self) -> Option<TextRange> {
+        let mut spans = self.children.iter().filter_map(Element::span);
+
+        let first = spans.next()?;
+        let last = spans.next_back().unwrap_or(first);
+
+        Some(first.cover(last))
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.children.iter().filter_map(|child| match child {
+            Element::Node(node) => Some(node),
+            Element::Leaf(_) => None,
+        })
+    }
+
+    // every leaf of the node in source order
+    pub fn leaves(&self) -> Vec<&Leaf> {
+        let mut leaves = vec![];
+
+        for child in &self.children {
+            match child {
+                Element::Node(node) => leaves.extend(node.leaves()),
+                Element::Leaf(leaf) => leaves.push(leaf),
+            }
+        }
+
+        leaves
+    }
+
+    // the leaf whose span (including trivia) contains the offset
+    pub fn leaf_at(&self, offset: TextSize) -> Option<&Leaf> {
+        self.leaves()
+            .into_iter()
+            .find(|leaf| leaf.span().contains(offset))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cst {
+    pub file: Node,
+    // trivia after the last token
+    pub eof: Vec<Token>,
+}
+
+impl Cst {
+    pub fn parse(input: &str, openers: Openers) -> Self {
+        let (leaves, eof) = attach(Lexer::new(input));
+        let mut builder = Builder {
+            leaves: leaves.into_iter().peekable(),
+            openers,
+        };
+
+        let mut file = Node::new(NodeKind::File);
+        file.children = builder.statements(Scope::Code, false);
+
+        Self { file, eof }
+    }
+}
+
+impl fmt::Display for Leaf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in self
+            .leading
+            .iter()
+            .chain([&self.token])
+            .chain(&self.trailing)
+        {
+            f.write_str(&token.content)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for child in &self.children {
+            match child {
+                Element::Node(node) => node.fmt(f)?,
+                Element::Leaf(leaf) => leaf.fmt(f)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Cst {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.file.fmt(f)?;
+
+        for token in &self.eof {
+            f.write_str(&token.content)?;
+        }
+
+        Ok(())
+    }
+}
+
+// attach the trivia to the surrounding tokens
+fn attach(tokens: impl Iterator<Item = Token>) -> (Vec<Leaf>, Vec<Token>) {
+    let mut leaves: Vec<Leaf> = vec![];
+    let mut pending = vec![];
+
+    for token in tokens {
+        // the newline of a line without any token is trivia as well
+        let empty = matches!(token.kind, Kind::Newline)
+            && leaves
+                .last()
+                .is_none_or(|leaf| matches!(leaf.token.kind, Kind::Newline));
+
+        if !token.kind.is_trivia() && !empty {
+            leaves.push(Leaf {
+                leading: std::mem::take(&mut pending),
+                token,
+                trailing: vec![],
+            });
+
+            continue;
+        }
+
+        match leaves.last_mut() {
+            Some(leaf) if pending.is_empty() && !matches!(leaf.token.kind, Kind::Newline) => {
+                leaf.trailing.push(token)
+            }
+            _ => pending.push(token),
+        }
+    }
+
+    (leaves, pending)
+}
+
+// inside of a macro declaration only the directives are nested
+#[derive(Copy, Clone)]
+enum Scope {
+    Code,
+    Directives,
+}
+
+struct Builder {
+    leaves: Peekable<IntoIter<Leaf>>,
+    openers: Openers,
+}
+
+impl Builder {
+    fn is_sep(leaf: &Leaf) -> bool {
+        matches!(leaf.token.kind, Kind::Newline | Kind::Semicolon)
+    }
+
+    fn closes(scope: Scope, leaf: &Leaf) -> bool {
+        match scope {
+            Scope::Code => matches!(leaf.token.kind, Kind::Keyword(Keyword::End)),
+            Scope::Directives => matches!(leaf.token.kind, Kind::Directive(Directive::End)),
+        }
+    }
+
+    // the scope of the block which is opened by the leaf
+    fn opens(&self, scope: Scope, leaf: &Leaf) -> Option<Scope> {
+        let kind = &leaf.token.kind;
+
+        match scope {
+            Scope::Code if matches!(kind, Kind::Directive(Directive::Macro { .. })) => {
+                Some(Scope::Directives)
+            }
+            Scope::Code
+                if matches!(kind, Kind::Keyword(Keyword::Do | Keyword::Decl))
+                    || self.openers.get(kind) == Some(Delimiter::End) =>
+            {
+                Some(Scope::Code)
+            }
+            Scope::Directives if matches!(kind, Kind::Directive(Directive::If)) => {
+                Some(Scope::Directives)
+            }
+            _ => None,
+        }
+    }
+
+    // statements and separators until the end of the input or the closing token of the scope
+    fn statements(&mut self, scope: Scope, nested: bool) -> Vec<Element> {
+        let mut children = vec![];
+
+        while let Some(leaf) = self.leaves.peek() {
+            if Self::is_sep(leaf) {
+                children.push(Element::Leaf(self.leaves.next().unwrap()));
+            } else if nested && Self::closes(scope, leaf) {
+                break;
+            } else {
+                children.push(Element::Node(self.statement(scope, nested)));
+            }
+        }
+
+        children
+    }
+
+    fn statement(&mut self, scope: Scope, nested: bool) -> Node {
+        let mut statement = Node::new(NodeKind::Statement);
+
+        while let Some(leaf) = self.leaves.peek() {
+            if Self::is_sep(leaf) {
+                break;
+            }
+
+            if Self::closes(scope, leaf) {
+                // an END without an opening construct is a statement on its own
+                if !nested && statement.children.is_empty() {
+                    statement
+                        .children
+                        .push(Element::Leaf(self.leaves.next().unwrap()));
+                }
+
+                break;
+            }
+
+            let leaf = self.leaves.next().unwrap();
+            let inner = self.opens(scope, &leaf);
+            statement.children.push(Element::Leaf(leaf));
+
+            if let Some(inner) = inner {
+                let mut block = Node::new(NodeKind::Block);
+                block.children = self.statements(inner, true);
+                statement.children.push(Element::Node(block));
+
+                // the construct might never be closed
+                if let Some(end) = self.leaves.next_if(|leaf| Self::closes(inner, leaf)) {
+                    statement.children.push(Element::Leaf(end));
+                }
+            }
+        }
+
+        statement
+    }
+}
+
+//region Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    fn cst(input: &str) -> Cst {
+        let openers = Openers::default().with(Kind::Ident("IF".to_string()), Delimiter::End, true);
+
+        Cst::parse(input, openers)
+    }
+
+    fn kinds(node: &Node) -> Vec<NodeKind> {
+        node.nodes().map(|node| node.kind).collect()
+    }
+
+    #[test]
+    fn cst_lossless() {
+        let inputs = [
+            "",
+            "# only a comment",
+            "x := x + 1   # increment\n\n\n",
+            indoc!(
+                "
+                ### a block comment
+                spanning multiple lines ###
+                from std::math import (add,   sub)
+
+                fn f(x) -> y decl
+                    y := x + 1 ; y := y + 1
+                end
+
+                LOOP x DO   # loop
+                    IF y > 0 THEN
+                        y := y - 1
+                    ELSE
+                        y := 0
+                    END
+                END
+                "
+            ),
+            indoc!(
+                "
+                @macro/i/2 swap(%1.i, %2.i)
+                    swap(%1.i, %2.i)
+                @sub
+                    @if %1.i == %2.i
+                        _ := 0
+                    @else
+                        _zero := %1.i
+                    @end
+                @end
+                "
+            ),
+            "LOOP x DO\n  END\n END\n  LOOP y DO\n x := 1 $ ?",
+        ];
+
+        for input in inputs {
+            assert_eq!(cst(input).to_string(), input);
+        }
+    }
+
+    #[test]
+    fn cst_trivia() {
+        let tree = cst(indoc!(
+            "
+            # leading
+            x := 1 # trailing
+              # eof"
+        ));
+
+        let leaves = tree.file.leaves();
+        assert_eq!(leaves[0].token.content, "x");
+        assert_eq!(leaves[0].leading[0].content, "# leading");
+        assert_eq!(leaves[2].token.content, "1");
+        assert_eq!(leaves[2].trailing[1].content, "# trailing");
+        assert_eq!(leaves[3].token.kind, Kind::Newline);
+        assert_eq!(tree.eof[1].content, "# eof");
+
+        assert_eq!(leaves.iter().flat_map(|leaf| leaf.comments()).count(), 2);
+    }
+
+    #[test]
+    fn cst_structure() {
+        let tree = cst(indoc!(
+            "
+            x := 1; y := 2
+            LOOP x DO
+                IF x > 0 THEN
+                    y := y + 1
+                END
+            END
+            END
+            "
+        ));
+
+        assert_eq!(kinds(&tree.file), vec![NodeKind::Statement; 4]);
+
+        let statements: Vec<_> = tree.file.nodes().collect();
+        assert_eq!(statements[3].to_string(), "END");
+
+        let lp = statements[2];
+        assert_eq!(kinds(lp), vec![NodeKind::Block]);
+
+        let block = lp.nodes().next().unwrap();
+        let nested = block.nodes().next().unwrap();
+        assert!(nested.to_string().starts_with("    IF x > 0 THEN"));
+        assert_eq!(kinds(nested), vec![NodeKind::Block]);
+    }
+
+    #[test]
+    fn cst_spans() {
+        let input = "x := 1\n  y := 2 # two\n";
+        let tree = cst(input);
+
+        let statements: Vec<_> = tree.file.nodes().collect();
+        let span = statements[1].span().unwrap();
+        assert_eq!(&input[span], "  y := 2 # two");
+
+        let leaf = tree.file.leaf_at(TextSize::from(11)).unwrap();
+        assert_eq!(leaf.token.content, ":=");
+        assert_eq!(
+            tree.file.span().unwrap(),
+            TextRange::up_to(TextSize::of(input))
+        );
+    }
+}
+//endregion
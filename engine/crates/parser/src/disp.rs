@@ -11,7 +11,7 @@ use variants::LineNo;
 
 static INDENTATION_LEVEL: usize = 2;
 
-pub(crate) trait CompactRepresentation {
+pub trait CompactRepresentation {
     fn compact(&self, indent: Option<usize>) -> String;
 }
 
@@ -24,6 +24,10 @@ impl CompactRepresentation for Hir {
             Hir::Func(f) => f.compact(Some(indent)),
             Hir::Control(c) => c.compact(Some(indent)),
             Hir::Unknown(u) => u.compact(Some(indent)),
+            Hir::Error(tokens) => format!(
+                "Error: {}",
+                tokens.iter().map(|token| token.content.as_str()).join(" ")
+            ),
             Hir::NoOp => "NoOp".to_string(),
         }
     }
@@ -0,0 +1,510 @@
+// Formatting of source files, based on the lossless syntax tree so that no comment is lost.
+// Keywords are written in uppercase, blocks are indented by four spaces and every statement
+// is on its own line. Macro declarations are kept as they are, as their patterns and
+// substitutions are matched token by token and do not follow the layout of regular code.
+// For the same reason no space is inserted next to an operator inside of a macro invocation,
+// x++ would otherwise become x + +.
+
+use crate::cst::{Cst, Element, Leaf, Node};
+use ctrl::Control;
+use hir::Hir;
+use lexer::{Directive, Keyword, Kind, Pair, Token};
+use mcr::Unknown;
+use module::Module;
+use std::collections::HashSet;
+use text_size::TextSize;
+
+const INDENT: &str = "    ";
+
+// case-insensitive words of the language which are not lexed as keywords
+const SOFT: [&str; 5] = ["if", "then", "else", "pub", "const"];
+
+fn is_soft(word: &str) -> bool {
+    SOFT.iter().any(|soft| word.eq_ignore_ascii_case(soft))
+}
+
+fn text(token: &Token) -> String {
+    match &token.kind {
+        Kind::Keyword(_) => token.content.to_uppercase(),
+        Kind::Ident(ident) if is_soft(ident) => ident.to_uppercase(),
+        _ => token.content.clone(),
+    }
+}
+
+fn spaced(prev: Option<&Kind>, next: Option<&Kind>) -> bool {
+    match (prev, next) {
+        (Some(Kind::Paren(Pair::Left)), _) => false,
+        (_, Some(Kind::Paren(Pair::Right) | Kind::Comma)) => false,
+        // calls and the parameters of function declarations
+        (Some(Kind::Ident(ident)), Some(Kind::Paren(Pair::Left))) => is_soft(ident),
+        (Some(Kind::Path(_)), Some(Kind::Paren(Pair::Left))) => false,
+        _ => true,
+    }
+}
+
+// the operator was written next to the previous token of the same macro invocation
+fn glued(invocations: &HashSet<TextSize>, prev: &Token, next: &Token) -> bool {
+    invocations.contains(&prev.span.start())
+        && invocations.contains(&next.span.start())
+        && prev.span.end() == next.span.start()
+        && (matches!(prev.kind, Kind::Op(_)) || matches!(next.kind, Kind::Op(_)))
+}
+
+// the start of every token which is part of a macro invocation
+fn collect(hir: &Hir, invocations: &mut HashSet<TextSize>) {
+    match hir {
+        Hir::Unknown(Unknown::Token(token)) => {
+            invocations.insert(token.span.start());
+        }
+        Hir::Unknown(Unknown::Tokens(tokens)) => {
+            invocations.extend(tokens.iter().map(|token| token.span.start()));
+        }
+        Hir::Control(Control::Block { terms }) => {
+            for term in terms {
+                collect(term, invocations);
+            }
+        }
+        Hir::Control(Control::Loop { terms, .. } | Control::While { terms, .. }) => {
+            collect(terms, invocations)
+        }
+        _ => {}
+    }
+}
+
+pub(crate) fn invocations(module: &Module) -> HashSet<TextSize> {
+    let mut invocations = HashSet::new();
+
+    collect(&module.code, &mut invocations);
+    for func in &module.decl.func {
+        collect(&func.terms, &mut invocations);
+    }
+
+    invocations
+}
+
+fn first(node: &Node) -> Option<&Leaf> {
+    node.leaves().into_iter().next()
+}
+
+fn is_word(leaf: &Leaf, word: &str) -> bool {
+    matches!(&leaf.token.kind, Kind::Ident(ident) if ident.eq_ignore_ascii_case(word))
+}
+
+// the statement continues the construct of the enclosing block, like ELSE
+fn is_continuation(node: &Node) -> bool {
+    first(node).is_some_and(|leaf| is_word(leaf, "else"))
+}
+
+fn is_macro(node: &Node) -> bool {
+    matches!(first(node), Some(leaf) if matches!(leaf.token.kind, Kind::Directive(Directive::Macro { .. })))
+}
+
+#[derive(Default)]
+struct Writer {
+    lines: Vec<String>,
+    line: String,
+    depth: usize,
+    invocations: HashSet<TextSize>,
+
+    // the previous token on the current line
+    prev: Option<Token>,
+    // a line comment has been written, nothing may follow on the same line
+    closed: bool,
+    // no empty line is kept at the start of the file or a block
+    fresh: bool,
+    // the next token, like ELSE, is written on the level of the enclosing block
+    dedent: bool,
+}
+
+impl Writer {
+    fn newline(&mut self) {
+        if !self.line.is_empty() {
+            self.lines.push(std::mem::take(&mut self.line));
+        }
+
+        self.prev = None;
+        self.closed = false;
+    }
+
+    fn blank(&mut self) {
+        self.newline();
+
+        if !self.fresh && self.lines.last().is_some_and(|line| !line.is_empty()) {
+            self.lines.push(String::new());
+        }
+    }
+
+    fn write(&mut self, text: &str, token: Option<&Token>) {
+        if self.closed {
+            self.newline();
+        }
+
+        let prev = self.prev.as_ref();
+        let glued = matches!((prev, token), (Some(prev), Some(next)) if glued(&self.invocations, prev, next));
+
+        if self.line.is_empty() {
+            self.line
+                .push_str(&INDENT.repeat(self.depth - self.dedent as usize));
+        } else if !glued && spaced(prev.map(|prev| &prev.kind), token.map(|next| &next.kind)) {
+            self.line.push(' ');
+        }
+
+        self.line.push_str(text);
+        self.prev = token.cloned();
+        self.fresh = false;
+    }
+
+    fn trivia(&mut self, tokens: &[Token], blanks: bool) {
+        for token in tokens {
+            match token.kind {
+                Kind::Comment => {
+                    self.write(token.content.trim_end(), None);
+
+                    let block = token.content.len() >= 6 && token.content.ends_with("###");
+                    self.closed = !block;
+                }
+                // the newline of a line without any token
+                Kind::Newline if self.line.is_empty() && blanks => self.blank(),
+                Kind::Newline => self.newline(),
+                _ => {}
+            }
+        }
+    }
+
+    fn leaf(&mut self, leaf: &Leaf) {
+        self.trivia(&leaf.leading, true);
+        self.write(&text(&leaf.token), Some(&leaf.token));
+        self.dedent = false;
+        self.trivia(&leaf.trailing, false);
+    }
+
+    fn separator(&mut self, leaf: &Leaf) {
+        self.trivia(&leaf.leading, true);
+        self.trivia(&leaf.trailing, false);
+        self.newline();
+    }
+
+    fn statements(&mut self, children: &[Element]) {
+        for child in children {
+            match child {
+                Element::Node(statement) => self.statement(statement),
+                Element::Leaf(leaf) => self.separator(leaf),
+            }
+        }
+    }
+
+    fn statement(&mut self, statement: &Node) {
+        if is_macro(statement) {
+            return self.verbatim(statement);
+        }
+
+        let mut children = statement.children.iter().peekable();
+        let mut opener = None;
+
+        while let Some(child) = children.next() {
+            match child {
+                Element::Leaf(leaf) => {
+                    if is_word(leaf, "else") && opener.is_some() {
+                        self.newline();
+                        self.dedent = true;
+                    }
+
+                    self.leaf(leaf);
+                    opener = Some(leaf);
+
+                    // the terms of a conditional always start on a new line
+                    if (is_word(leaf, "then") || is_word(leaf, "else")) && children.peek().is_some()
+                    {
+                        self.newline();
+                    }
+                }
+                Element::Node(block) => {
+                    let end = match children.peek() {
+                        Some(Element::Leaf(end)) => {
+                            children.next();
+                            Some(end)
+                        }
+                        _ => None,
+                    };
+
+                    self.block(block, opener, end);
+                }
+            }
+        }
+    }
+
+    fn block(&mut self, block: &Node, opener: Option<&Leaf>, end: Option<&Leaf>) {
+        // the statement before the first separator of DO and DECL is part of the body,
+        // while for the constructs of macros it is part of the head, like the condition of IF
+        let head = !matches!(
+            opener.map(|leaf| &leaf.token.kind),
+            Some(Kind::Keyword(Keyword::Do | Keyword::Decl))
+        );
+
+        self.depth += 1;
+        if !head {
+            self.newline();
+        }
+        self.fresh = true;
+
+        for (index, child) in block.children.iter().enumerate() {
+            match child {
+                Element::Node(statement) if index > 0 && is_continuation(statement) => {
+                    self.dedent = true;
+                    self.statement(statement);
+                    self.fresh = true;
+                }
+                Element::Node(statement) => self.statement(statement),
+                Element::Leaf(leaf) => self.separator(leaf),
+            }
+        }
+
+        if let Some(end) = end {
+            self.trivia(&end.leading, true);
+            self.depth -= 1;
+            self.newline();
+
+            // no empty line is kept at the end of a block
+            while self.lines.last().is_some_and(|line| line.is_empty()) {
+                self.lines.pop();
+            }
+
+            self.write(&text(&end.token), Some(&end.token));
+            self.trivia(&end.trailing, false);
+        } else {
+            self.depth -= 1;
+        }
+    }
+
+    fn verbatim(&mut self, statement: &Node) {
+        let leaves = statement.leaves();
+        self.trivia(&leaves[0].leading, true);
+
+        let mut source = leaves[0].token.content.clone();
+        for token in leaves[0].trailing.iter() {
+            source.push_str(&token.content);
+        }
+        for leaf in &leaves[1..] {
+            source.push_str(&leaf.to_string());
+        }
+
+        self.newline();
+        for line in source.lines() {
+            self.lines.push(line.trim_end().to_string());
+        }
+
+        self.fresh = false;
+    }
+
+    fn finish(mut self) -> String {
+        self.newline();
+
+        while self.lines.last().is_some_and(|line| line.is_empty()) {
+            self.lines.pop();
+        }
+
+        if self.lines.is_empty() {
+            return String::new();
+        }
+
+        self.lines.join("\n") + "\n"
+    }
+}
+
+pub(crate) fn format(cst: &Cst, invocations: HashSet<TextSize>) -> String {
+    let mut writer = Writer {
+        fresh: true,
+        invocations,
+        ..Writer::default()
+    };
+
+    writer.statements(&cst.file.children);
+    writer.trivia(&cst.eof, true);
+
+    writer.finish()
+}
+
+//region Tests
+#[cfg(test)]
+mod tests {
+    use crate::parsers::unknown::Openers;
+    use indoc::indoc;
+    use lexer::Kind;
+    use module::mcr::Delimiter;
+
+    fn openers() -> Openers {
+        Openers::default().with(Kind::Ident("IF".to_string()), Delimiter::End, true)
+    }
+
+    fn fmt(input: &str) -> String {
+        let formatted = crate::fmt(input, openers()).unwrap();
+
+        // formatting is idempotent
+        assert_eq!(crate::fmt(&formatted, openers()).unwrap(), formatted);
+
+        formatted
+    }
+
+    #[test]
+    fn format_code() {
+        assert_eq!(
+            fmt(indoc!(
+                "
+
+
+                x:=y+1;y := x
+                  loop x Do
+                      while y != 0 do y := y - 1 END
+                        x := x+1 ; z := 2
+                        end
+
+
+                "
+            )),
+            indoc!(
+                "
+                x := y + 1
+                y := x
+                LOOP x DO
+                    WHILE y != 0 DO
+                        y := y - 1
+                    END
+                    x := x + 1
+                    z := 2
+                END
+                "
+            )
+        );
+    }
+
+    #[test]
+    fn format_comments() {
+        assert_eq!(
+            fmt(indoc!(
+                "
+                ### header
+                    of the file ###
+                # first
+                x := 1   # one
+
+
+                    # loop
+                LOOP x DO # body
+                # inner
+                  y := 1 ;  # after
+
+                  # last
+                END
+                # eof
+                "
+            )),
+            indoc!(
+                "
+                ### header
+                    of the file ###
+                # first
+                x := 1 # one
+
+                # loop
+                LOOP x DO # body
+                    # inner
+                    y := 1 # after
+
+                    # last
+                END
+                # eof
+                "
+            )
+        );
+    }
+
+    #[test]
+    fn format_declarations() {
+        assert_eq!(
+            fmt(indoc!(
+                "
+                pub from std::math import (max,min)
+                import std::math as m
+                const c = 2
+
+                @macro   double(%1.i)
+                  double ( %1.i )
+                @sub
+                  %1.i := %1.i * 2
+                @end
+
+                fn f ( a , b ) -> c decl
+                    if a >= b then
+                            c := max(a, b)
+                    else
+                        c := m::min(a,b)
+                    end
+                end
+                "
+            )),
+            indoc!(
+                "
+                PUB FROM std::math IMPORT (max, min)
+                IMPORT std::math AS m
+                CONST c = 2
+
+                @macro   double(%1.i)
+                  double ( %1.i )
+                @sub
+                  %1.i := %1.i * 2
+                @end
+
+                FN f(a, b) -> c DECL
+                    IF a >= b THEN
+                        c := max(a, b)
+                    ELSE
+                        c := m::min(a, b)
+                    END
+                END
+                "
+            )
+        );
+    }
+
+    #[test]
+    fn format_single_line() {
+        assert_eq!(
+            fmt("if x > 0 then y := 1 else # none\n y := 2 end"),
+            indoc!(
+                "
+                IF x > 0 THEN
+                    y := 1
+                ELSE # none
+                    y := 2
+                END
+                "
+            )
+        );
+    }
+
+    #[test]
+    fn format_invocations() {
+        assert_eq!(
+            fmt("x++;y:=y*2\nx := x+1\nz  +=  x\nif x>0 then x-- end"),
+            indoc!(
+                "
+                x++
+                y := y*2
+                x := x + 1
+                z += x
+                IF x > 0 THEN
+                    x--
+                END
+                "
+            )
+        );
+    }
+
+    #[test]
+    fn format_invalid() {
+        assert!(crate::fmt("LOOP x DO\n", openers()).is_err());
+        assert_eq!(fmt(""), "");
+        assert_eq!(fmt("\n\n# only\n\n"), "# only\n");
+    }
+}
+//endregion
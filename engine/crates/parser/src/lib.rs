@@ -2,15 +2,123 @@
 extern crate combine;
 
 use combine::parser::char::{letter, spaces};
-use combine::{many1, ParseError, Parser, Stream};
+use combine::{eof, many1, ParseError, Parser, Stream};
+use hir::Hir;
 use lexer::Token;
+use module::{Decl, Module};
+use variants::Errors;
+
+use crate::cst::Cst;
+use crate::parsers::file;
+use crate::parsers::mcr::library;
+use crate::parsers::terms::terms;
+use crate::stream::LexerStream;
 
 mod combinators;
+pub mod cst;
+pub mod disp;
+mod format;
 mod parsers;
+mod recover;
 mod skip;
 pub(crate) mod stream;
 mod utils;
-mod disp;
+
+pub use crate::parsers::unknown::Openers;
+
+// parse a file only consisting of imports and macro declarations
+pub fn parse_library(input: &str) -> Result<Module, Errors> {
+    let stream = LexerStream::new(input);
+    let tokens = stream.tokens.clone();
+
+    library()
+        .skip(eof())
+        .parse(stream)
+        .map(|((imp, macr), _)| Module {
+            imp,
+            decl: Decl {
+                macr,
+                ..Decl::default()
+            },
+            code: Hir::NoOp,
+        })
+        .map_err(|err| utils::to_errors(err, &tokens))
+}
+
+// parse the imports and macro declarations at the start of a file, the remaining tokens are
+// returned as they can only be parsed once the openers of every visible macro are known.
+pub fn parse_header(input: &str) -> Result<(Module, Vec<Token>), Errors> {
+    let stream = LexerStream::new(input);
+    let tokens = stream.tokens.clone();
+
+    library()
+        .parse(stream)
+        .map(|((imp, macr), stream)| {
+            let module = Module {
+                imp,
+                decl: Decl {
+                    macr,
+                    ..Decl::default()
+                },
+                code: Hir::NoOp,
+            };
+
+            (module, stream.remaining())
+        })
+        .map_err(|err| utils::to_errors(err, &tokens))
+}
+
+// parse a whole file: imports, constants, macros, function declarations and the code,
+// the openers of imported macros need to be given, as imports are not resolved.
+pub fn parse_file(input: &str, openers: Openers) -> Result<Module, Errors> {
+    let stream = LexerStream::new(input);
+    let tokens = stream.tokens.clone();
+
+    file(openers)
+        .skip(eof())
+        .parse(stream)
+        .map(|(module, _)| module)
+        .map_err(|err| utils::to_errors(err, &tokens))
+}
+
+// parse a whole file like parse_file, but recover from syntax errors at statement separators
+// and END keywords, broken regions are kept as Hir::Error and every error is reported.
+pub fn parse_partial(input: &str, openers: Openers) -> (Module, Errors) {
+    let stream = LexerStream::new(input);
+
+    recover::recover(&stream.tokens, openers)
+}
+
+// build the lossless syntax tree of a file, which keeps every token including the trivia
+pub fn parse_cst(input: &str, openers: Openers) -> Cst {
+    Cst::parse(input, openers)
+}
+
+// format a file, files with syntax errors are not formatted
+pub fn fmt(input: &str, openers: Openers) -> Result<String, Errors> {
+    let (module, errors) = parse_partial(input, openers.clone());
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let openers = openers.merge(&Openers::from(&module.decl.macr[..]));
+    Ok(format::format(
+        &Cst::parse(input, openers),
+        format::invocations(&module),
+    ))
+}
+
+// parse the terms of an already tokenized input, used for the result of macro expansions
+pub fn parse_terms(tokens: Vec<Token>, openers: Openers) -> Result<Hir, Errors> {
+    let stream = LexerStream::from(tokens);
+    let tokens = stream.tokens.clone();
+
+    terms(true, openers)
+        .skip(eof())
+        .parse(stream)
+        .map(|(hir, _)| hir)
+        .map_err(|err| utils::to_errors(err, &tokens))
+}
 
 // fn parse(input: &str) -> Parser {
 //     todo!()
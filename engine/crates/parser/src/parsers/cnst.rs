@@ -0,0 +1,95 @@
+// This module is called cnst instead of const because const is a reserved keyword
+
+use crate::combinators::is::{is_assign, is_ident, is_number};
+use crate::combinators::kw::kw_soft;
+use crate::combinators::trivia::sep;
+use crate::utils::{to_ident, to_uint};
+use combine::error::Info::Format;
+use combine::parser::combinator::no_partial;
+use combine::{attempt, optional, sep_end_by, unexpected_any, value, Parser, Stream};
+use lexer::Token;
+use module::decl::ConstDecl;
+
+// parse: [PUB] CONST <ident> = <uint>
+// return: ConstDecl
+pub(crate) fn cnst<Input>() -> impl Parser<Input, Output = ConstDecl, PartialState = ()>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    let combinator = (
+        optional(kw_soft("pub")),
+        kw_soft("const"),
+        is_ident(),
+        is_assign(),
+        is_number(),
+    )
+        .then(|(public, keyword, ident, _, number)| {
+            let start = public.clone().unwrap_or(keyword);
+
+            let ident = to_ident(ident);
+            let number_ = to_uint(number.clone());
+
+            collect!(exc | ident, number_);
+
+            if !exc.is_empty() {
+                return unexpected_any(Format(exc)).right();
+            }
+
+            value(ConstDecl {
+                lno: start.lno.end_at(&number.lno),
+                public: public.is_some(),
+
+                ident: ident.unwrap(),
+                value: number_.unwrap(),
+            })
+            .left()
+        });
+
+    no_partial(combinator)
+}
+
+// parse every constant, which come after the imports
+pub(crate) fn cnsts<Input>() -> impl Parser<Input, Output = Vec<ConstDecl>, PartialState = ()>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    no_partial((sep(), sep_end_by(attempt(cnst()), sep())).map(|(_, decls): ((), Vec<_>)| decls))
+}
+
+//region Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expr::Primitive;
+
+    #[test]
+    fn parser_cnst() {
+        let stream = crate::stream::LexerStream::new("PUB CONST x = 5");
+        let parsed = cnst().parse(stream);
+
+        assert!(parsed.is_ok());
+
+        let (decl, stream) = parsed.unwrap();
+
+        assert!(stream.is_exhausted());
+        assert!(decl.public);
+        assert!(matches!(decl.ident, Primitive::Ident { ref value, .. } if value == "x"));
+    }
+
+    #[test]
+    fn parser_cnsts() {
+        let stream = crate::stream::LexerStream::new("const a = 1\nconst b = 2\n");
+        let parsed = cnsts().parse(stream);
+
+        assert!(parsed.is_ok());
+
+        let (decls, stream) = parsed.unwrap();
+
+        assert!(stream.is_exhausted());
+        assert_eq!(decls.len(), 2);
+        assert!(decls.iter().all(|decl| !decl.public));
+    }
+}
+//endregion
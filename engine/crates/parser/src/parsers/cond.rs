@@ -0,0 +1,186 @@
+use crate::combinators::is::{is_lparen, is_rparen};
+use crate::combinators::kw::{is_soft, kw_soft};
+use crate::parsers::mcr::to_segment;
+use combine::parser::combinator::no_partial;
+use combine::{chainl1, many, optional, satisfy, Parser, Stream};
+use lexer::{Comp, Directive, Kind, Token};
+use module::mcr::{Class, Condition, Segment};
+
+// words with a meaning in conditions, which therefore cannot be used as an operand
+const RESERVED: [&str; 4] = ["is", "not", "and", "or"];
+
+fn operand<Input>() -> impl Parser<Input, Output = Segment>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    satisfy(|token: Token| match &token.kind {
+        Kind::Directive(Directive::Placeholder(_)) | Kind::Number(_) | Kind::Op(_) => true,
+        Kind::Ident(_) => !RESERVED.iter().any(|word| is_soft(&token, word)),
+        _ => false,
+    })
+    .map(to_segment)
+    .expected("operand")
+}
+
+fn comp<Input>() -> impl Parser<Input, Output = Comp>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    satisfy(|token: Token| matches!(token.kind, Kind::Comp(_)))
+        .map(|token: Token| match token.kind {
+            Kind::Comp(comp) => comp,
+            _ => unreachable!(),
+        })
+        .expected("comparison")
+}
+
+fn class<Input>() -> impl Parser<Input, Output = Class>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    kw_soft("value")
+        .map(|_| Class::Value)
+        .or(kw_soft("ident").map(|_| Class::Ident))
+}
+
+enum Test {
+    Is { negated: bool, class: Class },
+    Comp { verb: Comp, rhs: Segment },
+}
+
+// parse: <operand> IS [NOT] (VALUE | IDENT) | <operand> <comp> <operand> | (<condition>)
+fn atom<Input>() -> impl Parser<Input, Output = Condition>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    let is = (kw_soft("is"), optional(kw_soft("not")), class()).map(|(_, not, class)| Test::Is {
+        negated: not.is_some(),
+        class,
+    });
+    let cmp = (comp(), operand()).map(|(verb, rhs)| Test::Comp { verb, rhs });
+
+    let test = (operand(), is.or(cmp)).map(|(lhs, test)| match test {
+        Test::Is { negated, class } => {
+            let cond = Condition::Is {
+                operand: Box::new(lhs),
+                class,
+            };
+
+            if negated {
+                Condition::Not(Box::new(cond))
+            } else {
+                cond
+            }
+        }
+        Test::Comp { verb, rhs } => Condition::Comp {
+            lhs: Box::new(lhs),
+            verb,
+            rhs: Box::new(rhs),
+        },
+    });
+
+    (is_lparen(), condition(), is_rparen())
+        .map(|(_, cond, _)| cond)
+        .or(test)
+}
+
+// parse: NOT* <atom>
+fn unary<Input>() -> impl Parser<Input, Output = Condition>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    (many::<Vec<_>, _, _>(kw_soft("not")), atom()).map(|(nots, cond)| {
+        nots.iter()
+            .fold(cond, |cond, _| Condition::Not(Box::new(cond)))
+    })
+}
+
+fn condition_<Input>() -> impl Parser<Input, Output = Condition, PartialState = ()>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    let and = chainl1(
+        unary(),
+        kw_soft("and").map(|_| |lhs, rhs| Condition::And(Box::new(lhs), Box::new(rhs))),
+    );
+    let or = chainl1(
+        and,
+        kw_soft("or").map(|_| |lhs, rhs| Condition::Or(Box::new(lhs), Box::new(rhs))),
+    );
+
+    no_partial(or)
+}
+
+parser! {
+    // parse the compile-time expression of @if, AND binds stronger than OR
+    pub(crate) fn condition[Input]()(Input) -> Condition
+    where [Input: Stream<Token = Token>]
+    {
+        condition_()
+    }
+}
+
+//region Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Condition {
+        let stream = crate::stream::LexerStream::new(input);
+        let (cond, stream) = condition().parse(stream).unwrap();
+        assert!(stream.is_exhausted());
+
+        cond
+    }
+
+    #[test]
+    fn parser_cond_comp() {
+        let cond = parse("%2.n == 0");
+
+        assert!(matches!(
+            cond,
+            Condition::Comp {
+                verb: Comp::Equal,
+                ..
+            }
+        ));
+        assert_eq!(cond.operands().len(), 2);
+    }
+
+    #[test]
+    fn parser_cond_is() {
+        assert!(matches!(
+            parse("%2.p is value"),
+            Condition::Is {
+                class: Class::Value,
+                ..
+            }
+        ));
+        assert!(matches!(parse("%2.p is not ident"), Condition::Not(_)));
+    }
+
+    #[test]
+    fn parser_cond_precedence() {
+        let cond = parse("%1.p is value or not %2.p is value and %1.p < %2.p");
+
+        match cond {
+            Condition::Or(lhs, rhs) => {
+                assert!(matches!(*lhs, Condition::Is { .. }));
+                assert!(matches!(*rhs, Condition::And(..)));
+            }
+            _ => panic!("expected or"),
+        }
+
+        assert!(matches!(
+            parse("(%1.p is value or %2.p is value) and %1.p < %2.p"),
+            Condition::And(..)
+        ));
+    }
+}
+//endregion
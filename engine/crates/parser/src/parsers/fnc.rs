@@ -1,19 +1,25 @@
-use crate::combinators::is::{is_assign, is_comma, is_ident, is_lparen, is_number, is_rparen};
-use crate::utils::{to_ident, to_uint};
+use crate::combinators::is::{
+    is_assign, is_comma, is_ident, is_into, is_lparen, is_number, is_path, is_rparen,
+};
+use crate::combinators::kw::{kw_decl, kw_end, kw_fn, kw_soft};
+use crate::combinators::trivia::sep;
+use crate::parsers::terms::terms;
+use crate::parsers::unknown::Openers;
+use crate::utils::{to_ident, to_path, to_uint};
 use combine::error::Info::Format;
 use combine::parser::combinator::no_partial;
-use combine::{sep_by, unexpected_any, value, Parser, Stream};
+use combine::{
+    attempt, optional, sep_by, sep_by1, sep_end_by, unexpected_any, value, Parser, Stream,
+};
 
 use fnc::{BoundCall, Call, Func};
 use hir::Hir;
 use itertools::{Either, Itertools};
 use lexer::Token;
+use module::decl::FuncDecl;
 
-// TODO
-// decl and calling functions
-
-// parser for function calling statements
-// parse lhs := ident(args, )
+// parser for function calling statements, functions of namespaced imports are called by path
+// parse lhs := (ident | path)(args, )
 pub(crate) fn fnc_call<Input>() -> impl Parser<Input, Output = Hir, PartialState = ()>
 where
     Input: Stream<Token = Token>,
@@ -22,7 +28,7 @@ where
     let combinator = (
         is_ident(),
         is_assign(),
-        is_ident(),
+        is_path().or(is_ident()),
         is_lparen(),
         sep_by::<Vec<_>, _, _, _>(
             choice!(
@@ -39,7 +45,7 @@ where
 
             // convert into primitives and correctly collect all the errors
             let lhs = to_ident(lhs);
-            let ident = to_ident(ident);
+            let ident = to_path(ident);
             let (arg_errors, args): (Vec<_>, Vec<_>) =
                 args.into_iter().partition_map(|value| match value {
                     Ok(v) => Either::Right(v),
@@ -75,6 +81,83 @@ where
     no_partial(combinator)
 }
 
+// parse: [PUB] FN <ident>(<ident>, ...) -> <ident> DECL <terms> END
+// return: FuncDecl
+pub(crate) fn fnc_decl<Input>(
+    openers: Openers,
+) -> impl Parser<Input, Output = FuncDecl, PartialState = ()>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    let combinator = (
+        optional(kw_soft("pub")),
+        kw_fn(),
+        is_ident(),
+        is_lparen(),
+        sep_by1::<Vec<_>, _, _, _>(is_ident(), is_comma()),
+        is_rparen(),
+        is_into(),
+        is_ident(),
+        (kw_decl(), sep()),
+        terms(true, openers),
+        kw_end(),
+    )
+        .then(
+            |(public, keyword, ident, _, params, _, _, ret, _, terms, end)| {
+                let start = public.clone().unwrap_or(keyword);
+
+                let ident = to_ident(ident);
+                let ret = to_ident(ret);
+                let (param_errors, params): (Vec<_>, Vec<_>) = params
+                    .into_iter()
+                    .map(to_ident)
+                    .partition_map(|value| match value {
+                        Ok(v) => Either::Right(v),
+                        Err(v) => Either::Left(v),
+                    });
+
+                collect!(exc | ident, ret);
+
+                if let Some(errors) = param_errors.into_iter().reduce(|a, b| a + b) {
+                    exc += errors;
+                }
+
+                if !exc.is_empty() {
+                    return unexpected_any(Format(exc)).right();
+                }
+
+                value(FuncDecl {
+                    lno: start.lno.end_at(&end.lno),
+                    public: public.is_some(),
+
+                    ident: ident.unwrap(),
+                    params,
+                    ret: ret.unwrap(),
+
+                    terms: Box::new(terms),
+                })
+                .left()
+            },
+        );
+
+    no_partial(combinator)
+}
+
+// parse every function declaration, which come after the imports and constants
+pub(crate) fn fnc_decls<Input>(
+    openers: Openers,
+) -> impl Parser<Input, Output = Vec<FuncDecl>, PartialState = ()>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    no_partial(
+        (sep(), sep_end_by(attempt(fnc_decl(openers)), sep()))
+            .map(|(_, decls): ((), Vec<_>)| decls),
+    )
+}
+
 //region Tests
 #[cfg(test)]
 mod tests {
@@ -139,6 +222,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parser_fnc_call_path() {
+        let stream = crate::stream::LexerStream::new("x := m::max(a, 2)");
+        let parsed = fnc_call().parse(stream);
+
+        assert!(parsed.is_ok());
+
+        let (hir, stream) = parsed.unwrap();
+
+        assert!(stream.is_exhausted());
+        assert_eq!("FncCall@[0:0->0:17]: x := m::max(a, 2)", hir.compact(None));
+    }
+
+    #[test]
+    fn parser_fnc_decl() {
+        let stream = crate::stream::LexerStream::new(indoc::indoc!(
+            "
+            PUB FN add(a, b) -> c DECL
+                c := a + 0
+                LOOP b DO
+                    c := c + 1
+                END
+            END"
+        ));
+        let parsed = fnc_decl(Openers::default()).parse(stream);
+
+        assert!(parsed.is_ok());
+
+        let (decl, stream) = parsed.unwrap();
+
+        assert!(stream.is_exhausted());
+        assert!(decl.public);
+        assert_eq!(decl.params.len(), 2);
+        assert_eq!(u32::from(decl.lno.row.end()), 5);
+    }
+
     #[test]
     fn parser_fnc_call_primitive_args() {
         let stream = crate::stream::LexerStream::new("x := y(1, b, 3, d, 5)");
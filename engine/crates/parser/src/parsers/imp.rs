@@ -0,0 +1,245 @@
+use crate::combinators::is::{is_comma, is_ident, is_lparen, is_path, is_rparen};
+use crate::combinators::kw::{kw_as, kw_from, kw_import, kw_macro, kw_soft};
+use crate::combinators::op::op_star;
+use crate::combinators::trivia::sep;
+use combine::parser::combinator::no_partial;
+use combine::{attempt, optional, sep_by1, sep_end_by, Parser, Stream};
+use either::Either;
+use lexer::{Kind, Token};
+use module::imp::{Alias, Namespace, Stmt, Wildcard};
+use module::Imp;
+
+fn content(token: &Token) -> String {
+    match &token.kind {
+        Kind::Ident(ident) => ident.clone(),
+        _ => token.content.clone(),
+    }
+}
+
+fn alias<Input>() -> impl Parser<Input, Output = (Alias, Token)>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    (is_ident(), optional(attempt((kw_as(), is_ident())))).map(|(ident, alias)| {
+        let end = alias.clone().map_or(ident.clone(), |(_, alias)| alias);
+
+        (
+            Alias {
+                ident: content(&ident),
+                alias: alias.map(|(_, alias)| content(&alias)),
+            },
+            end,
+        )
+    })
+}
+
+fn path(token: Token) -> Vec<String> {
+    match token.kind {
+        Kind::Path(path) => path,
+        _ => vec![content(&token)],
+    }
+}
+
+fn aliases<Input>() -> impl Parser<Input, Output = (Vec<Alias>, Token)>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    sep_by1::<Vec<_>, _, _, _>(alias(), is_comma()).map(|aliases| {
+        let end = aliases.last().map(|(_, end)| end.clone()).unwrap();
+
+        (aliases.into_iter().map(|(alias, _)| alias).collect(), end)
+    })
+}
+
+// parse: [PUB] FROM <path> [MACRO] IMPORT (* | <ident> [AS <ident>], ... | (<ident> [AS <ident>], ...))
+// return: if it is a macro import and the statement
+pub(crate) fn imp<Input>() -> impl Parser<Input, Output = (bool, Stmt), PartialState = ()>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    let combinator = (
+        optional(kw_soft("pub")),
+        kw_from(),
+        is_path().or(is_ident()),
+        optional(kw_macro()),
+        kw_import(),
+        choice!(
+            op_star().map(|token| (Either::Right(Wildcard {}), token)),
+            (is_lparen(), aliases(), is_rparen())
+                .map(|(_, (aliases, _), end)| (Either::Left(aliases), end)),
+            aliases().map(|(aliases, end)| (Either::Left(aliases), end))
+        ),
+    )
+        .map(|(public, from, module, macr, _, (imports, end))| {
+            let start = public.clone().unwrap_or(from);
+
+            (
+                macr.is_some(),
+                Stmt {
+                    lno: start.lno.end_at(&end.lno),
+                    public: public.is_some(),
+
+                    path: path(module),
+                    imports,
+                },
+            )
+        });
+
+    no_partial(combinator)
+}
+
+// parse: IMPORT <path> [AS <ident>]
+// return: the namespaced import
+pub(crate) fn namespace<Input>() -> impl Parser<Input, Output = Namespace, PartialState = ()>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    let combinator = (
+        kw_import(),
+        is_path().or(is_ident()),
+        optional(attempt((kw_as(), is_ident()))),
+    )
+        .map(|(start, module, alias)| {
+            let end = alias.clone().map_or(module.clone(), |(_, alias)| alias);
+
+            Namespace {
+                lno: start.lno.end_at(&end.lno),
+
+                path: path(module),
+                alias: alias.map(|(_, alias)| content(&alias)),
+            }
+        });
+
+    no_partial(combinator)
+}
+
+// parse every import statement at the start of a file
+pub(crate) fn imports<Input>() -> impl Parser<Input, Output = Imp, PartialState = ()>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    let combinator = (
+        sep(),
+        sep_end_by::<Vec<_>, _, _, _>(
+            attempt(imp())
+                .map(Either::Left)
+                .or(namespace().map(Either::Right)),
+            sep(),
+        ),
+    )
+        .map(|(_, stmts)| {
+            let mut imports = Imp::default();
+
+            for stmt in stmts {
+                match stmt {
+                    Either::Left((true, stmt)) => imports.macr.push(stmt),
+                    Either::Left((false, stmt)) => imports.func.push(stmt),
+                    Either::Right(namespace) => imports.namespace.push(namespace),
+                }
+            }
+
+            imports
+        });
+
+    no_partial(combinator)
+}
+
+//region Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parser_imp_macro_wildcard() {
+        let stream = crate::stream::LexerStream::new("from std::macros::assign macro import *");
+        let parsed = imp().parse(stream);
+
+        assert!(parsed.is_ok());
+
+        let ((macr, stmt), stream) = parsed.unwrap();
+        assert!(stream.is_exhausted());
+
+        assert!(macr);
+        assert_eq!(stmt.path, vec!["std", "macros", "assign"]);
+        assert!(stmt.imports.is_right());
+    }
+
+    #[test]
+    fn parser_imp_alias() {
+        let stream = crate::stream::LexerStream::new("from course import a, b as c");
+        let parsed = imp().parse(stream);
+
+        assert!(parsed.is_ok());
+
+        let ((macr, stmt), stream) = parsed.unwrap();
+        assert!(stream.is_exhausted());
+
+        assert!(!macr);
+        assert_eq!(stmt.path, vec!["course"]);
+        assert!(!stmt.public);
+
+        let aliases = stmt.imports.left().unwrap();
+        assert_eq!(
+            aliases
+                .iter()
+                .map(|alias| (alias.ident.clone(), alias.alias.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("a".to_string(), None),
+                ("b".to_string(), Some("c".to_string()))
+            ]
+        );
+    }
+
+    #[test]
+    fn parser_imp_public_parens() {
+        let stream = crate::stream::LexerStream::new("PUB FROM course IMPORT (a AS b, c)");
+        let parsed = imp().parse(stream);
+
+        assert!(parsed.is_ok());
+
+        let ((macr, stmt), stream) = parsed.unwrap();
+        assert!(stream.is_exhausted());
+
+        assert!(!macr);
+        assert!(stmt.public);
+        assert_eq!(stmt.imports.left().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn parser_imports() {
+        let stream = crate::stream::LexerStream::new(indoc::indoc!(
+            "
+            from std::macros::inline macro import *
+            import std::math as m
+            from course import a
+            import fs::util
+            "
+        ));
+        let parsed = imports().parse(stream);
+
+        assert!(parsed.is_ok());
+
+        let (imp, stream) = parsed.unwrap();
+        assert!(stream.is_exhausted());
+
+        assert_eq!(imp.macr.len(), 1);
+        assert_eq!(imp.func.len(), 1);
+        assert_eq!(
+            imp.namespace
+                .iter()
+                .map(|namespace| (namespace.path.join("::"), namespace.alias.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("std::math".to_string(), Some("m".to_string())),
+                ("fs::util".to_string(), None)
+            ]
+        );
+    }
+}
+//endregion
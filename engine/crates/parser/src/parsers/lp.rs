@@ -2,6 +2,7 @@ use crate::combinators::is::is_ident;
 use crate::combinators::kw::{kw_do, kw_loop};
 use crate::combinators::trivia::sep;
 use crate::parsers::terms::block;
+use crate::parsers::unknown::Openers;
 use crate::utils::to_ident;
 use combine::error::Info::Format;
 use combine::parser::combinator::no_partial;
@@ -13,7 +14,7 @@ use lexer::Token;
 
 // parse LOOP <ident> DO <terms> END
 // return HIR
-pub(crate) fn lp<Input>() -> impl Parser<Input, Output = Hir, PartialState = ()>
+pub(crate) fn lp<Input>(openers: Openers) -> impl Parser<Input, Output = Hir, PartialState = ()>
 where
     Input: Stream<Token = Token>,
     Input::Error: Sized,
@@ -21,7 +22,7 @@ where
     let combinator = (
         kw_loop(), //
         is_ident(),
-        block(),
+        block(openers),
     )
         .then(|(start, ident, block)| {
             let ident = to_ident(ident);
@@ -54,7 +55,7 @@ mod tests {
     #[test]
     fn parser_lp() {
         let stream = crate::stream::LexerStream::new("LOOP x DO; ... ;END");
-        let parsed = lp().parse(stream);
+        let parsed = lp(Openers::default()).parse(stream);
 
         assert!(parsed.is_ok());
 
@@ -67,7 +68,7 @@ mod tests {
     #[test]
     fn parser_inline_nested_lp() {
         let stream = crate::stream::LexerStream::new("LOOP x DO; LOOP y DO; ... ; END; END");
-        let parsed = lp().parse(stream);
+        let parsed = lp(Openers::default()).parse(stream);
 
         assert!(parsed.is_ok());
 
@@ -96,7 +97,7 @@ mod tests {
                 END
             END"
         ));
-        let parsed = lp().parse(stream);
+        let parsed = lp(Openers::default()).parse(stream);
 
         assert!(parsed.is_ok());
 
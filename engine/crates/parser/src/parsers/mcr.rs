@@ -1,2 +1,446 @@
-// TODO
-// decl macro
+use crate::combinators::directive::{
+    dir_else, dir_end, dir_group_end, dir_group_start, dir_if, dir_macro, dir_sub,
+};
+use crate::combinators::is::is_ident;
+use crate::combinators::trivia::sep;
+use crate::parsers::cond::condition;
+use crate::parsers::imp::imports;
+use combine::error::Info::Format;
+use combine::parser::combinator::no_partial;
+use combine::{many, optional, satisfy, sep_end_by, unexpected_any, value, Parser, Stream};
+use lexer::{Directive, GroupQuantifier, Kind, MacroModifier, Token};
+use module::mcr::{MacroDecl, Segment};
+use module::Imp;
+
+use crate::utils::to_ident;
+use expr::Primitive;
+
+fn is_sep(token: &Token) -> bool {
+    matches!(token.kind, Kind::Newline | Kind::Semicolon)
+}
+
+pub(crate) fn to_segment(token: Token) -> Segment {
+    match token.kind {
+        Kind::Directive(Directive::Placeholder(placeholder)) => Segment::Placeholder {
+            lno: token.lno,
+            placeholder,
+        },
+        _ => Segment::Token(token),
+    }
+}
+
+// the separators of the substitution are needed to parse the result again,
+// only leading and trailing ones can be removed.
+fn trim(segments: Vec<Segment>) -> Vec<Segment> {
+    let is_sep = |segment: &Segment| matches!(segment, Segment::Token(token) if is_sep(token));
+
+    let first = segments.iter().position(|segment| !is_sep(segment));
+    let last = segments.iter().rposition(|segment| !is_sep(segment));
+    match (first, last) {
+        (Some(first), Some(last)) => segments[first..=last].to_vec(),
+        _ => vec![],
+    }
+}
+
+// separators are not part of the pattern
+fn strip(segments: Vec<Segment>) -> Vec<Segment> {
+    segments
+        .into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Token(token) if is_sep(&token) => None,
+            Segment::Group {
+                lno,
+                segments,
+                quantifier,
+            } => Some(Segment::Group {
+                lno,
+                segments: strip(segments),
+                quantifier,
+            }),
+            segment => Some(segment),
+        })
+        .collect()
+}
+
+// parse: @( <segments> @)<quantifier>
+// return: Segment
+fn group<Input, P>(segments: P) -> impl Parser<Input, Output = Segment>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+    P: Parser<Input, Output = Vec<Segment>>,
+{
+    (dir_group_start(), segments, dir_group_end()).map(|(start, segments, end)| {
+        let quantifier = match end.kind {
+            Kind::Directive(Directive::GroupEnd { quantifier }) => quantifier,
+            _ => GroupQuantifier::None,
+        };
+
+        Segment::Group {
+            lno: start.lno.end_at(&end.lno),
+
+            segments: trim(segments),
+            quantifier,
+        }
+    })
+}
+
+// parse: <token | placeholder | group>*, groups cannot be nested in the pattern
+fn pattern<Input>() -> impl Parser<Input, Output = Vec<Segment>>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    fn token<Input>() -> impl Parser<Input, Output = Segment>
+    where
+        Input: Stream<Token = Token>,
+        Input::Error: Sized,
+    {
+        satisfy(|token: Token| {
+            !matches!(
+                token.kind,
+                Kind::Directive(
+                    Directive::Sub
+                        | Directive::End
+                        | Directive::GroupStart
+                        | Directive::GroupEnd { .. }
+                )
+            )
+        })
+        .map(to_segment)
+    }
+
+    many(group(many(token())).or(token())).map(strip)
+}
+
+// parse: @if <condition> <segments> [@else <segments>] @end
+// return: Segment
+fn conditional<Input>() -> impl Parser<Input, Output = Segment, PartialState = ()>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    let combinator = (
+        dir_if(),
+        condition(),
+        segments(),
+        optional((dir_else(), segments())),
+        dir_end(),
+    )
+        .map(
+            |(start, condition, then, otherwise, end)| Segment::Conditional {
+                lno: start.lno.end_at(&end.lno),
+
+                condition,
+                then: trim(then),
+                otherwise: otherwise.map_or_else(Vec::new, |(_, otherwise)| trim(otherwise)),
+            },
+        );
+
+    no_partial(combinator)
+}
+
+fn segments_<Input>() -> impl Parser<Input, Output = Vec<Segment>, PartialState = ()>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    let token = satisfy(|token: Token| {
+        !matches!(
+            token.kind,
+            Kind::Directive(
+                Directive::If
+                    | Directive::Else
+                    | Directive::End
+                    | Directive::GroupStart
+                    | Directive::GroupEnd { .. }
+            )
+        )
+    })
+    .map(to_segment);
+
+    no_partial(many(conditional().or(group(segments())).or(token)))
+}
+
+parser! {
+    // the substitution of a macro, which can contain nested conditionals and groups
+    fn segments[Input]()(Input) -> Vec<Segment>
+    where [Input: Stream<Token = Token>]
+    {
+        segments_()
+    }
+}
+
+// parse: @macro <ident> <pattern> @sub <substitution> @end
+// return: MacroDecl
+pub(crate) fn macro_decl<Input>() -> impl Parser<Input, Output = MacroDecl, PartialState = ()>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    let combinator = (
+        dir_macro(),
+        is_ident(),
+        pattern(),
+        dir_sub(),
+        segments(),
+        dir_end(),
+    )
+        .then(|(start, ident, pattern, _, sub, end)| {
+            let ident = match to_ident(ident) {
+                Ok(Primitive::Ident { value, .. }) => value,
+                Ok(_) => unreachable!(),
+                Err(err) => return unexpected_any(Format(err)).right(),
+            };
+
+            let (modifier, priority) = match start.kind {
+                Kind::Directive(Directive::Macro { modifier, priority }) => (modifier, priority),
+                _ => (MacroModifier::NONE, 0),
+            };
+
+            let decl = MacroDecl {
+                lno: start.lno.end_at(&end.lno),
+
+                ident,
+                modifier,
+                priority,
+
+                pattern,
+                sub: trim(sub),
+            };
+
+            value(decl).left()
+        });
+
+    no_partial(combinator)
+}
+
+// parse all macro declarations, separated by newlines
+pub(crate) fn macros<Input>() -> impl Parser<Input, Output = Vec<MacroDecl>, PartialState = ()>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    no_partial((sep(), sep_end_by(macro_decl(), sep())).map(|(_, decls)| decls))
+}
+
+// parse the imports, followed by all macro declarations
+pub(crate) fn library<Input>(
+) -> impl Parser<Input, Output = (Imp, Vec<MacroDecl>), PartialState = ()>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    no_partial((imports(), macros()))
+}
+
+//region Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use lexer::{Placeholder, PlaceholderVariant};
+
+    #[test]
+    fn parser_macro_decl() {
+        let stream = crate::stream::LexerStream::new(indoc!(
+            "
+            @macro/i/10 inlineIncrement
+                %1.i++
+            @sub
+                %1.i := %1.i + 1
+                $2.i := 0
+            @end"
+        ));
+        let parsed = macros().parse(stream);
+
+        assert!(parsed.is_ok());
+
+        let (decls, stream) = parsed.unwrap();
+        assert!(stream.is_exhausted());
+        assert_eq!(decls.len(), 1);
+
+        let decl = &decls[0];
+        assert_eq!(decl.ident, "inlineIncrement");
+        assert_eq!(decl.modifier, MacroModifier::CASE_INSENSITIVE);
+        assert_eq!(decl.priority, 10);
+
+        assert_eq!(decl.pattern.len(), 3);
+        assert!(matches!(
+            decl.pattern[0],
+            Segment::Placeholder {
+                placeholder: Placeholder::Match {
+                    variant: PlaceholderVariant::IDENT,
+                    index: 1
+                },
+                ..
+            }
+        ));
+
+        // the newline between both statements is kept
+        assert_eq!(decl.sub.len(), 9);
+        assert!(matches!(
+            decl.sub[6],
+            Segment::Placeholder {
+                placeholder: Placeholder::Sub { index: 2, .. },
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn parser_library() {
+        let stream = crate::stream::LexerStream::new(indoc!(
+            "
+            from std::macros::inline macro import *
+            from course::util import double
+
+            @macro inlineIncrement
+                %1.i++
+            @sub
+                %1.i := %1.i + 1
+            @end
+            "
+        ));
+        let parsed = library().parse(stream);
+
+        assert!(parsed.is_ok());
+
+        let ((imp, decls), stream) = parsed.unwrap();
+        assert!(stream.is_exhausted());
+
+        assert_eq!(imp.macr.len(), 1);
+        assert_eq!(imp.func.len(), 1);
+        assert_eq!(decls.len(), 1);
+    }
+
+    #[test]
+    fn parser_macro_decl_multiple() {
+        let stream = crate::stream::LexerStream::new(indoc!(
+            "
+            @macro inlineIncrement
+                %1.i++
+            @sub
+                %1.i := %1.i + 1
+            @end
+
+            @macro inlineDecrement
+                %1.i--
+            @sub
+                %1.i := %1.i - 1
+            @end
+            "
+        ));
+        let parsed = macros().parse(stream);
+
+        assert!(parsed.is_ok());
+
+        let (decls, stream) = parsed.unwrap();
+        assert!(stream.is_exhausted());
+        assert_eq!(
+            decls
+                .iter()
+                .map(|decl| decl.ident.clone())
+                .collect::<Vec<_>>(),
+            vec!["inlineIncrement", "inlineDecrement"]
+        );
+    }
+
+    #[test]
+    fn parser_macro_decl_group() {
+        let stream = crate::stream::LexerStream::new(indoc!(
+            "
+            @macro ifElse
+                IF %1.i THEN
+                    %2.t
+                @(
+                ELSE
+                    %3.t
+                @)?
+                END
+            @sub
+                IF %1.i THEN
+                    %2.t
+                @(
+                ELSE
+                    %3.t
+                @)
+                END
+            @end"
+        ));
+        let parsed = macros().parse(stream);
+
+        assert!(parsed.is_ok());
+
+        let (decls, stream) = parsed.unwrap();
+        assert!(stream.is_exhausted());
+
+        let decl = &decls[0];
+        assert_eq!(decl.pattern.len(), 6);
+        match &decl.pattern[4] {
+            Segment::Group {
+                segments,
+                quantifier,
+                ..
+            } => {
+                assert_eq!(segments.len(), 2);
+                assert_eq!(*quantifier, GroupQuantifier::Optional);
+            }
+            _ => panic!("expected group"),
+        }
+
+        // the newlines around the group are kept, but not the ones inside
+        match &decl.sub[6] {
+            Segment::Group {
+                segments,
+                quantifier,
+                ..
+            } => {
+                assert_eq!(segments.len(), 3);
+                assert_eq!(*quantifier, GroupQuantifier::None);
+            }
+            _ => panic!("expected group"),
+        }
+    }
+
+    #[test]
+    fn parser_macro_decl_conditional() {
+        let stream = crate::stream::LexerStream::new(indoc!(
+            "
+            @macro assignToValue
+                %1.i := %2.n
+            @sub
+                @if %2.n == 0
+                    LOOP %1.i DO
+                        %1.i := %1.i - 1
+                    END
+                @else
+                    @if %2.n is value
+                        %1.i := 0
+                    @end
+                    %1.i := %1.i + %2.n
+                @end
+            @end"
+        ));
+        let parsed = macros().parse(stream);
+
+        assert!(parsed.is_ok());
+
+        let (decls, stream) = parsed.unwrap();
+        assert!(stream.is_exhausted());
+
+        let decl = &decls[0];
+        assert_eq!(decl.sub.len(), 1);
+
+        match &decl.sub[0] {
+            Segment::Conditional {
+                then, otherwise, ..
+            } => {
+                assert_eq!(then.len(), 11);
+                assert!(matches!(otherwise[0], Segment::Conditional { .. }));
+            }
+            _ => panic!("expected conditional"),
+        }
+    }
+}
+//endregion
@@ -1,14 +1,25 @@
 #[macro_use]
 pub(crate) mod macros;
 
+use crate::combinators::trivia::sep;
+use crate::parsers::cnst::cnsts;
+use crate::parsers::fnc::fnc_decls;
+use crate::parsers::imp::imports;
+use crate::parsers::mcr::macros;
+use crate::parsers::unknown::Openers;
+use combine::parser::combinator::no_partial;
+use combine::{attempt, eof, Parser, Stream};
 use hir::Hir;
-use variants::Errors;
+use lexer::Token;
+use module::{Decl, Module};
 
-// TODO: macro declaration
 // TODO: undefined lazy eval
 
 pub(crate) mod assign;
+pub(crate) mod cnst;
+pub(crate) mod cond;
 pub(crate) mod fnc;
+pub(crate) mod imp;
 pub(crate) mod lp;
 pub(crate) mod mcr;
 pub(crate) mod noop;
@@ -16,11 +27,104 @@ pub(crate) mod terms;
 pub(crate) mod unknown;
 pub(crate) mod whl;
 
-// This should parse in the following order:
-// 1) import
-// 2) macro
-// 3) func decl
-// 4) code
-pub(crate) fn parse_file(input: &str) -> Result<Hir, Errors> {
-    todo!()
+// This parses in the following order:
+// 1) imports
+// 2) constants
+// 3) macros
+// 4) function declarations
+// 5) code
+// the openers are the ones of all imported macros, the ones declared in the file are added.
+pub(crate) fn file<Input>(
+    openers: Openers,
+) -> impl Parser<Input, Output = Module, PartialState = ()>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    let combinator = (imports(), cnsts(), macros()).then(move |(imp, consts, macr)| {
+        let openers = openers.merge(&Openers::from(&macr[..]));
+
+        (
+            fnc_decls(openers.clone()),
+            sep(),
+            // a file without any terms has no code
+            attempt(eof().map(|_| Hir::NoOp)).or(terms::terms(true, openers)),
+        )
+            .map(move |(func, _, code)| Module {
+                imp: imp.clone(),
+                decl: Decl {
+                    macr: macr.clone(),
+                    func,
+                    consts: consts.clone(),
+                },
+                code,
+            })
+    });
+
+    no_partial(combinator)
+}
+
+//region Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ctrl::Control;
+    use indoc::indoc;
+
+    #[test]
+    fn parser_file() {
+        let stream = crate::stream::LexerStream::new(indoc!(
+            "
+            # comment
+            from std::macros::inline macro import *
+            import std::math as m
+
+            pub const c = 2 ### block
+            comment ###
+
+            @macro/i ifThen
+                IF %1.i THEN %2.t END
+            @sub
+                LOOP %1.i DO
+                    %2.t
+                END
+            @end
+
+            fn f(a) -> b decl
+                b := a + 0
+            end
+
+            x := f(c); if x then
+                y := x + 1
+            end
+            "
+        ));
+        let parsed = file(Openers::default()).skip(eof()).parse(stream);
+
+        assert!(parsed.is_ok());
+
+        let (module, _) = parsed.unwrap();
+
+        assert_eq!(module.imp.macr.len(), 1);
+        assert_eq!(module.imp.namespace.len(), 1);
+        assert_eq!(module.decl.consts.len(), 1);
+        assert_eq!(module.decl.macr.len(), 1);
+        assert_eq!(module.decl.func.len(), 1);
+
+        // the opener of the declared macro is known to the code
+        match module.code {
+            Hir::Control(Control::Block { terms }) => assert_eq!(terms.len(), 2),
+            _ => panic!("expected block"),
+        }
+    }
+
+    #[test]
+    fn parser_file_no_code() {
+        let stream = crate::stream::LexerStream::new("const c = 2\n\n");
+        let parsed = file(Openers::default()).skip(eof()).parse(stream);
+
+        assert!(parsed.is_ok());
+        assert!(matches!(parsed.unwrap().0.code, Hir::NoOp));
+    }
 }
+//endregion
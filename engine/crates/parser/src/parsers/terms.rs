@@ -7,7 +7,8 @@ use crate::parsers::whl::whl;
 use combine::parser::combinator::no_partial;
 
 use crate::parsers::assign::assign;
-use crate::parsers::unknown::unknown;
+use crate::parsers::fnc::fnc_call;
+use crate::parsers::unknown::{unknown, unknown_delimited, Openers};
 use combine::{attempt, sep_end_by, Parser, Stream};
 use ctrl::Control;
 use hir::Hir;
@@ -15,17 +16,19 @@ use lexer::Token;
 use mcr::Unknown;
 use variants::LineNo;
 
-fn term<Input>(unkn: bool) -> impl Parser<Input, Output = Hir, PartialState = ()>
+fn term<Input>(unkn: bool, openers: Openers) -> impl Parser<Input, Output = Hir, PartialState = ()>
 where
     Input: Stream<Token = Token>,
     Input::Error: Sized,
 {
-    let combinator = attempt(whl()) //
-        .or(attempt(lp()) //
+    let combinator = attempt(whl(openers.clone())) //
+        .or(attempt(lp(openers.clone())) //
             .or(attempt(assign())) //
+            .or(attempt(fnc_call())) //
             .or(if unkn {
                 attempt(noop())
-                    .or(attempt(block().map(|block| block.terms)) //
+                    .or(attempt(unknown_delimited(openers.clone())))
+                    .or(attempt(block(openers).map(|block| block.terms)) //
                         .or(attempt(unknown())))
                     .left()
             } else {
@@ -35,53 +38,57 @@ where
     no_partial(combinator)
 }
 
-fn terms_<Input>(unknown: bool) -> impl Parser<Input, Output = Hir, PartialState = ()>
+fn terms_<Input>(
+    unknown: bool,
+    openers: Openers,
+) -> impl Parser<Input, Output = Hir, PartialState = ()>
 where
     Input: Stream<Token = Token>,
     Input::Error: Sized,
 {
-    let combinator = sep_end_by::<Vec<_>, _, _, _>(attempt(term(unknown)), sep()).map(|terms| {
-        if terms.is_empty() {
-            Hir::Control(Control::Block {
-                terms: vec![Hir::NoOp],
-            })
-        } else {
-            let mut block: Vec<Hir> = vec![];
-            let mut backlog: Vec<Token> = vec![];
+    let combinator =
+        sep_end_by::<Vec<_>, _, _, _>(attempt(term(unknown, openers)), sep()).map(|terms| {
+            if terms.is_empty() {
+                Hir::Control(Control::Block {
+                    terms: vec![Hir::NoOp],
+                })
+            } else {
+                let mut block: Vec<Hir> = vec![];
+                let mut backlog: Vec<Token> = vec![];
 
-            fn push_backlog(block: &mut Vec<Hir>, backlog: &mut Vec<Token>) {
-                if backlog.is_empty() {
-                    return;
-                }
+                fn push_backlog(block: &mut Vec<Hir>, backlog: &mut Vec<Token>) {
+                    if backlog.is_empty() {
+                        return;
+                    }
 
-                block.push(Hir::Unknown(Unknown::Tokens(backlog.clone())));
-                backlog.clear();
-            }
+                    block.push(Hir::Unknown(Unknown::Tokens(backlog.clone())));
+                    backlog.clear();
+                }
 
-            for term in terms {
-                match term {
-                    Hir::Unknown(Unknown::Token(token)) => backlog.push(token.clone()),
-                    _ => {
-                        push_backlog(&mut block, &mut backlog);
-                        block.push(term)
+                for term in terms {
+                    match term {
+                        Hir::Unknown(Unknown::Token(token)) => backlog.push(token.clone()),
+                        _ => {
+                            push_backlog(&mut block, &mut backlog);
+                            block.push(term)
+                        }
                     }
                 }
-            }
 
-            push_backlog(&mut block, &mut backlog);
+                push_backlog(&mut block, &mut backlog);
 
-            Hir::Control(Control::Block { terms: block })
-        }
-    });
+                Hir::Control(Control::Block { terms: block })
+            }
+        });
 
     no_partial(combinator)
 }
 
 parser! {
-    pub(crate) fn terms[Input](unknown: bool)(Input) -> Hir
+    pub(crate) fn terms[Input](unknown: bool, openers: Openers)(Input) -> Hir
     where [Input: Stream<Token = Token>]
     {
-        terms_(*unknown)
+        terms_(*unknown, openers.clone())
     }
 }
 
@@ -90,23 +97,23 @@ pub(crate) struct Block {
     pub(crate) terms: Hir,
 }
 
-fn block_<Input>() -> impl Parser<Input, Output = Block, PartialState = ()>
+fn block_<Input>(openers: Openers) -> impl Parser<Input, Output = Block, PartialState = ()>
 where
     Input: Stream<Token = Token>,
     Input::Error: Sized,
 {
-    no_partial(
-        ((kw_do(), sep()), terms(true), kw_end()).map(|((start, _), terms, end)| Block {
+    no_partial(((kw_do(), sep()), terms(true, openers), kw_end()).map(
+        |((start, _), terms, end)| Block {
             lno: start.lno.end_at(&end.lno),
             terms,
-        }),
-    )
+        },
+    ))
 }
 
 parser! {
-    pub(crate) fn block[Input]()(Input) -> Block
+    pub(crate) fn block[Input](openers: Openers)(Input) -> Block
     where [Input: Stream<Token = Token>]
     {
-        block_()
+        block_(openers.clone())
     }
 }
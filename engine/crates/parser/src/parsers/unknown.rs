@@ -1,7 +1,70 @@
-use combine::{satisfy, Parser, Stream};
+use crate::combinators::kw::{kw_do, kw_end};
+use combine::parser::combinator::no_partial;
+use combine::{attempt, many, satisfy, value, Parser, Stream};
 use hir::Hir;
-use lexer::{Keyword, Kind, Token};
+use lexer::{Keyword, Kind, MacroModifier, Token};
 use mcr::Unknown;
+use module::mcr::{Delimiter, MacroDecl};
+use std::rc::Rc;
+
+// The tokens that start a macro spanning multiple lines,
+// these are taken verbatim until the construct is closed.
+#[derive(Debug, Clone, Default)]
+pub struct Openers(Rc<Vec<(Kind, Delimiter, bool)>>);
+
+impl Openers {
+    pub fn get(&self, kind: &Kind) -> Option<Delimiter> {
+        self.0
+            .iter()
+            .find(|(opener, _, case_insensitive)| opener.matches(kind, *case_insensitive))
+            .map(|(_, delimiter, _)| *delimiter)
+    }
+
+    // a construct which is not declared as macro, like the conditionals of the language itself
+    pub fn with(&self, kind: Kind, delimiter: Delimiter, case_insensitive: bool) -> Self {
+        let mut openers = (*self.0).clone();
+        let opener = (kind, delimiter, case_insensitive);
+
+        if !openers.contains(&opener) {
+            openers.push(opener);
+        }
+
+        Self(Rc::new(openers))
+    }
+
+    pub fn merge(&self, other: &Openers) -> Self {
+        other.0.iter().fold(
+            self.clone(),
+            |openers, (kind, delimiter, case_insensitive)| {
+                openers.with(kind.clone(), *delimiter, *case_insensitive)
+            },
+        )
+    }
+}
+
+impl From<&[MacroDecl]> for Openers {
+    fn from(macros: &[MacroDecl]) -> Self {
+        let mut openers: Vec<(Kind, Delimiter, bool)> = vec![];
+
+        for decl in macros {
+            let (token, delimiter) = match decl.delimiter() {
+                Some(value) => value,
+                None => continue,
+            };
+            let opener = (
+                token.kind.clone(),
+                delimiter,
+                decl.modifier.contains(MacroModifier::CASE_INSENSITIVE),
+            );
+
+            if !openers.contains(&opener) {
+                openers.push(opener);
+            }
+        }
+
+        Self(Rc::new(openers))
+    }
+}
 
 pub(crate) fn unknown<Input>() -> impl Parser<Input, Output = Hir>
 where
@@ -16,3 +79,103 @@ where
     .map(|value| Hir::Unknown(Unknown::Token(value)))
     .expected("unknown")
 }
+
+fn raw<Input>() -> impl Parser<Input, Output = Vec<Token>>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    satisfy(|token: Token| {
+        !matches!(token.kind, Kind::Keyword(Keyword::End))
+            && !matches!(token.kind, Kind::Keyword(Keyword::Do))
+    })
+    .map(|token| vec![token])
+}
+
+fn body<Input>(openers: Openers) -> impl Parser<Input, Output = Vec<Token>>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    many::<Vec<_>, _, _>(
+        attempt(delimited(openers.clone()))
+            .or(attempt(raw_block(openers)))
+            .or(raw()),
+    )
+    .map(|tokens| tokens.concat())
+}
+
+fn raw_block_<Input>(openers: Openers) -> impl Parser<Input, Output = Vec<Token>, PartialState = ()>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    let combinator = (kw_do(), body(openers), kw_end()).map(|(start, body, end)| {
+        let mut tokens = vec![start];
+        tokens.extend(body);
+        tokens.push(end);
+
+        tokens
+    });
+
+    no_partial(combinator)
+}
+
+parser! {
+    fn raw_block[Input](openers: Openers)(Input) -> Vec<Token>
+    where [Input: Stream<Token = Token>]
+    {
+        raw_block_(openers.clone())
+    }
+}
+
+fn delimited_<Input>(openers: Openers) -> impl Parser<Input, Output = Vec<Token>, PartialState = ()>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    let check = openers.clone();
+    let combinator =
+        satisfy(move |token: Token| check.get(&token.kind).is_some()).then(move |start: Token| {
+            let head = value(vec![start.clone()]);
+
+            match openers.get(&start.kind) {
+                // IF ... END
+                Some(Delimiter::End) => {
+                    (head, body(openers.clone()), kw_end().map(|end| vec![end]))
+                        .map(|(head, body, end)| [head, body, end].concat())
+                        .left()
+                }
+                // WHILE ... DO ... END
+                _ => (
+                    head,
+                    many::<Vec<_>, _, _>(raw()).map(|tokens: Vec<_>| tokens.concat()),
+                    raw_block(openers.clone()),
+                )
+                    .map(|(head, body, block)| [head, body, block].concat())
+                    .right(),
+            }
+        });
+
+    no_partial(combinator)
+}
+
+parser! {
+    fn delimited[Input](openers: Openers)(Input) -> Vec<Token>
+    where [Input: Stream<Token = Token>]
+    {
+        delimited_(openers.clone())
+    }
+}
+
+// parse a construct of a multiline macro, like: IF <...> THEN <terms> END
+// return HIR
+pub(crate) fn unknown_delimited<Input>(openers: Openers) -> impl Parser<Input, Output = Hir>
+where
+    Input: Stream<Token = Token>,
+    Input::Error: Sized,
+{
+    delimited(openers)
+        .map(|tokens| Hir::Unknown(Unknown::Tokens(tokens)))
+        .expected("unknown")
+}
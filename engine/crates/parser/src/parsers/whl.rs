@@ -4,6 +4,7 @@ use crate::combinators::comp::comp_ne;
 use crate::combinators::is::{is_ident, is_number};
 use crate::combinators::kw::{kw_do, kw_end, kw_while};
 use crate::parsers::terms::block;
+use crate::parsers::unknown::Openers;
 use crate::utils::{to_comp_verb, to_ident, to_uint};
 use combine::error::Info::Format;
 use combine::parser::combinator::no_partial;
@@ -17,7 +18,7 @@ use variants::Errors;
 
 // parse: WHILE <ident> != <uint> DO <terms> END
 // return: HIR
-pub(crate) fn whl<Input>() -> impl Parser<Input, Output = Hir, PartialState = ()>
+pub(crate) fn whl<Input>(openers: Openers) -> impl Parser<Input, Output = Hir, PartialState = ()>
 where
     Input: Stream<Token = Token>,
     Input::Error: Sized,
@@ -32,7 +33,7 @@ where
                 .message("Number is not a 0")
                 .right(),
         }),
-        block(),
+        block(openers),
     )
         .then(|(start, ident, comp, number, block)| {
             let lhs = to_ident(ident.clone());
@@ -84,7 +85,7 @@ mod tests {
     #[test]
     fn parser_whl() {
         let stream = crate::stream::LexerStream::new("WHILE x != 0 DO; ... ;END");
-        let parsed = whl().parse(stream);
+        let parsed = whl(Openers::default()).parse(stream);
 
         assert!(parsed.is_ok());
 
@@ -98,7 +99,7 @@ mod tests {
     fn parser_nested_whl() {
         let stream =
             crate::stream::LexerStream::new("WHILE x != 0 DO; WHILE y != 0 DO; ... ; END; END");
-        let parsed = whl().parse(stream);
+        let parsed = whl(Openers::default()).parse(stream);
 
         assert!(parsed.is_ok());
 
@@ -126,7 +127,7 @@ mod tests {
                 END
             END"
         ));
-        let parsed = whl().parse(stream);
+        let parsed = whl(Openers::default()).parse(stream);
 
         assert!(parsed.is_ok());
 
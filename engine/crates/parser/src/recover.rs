@@ -0,0 +1,434 @@
+// Error recovery for whole files. The tokens are split into statements at the separators,
+// constructs spanning multiple lines are kept together, every statement is then parsed on its
+// own. A statement which cannot be parsed is kept as error node and parsing continues with
+// the next one, so that the valid parts of a file can still be analysed.
+
+use crate::parsers::cnst::cnst;
+use crate::parsers::fnc::fnc_decl;
+use crate::parsers::imp::{imp, namespace};
+use crate::parsers::mcr::macro_decl;
+use crate::parsers::terms::terms;
+use crate::parsers::unknown::Openers;
+use crate::stream::LexerStream;
+use crate::utils;
+use combine::{attempt, eof, Parser};
+use ctrl::Control;
+use either::Either;
+use hir::Hir;
+use itertools::Itertools;
+use lexer::{Directive, Keyword, Kind, Token};
+use module::mcr::{Delimiter, MacroDecl};
+use module::{Decl, Imp, Module};
+use variants::err::{ErrorKind, ErrorKindParse};
+use variants::{Error, Errors};
+
+enum Statement {
+    Tokens(Vec<Token>),
+    // an END without a construct it closes
+    Stray(Token),
+    // the tokens up until the opening token of a construct which is never closed
+    Unterminated(Vec<Token>),
+}
+
+// the sections of a file, in the order they need to appear in
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+enum Section {
+    Imports,
+    Consts,
+    Macros,
+    Functions,
+    Code,
+}
+
+fn is_word(token: &Token, word: &str) -> bool {
+    matches!(&token.kind, Kind::Ident(ident) if ident.eq_ignore_ascii_case(word))
+}
+
+fn error(token: &Token, kind: ErrorKindParse) -> Errors {
+    Error::new_from_kind(Some(token.lno), ErrorKind::Parse(kind)).into()
+}
+
+// the first line of a statement, names the statement in errors
+fn excerpt(tokens: &[Token]) -> String {
+    tokens
+        .iter()
+        .take_while(|token| !matches!(token.kind, Kind::Newline | Kind::Semicolon))
+        .map(|token| token.content.as_str())
+        .join(" ")
+}
+
+fn section(tokens: &[Token]) -> Section {
+    let tokens = match tokens {
+        [public, rest @ ..] if is_word(public, "pub") && !rest.is_empty() => rest,
+        _ => tokens,
+    };
+
+    match tokens.first().map(|token| &token.kind) {
+        Some(Kind::Keyword(Keyword::From | Keyword::Import)) => Section::Imports,
+        Some(Kind::Ident(ident)) if ident.eq_ignore_ascii_case("const") => Section::Consts,
+        Some(Kind::Directive(Directive::Macro { .. })) => Section::Macros,
+        Some(Kind::Keyword(Keyword::Fn)) => Section::Functions,
+        _ => Section::Code,
+    }
+}
+
+// split the tokens into statements at the separators which are not part of a construct
+fn split(tokens: &[Token], openers: &Openers) -> Vec<Statement> {
+    let mut statements = vec![];
+    let mut current: Vec<Token> = vec![];
+    // the position in current of every construct which has not been closed yet
+    let mut open: Vec<usize> = vec![];
+    // inside of a macro declaration only the directives are nested
+    let mut directives = 0;
+
+    for (index, token) in tokens.iter().enumerate() {
+        let sep = matches!(token.kind, Kind::Newline | Kind::Semicolon);
+
+        if directives > 0 {
+            match token.kind {
+                Kind::Directive(Directive::If) => directives += 1,
+                Kind::Directive(Directive::End) => directives -= 1,
+                _ => {}
+            }
+        } else if matches!(token.kind, Kind::Directive(Directive::Macro { .. })) {
+            directives += 1;
+        } else if matches!(token.kind, Kind::Keyword(Keyword::End)) {
+            if open.pop().is_none() {
+                if !current.is_empty() {
+                    statements.push(Statement::Tokens(std::mem::take(&mut current)));
+                }
+
+                statements.push(Statement::Stray(token.clone()));
+                continue;
+            }
+        } else if matches!(token.kind, Kind::Keyword(Keyword::Do | Keyword::Decl))
+            || openers.get(&token.kind) == Some(Delimiter::End)
+        {
+            open.push(current.len());
+        }
+
+        if sep && open.is_empty() && directives == 0 {
+            if !current.is_empty() {
+                statements.push(Statement::Tokens(std::mem::take(&mut current)));
+            }
+        } else if !(sep && current.is_empty()) {
+            current.push(token.clone());
+        }
+
+        if index == tokens.len() - 1 && !open.is_empty() {
+            // the outermost construct is never closed, everything after it is split again
+            let position = open[0];
+            let rest = current.split_off(position + 1);
+
+            statements.push(Statement::Unterminated(std::mem::take(&mut current)));
+            statements.extend(split(&rest, openers));
+        }
+    }
+
+    if !current.is_empty() {
+        statements.push(Statement::Tokens(current));
+    }
+
+    statements
+}
+
+struct Recovery {
+    openers: Openers,
+    errors: Errors,
+}
+
+impl Recovery {
+    fn parse<P, O>(&mut self, mut parser: P, tokens: &[Token]) -> Option<O>
+    where
+        P: Parser<LexerStream, Output = O>,
+    {
+        match parser.parse(LexerStream::from(tokens.to_vec())) {
+            Ok((output, _)) => Some(output),
+            Err(err) => {
+                self.errors += utils::to_errors(err, tokens);
+                None
+            }
+        }
+    }
+
+    // the terms of a block, nested statements are recovered on their own
+    fn terms(&mut self, tokens: &[Token]) -> Vec<Hir> {
+        let mut block = vec![];
+
+        for statement in split(tokens, &self.openers) {
+            match statement {
+                Statement::Tokens(tokens) => {
+                    let parser = terms(true, self.openers.clone()).skip(eof());
+
+                    match self.parse(parser, &tokens) {
+                        Some(Hir::Control(Control::Block { terms })) => block.extend(terms),
+                        Some(hir) => block.push(hir),
+                        None => block.push(Hir::Error(tokens)),
+                    }
+                }
+                Statement::Stray(token) => {
+                    self.errors += error(&token, ErrorKindParse::UnexpectedEnd);
+                    block.push(Hir::Error(vec![token]));
+                }
+                Statement::Unterminated(tokens) => {
+                    let last = tokens.last().unwrap();
+                    let excerpt = last.content.clone();
+                    self.errors += error(last, ErrorKindParse::Unclosed { excerpt });
+                    block.push(Hir::Error(tokens));
+                }
+            }
+        }
+
+        block
+    }
+
+    // a function declaration with a broken signature, the errors of the body are still reported
+    fn function_body(&mut self, tokens: &[Token]) {
+        let body = tokens
+            .iter()
+            .position(|token| matches!(token.kind, Kind::Keyword(Keyword::Decl)))
+            .map(|start| &tokens[start + 1..tokens.len() - 1]);
+
+        if let Some(body) = body {
+            self.terms(body);
+        }
+    }
+}
+
+pub(crate) fn recover(tokens: &[Token], openers: Openers) -> (Module, Errors) {
+    // the openers of the macros declared in the file are needed to split the code
+    let mut declared = Recovery {
+        openers: openers.clone(),
+        errors: Errors::new(),
+    };
+    let macros: Vec<MacroDecl> = split(tokens, &openers)
+        .into_iter()
+        .filter_map(|statement| match statement {
+            Statement::Tokens(tokens) if section(&tokens) == Section::Macros => {
+                declared.parse(macro_decl().skip(eof()), &tokens)
+            }
+            _ => None,
+        })
+        .collect();
+
+    let mut recovery = Recovery {
+        openers: openers.merge(&Openers::from(&macros[..])),
+        errors: Errors::new(),
+    };
+
+    let mut imports = Imp::default();
+    let mut decl = Decl::default();
+    let mut code: Vec<Hir> = vec![];
+    let mut current = Section::Imports;
+
+    for statement in split(tokens, &recovery.openers) {
+        let tokens = match statement {
+            Statement::Tokens(tokens) => tokens,
+            Statement::Stray(token) => {
+                code.extend(recovery.terms(&[token]));
+                continue;
+            }
+            Statement::Unterminated(tokens) => {
+                code.extend(recovery.terms(&tokens));
+                continue;
+            }
+        };
+
+        let section = section(&tokens);
+        if section < current {
+            let excerpt = excerpt(&tokens);
+            recovery.errors += error(&tokens[0], ErrorKindParse::Misplaced { excerpt });
+            code.push(Hir::Error(tokens));
+            continue;
+        }
+        current = section;
+
+        match section {
+            Section::Imports => {
+                let parser = attempt(imp())
+                    .map(Either::Left)
+                    .or(namespace().map(Either::Right))
+                    .skip(eof());
+
+                match recovery.parse(parser, &tokens) {
+                    Some(Either::Left((true, stmt))) => imports.macr.push(stmt),
+                    Some(Either::Left((false, stmt))) => imports.func.push(stmt),
+                    Some(Either::Right(stmt)) => imports.namespace.push(stmt),
+                    None => {}
+                }
+            }
+            Section::Consts => decl
+                .consts
+                .extend(recovery.parse(cnst().skip(eof()), &tokens)),
+            Section::Macros => decl
+                .macr
+                .extend(recovery.parse(macro_decl().skip(eof()), &tokens)),
+            Section::Functions => {
+                let parser = fnc_decl(recovery.openers.clone()).skip(eof());
+
+                match recovery.parse(parser, &tokens) {
+                    Some(func) => decl.func.push(func),
+                    None => recovery.function_body(&tokens),
+                }
+            }
+            Section::Code => code.extend(recovery.terms(&tokens)),
+        }
+    }
+
+    let code = if code.is_empty() {
+        Hir::NoOp
+    } else {
+        Hir::Control(Control::Block { terms: code })
+    };
+
+    (
+        Module {
+            imp: imports,
+            decl,
+            code,
+        },
+        recovery.errors,
+    )
+}
+
+//region Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disp::CompactRepresentation;
+    use indoc::indoc;
+
+    fn recover_str(input: &str) -> (Module, Errors) {
+        recover(&LexerStream::new(input).tokens, Openers::default())
+    }
+
+    fn rows(errors: &Errors) -> Vec<u32> {
+        errors
+            .iter()
+            .map(|error| u32::from(error.lno.unwrap().row.start()))
+            .collect()
+    }
+
+    #[test]
+    fn recover_valid() {
+        let (module, errors) = recover_str(indoc!(
+            "
+            from a import b
+            const c = 1
+
+            x := y + 1
+            LOOP x DO
+                x := x - 1
+            END
+            "
+        ));
+
+        assert!(errors.is_empty());
+        assert_eq!(module.imp.func.len(), 1);
+        assert_eq!(module.decl.consts.len(), 1);
+        assert_eq!(
+            module.code.compact(None).matches("Assign").count(),
+            2,
+            "{}",
+            module.code.compact(None)
+        );
+    }
+
+    #[test]
+    fn recover_stray_end() {
+        let (module, errors) = recover_str(indoc!(
+            "
+            x := x + 1
+            END
+            y := y + 1
+            END
+            "
+        ));
+
+        assert_eq!(rows(&errors), vec![1, 3]);
+
+        let code = module.code.compact(None);
+        assert_eq!(code.matches("Error: END").count(), 2);
+        assert_eq!(code.matches("Assign").count(), 2);
+    }
+
+    #[test]
+    fn recover_unterminated() {
+        let (module, errors) = recover_str(indoc!(
+            "
+            LOOP x DO
+                y := y + 1
+                LOOP y DO
+                    z := z + 1
+                END
+            "
+        ));
+
+        assert_eq!(rows(&errors), vec![0]);
+
+        let code = module.code.compact(None);
+        assert!(code.contains("Error: LOOP x DO"), "{}", code);
+        assert!(code.contains("Loop"), "{}", code);
+    }
+
+    #[test]
+    fn recover_broken_declarations() {
+        let (module, errors) = recover_str(indoc!(
+            "
+            from a import
+            const b =
+            fn f(x -> y decl
+                y := x + 1
+                end
+            end
+            fn g(x) -> y decl
+                y := x + 1
+            end
+
+            z := z + 1
+            "
+        ));
+
+        assert_eq!(rows(&errors), vec![0, 1, 2, 5]);
+        assert_eq!(module.decl.func.len(), 1);
+        assert!(module.code.compact(None).contains("Assign"));
+    }
+
+    #[test]
+    fn recover_section_order() {
+        let (module, errors) = recover_str(indoc!(
+            "
+            x := x + 1
+            from a import b
+            "
+        ));
+
+        assert_eq!(rows(&errors), vec![1]);
+        assert!(module.imp.func.is_empty());
+        assert!(module.code.compact(None).contains("Error: from a import b"));
+    }
+
+    #[test]
+    fn recover_macro_openers() {
+        let (module, errors) = recover_str(indoc!(
+            "
+            @macro ifThen
+                IF %1.i THEN %2.t END
+            @sub
+                LOOP %1.i DO
+                    %2.t
+                END
+            @end
+
+            IF x THEN
+                y := y + 1
+            END
+            "
+        ));
+
+        assert!(errors.is_empty());
+        assert_eq!(module.decl.macr.len(), 1);
+        assert!(module.code.compact(None).contains("Unknown"));
+    }
+}
+//endregion
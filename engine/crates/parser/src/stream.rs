@@ -26,6 +26,11 @@ impl LexerStream {
     pub fn is_exhausted(&self) -> bool {
         self.pos == self.tokens.len()
     }
+
+    // the tokens which have not been consumed yet
+    pub(crate) fn remaining(&self) -> Vec<Token> {
+        self.tokens[self.pos..].to_vec()
+    }
 }
 
 impl<'a> From<Lexer<'a>> for LexerStream {
@@ -39,6 +44,18 @@ impl<'a> From<Lexer<'a>> for LexerStream {
     }
 }
 
+impl From<Vec<Token>> for LexerStream {
+    fn from(tokens: Vec<Token>) -> Self {
+        Self {
+            pos: 0,
+            tokens: tokens
+                .into_iter()
+                .filter(|token| !token.kind.is_trivia())
+                .collect(),
+        }
+    }
+}
+
 impl StreamOnce for LexerStream {
     type Token = Token;
     type Range = Range;
@@ -4,7 +4,7 @@ use expr::comp::CompVerb;
 use expr::Primitive;
 use lexer::Kind;
 use std::convert::TryFrom;
-use variants::err::{ErrorKind, ErrorKindInvalidToken};
+use variants::err::{ErrorKind, ErrorKindInvalidToken, ErrorKindParse};
 use variants::{Error, Errors};
 
 pub(crate) fn to_ident(token: Token) -> Result<Primitive, Errors> {
@@ -23,6 +23,17 @@ pub(crate) fn to_ident(token: Token) -> Result<Primitive, Errors> {
     }
 }
 
+// a path is kept as single identifier, like m::max
+pub(crate) fn to_path(token: Token) -> Result<Primitive, Errors> {
+    match &token.kind {
+        Kind::Path(path) => Ok(Primitive::Ident {
+            value: path.join("::"),
+            token: vec![token],
+        }),
+        _ => to_ident(token),
+    }
+}
+
 pub(crate) fn to_comp_verb(token: Token) -> Result<CompVerb, Errors> {
     match token.kind {
         Kind::Comp(comp) => Ok(comp.into()),
@@ -64,3 +75,18 @@ pub(crate) fn to_uint(token: Token) -> Result<Primitive, Errors> {
         ))),
     }
 }
+
+pub(crate) fn to_errors(
+    error: combine::easy::Errors<Token, Vec<Token>, usize>,
+    tokens: &[Token],
+) -> Errors {
+    let token = tokens.get(error.position);
+    let excerpt = token.map(|token| token.content.clone()).unwrap_or_default();
+
+    let lno = token.or_else(|| tokens.last()).map(|token| token.lno);
+
+    Errors::from(Error::new_from_kind(
+        lno,
+        ErrorKind::Parse(ErrorKindParse::Syntax { excerpt }),
+    ))
+}
@@ -0,0 +1,25 @@
+use std::collections::HashMap;
+
+// The sources of every module which can be imported, keyed by their path.
+// A course can ship its own library by inserting it alongside the standard library.
+#[derive(Debug, Clone, Default)]
+pub struct Directory {
+    modules: HashMap<Vec<String>, String>,
+}
+
+impl Directory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, path: &[&str], contents: &str) {
+        self.modules.insert(
+            path.iter().map(|segment| segment.to_string()).collect(),
+            contents.to_string(),
+        );
+    }
+
+    pub fn get(&self, path: &[String]) -> Option<&String> {
+        self.modules.get(path)
+    }
+}
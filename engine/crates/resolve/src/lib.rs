@@ -1,3 +1,6 @@
+pub mod dir;
+pub mod macros;
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -0,0 +1,251 @@
+use crate::macros::matcher::Bindings;
+use lexer::{Comp, GroupQuantifier, Kind, Lexer, Placeholder, Token};
+use module::mcr::{Class, Condition, Segment};
+use module::MacroDecl;
+use std::cmp::Ordering;
+use text_size::TextRange;
+use variants::{LineNo, UInt};
+
+// macros are hygienic, temporary variables are unique for every expansion
+pub(crate) fn temporary(decl: &MacroDecl, expansion: usize, index: u32) -> String {
+    format!("_{}_{}_{}", decl.ident, expansion, index)
+}
+
+fn relocate(token: &Token, span: TextRange, lno: LineNo) -> Token {
+    Token {
+        kind: token.kind.clone(),
+        content: token.content.clone(),
+
+        span,
+        lno,
+    }
+}
+
+fn operand<'a>(
+    segment: &'a Segment,
+    bindings: &'a Bindings,
+    repetition: Option<usize>,
+) -> &'a [Token] {
+    match segment {
+        Segment::Token(token) => std::slice::from_ref(token),
+        Segment::Placeholder {
+            placeholder: Placeholder::Match { index, .. },
+            ..
+        } => bindings.get(*index, repetition).unwrap_or_default(),
+        // temporary variables have no value at compile-time
+        _ => &[],
+    }
+}
+
+fn value(tokens: &[Token]) -> Option<&UInt> {
+    match tokens {
+        [Token {
+            kind: Kind::Number(value),
+            ..
+        }] => Some(value),
+        _ => None,
+    }
+}
+
+// evaluate the condition of @if against the tokens matched by the placeholders,
+// ordering is only defined for values, equality compares the tokens.
+pub(crate) fn evaluate(
+    condition: &Condition,
+    bindings: &Bindings,
+    repetition: Option<usize>,
+) -> bool {
+    match condition {
+        Condition::Comp { lhs, verb, rhs } => {
+            let lhs = operand(lhs, bindings, repetition);
+            let rhs = operand(rhs, bindings, repetition);
+
+            let equal =
+                lhs.len() == rhs.len() && lhs.iter().zip(rhs).all(|(a, b)| a.kind == b.kind);
+            let ordering = match (value(lhs), value(rhs)) {
+                (Some(lhs), Some(rhs)) => Some(lhs.cmp(rhs)),
+                _ => None,
+            };
+
+            match verb {
+                Comp::Equal => equal,
+                Comp::NotEqual => !equal,
+                Comp::GreaterThan => ordering == Some(Ordering::Greater),
+                Comp::GreaterEqual => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+                Comp::LessThan => ordering == Some(Ordering::Less),
+                Comp::LessEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            }
+        }
+        Condition::Is {
+            operand: segment,
+            class,
+        } => match (operand(segment, bindings, repetition), class) {
+            ([token], Class::Value) => matches!(token.kind, Kind::Number(_)),
+            ([token], Class::Ident) => matches!(token.kind, Kind::Ident(_)),
+            _ => false,
+        },
+        Condition::Not(condition) => !evaluate(condition, bindings, repetition),
+        Condition::And(lhs, rhs) => {
+            evaluate(lhs, bindings, repetition) && evaluate(rhs, bindings, repetition)
+        }
+        Condition::Or(lhs, rhs) => {
+            evaluate(lhs, bindings, repetition) || evaluate(rhs, bindings, repetition)
+        }
+    }
+}
+
+// the indices of the match placeholders, including the ones in conditionals and groups
+fn indices(segments: &[Segment]) -> Vec<u32> {
+    segments
+        .iter()
+        .flat_map(|segment| match segment {
+            Segment::Token(_) => vec![],
+            Segment::Placeholder {
+                placeholder: Placeholder::Match { index, .. },
+                ..
+            } => vec![*index],
+            Segment::Placeholder { .. } => vec![],
+            Segment::Group { segments, .. } => indices(segments),
+            Segment::Conditional {
+                condition,
+                then,
+                otherwise,
+                ..
+            } => [
+                indices(
+                    &condition
+                        .operands()
+                        .into_iter()
+                        .cloned()
+                        .collect::<Vec<_>>(),
+                ),
+                indices(then),
+                indices(otherwise),
+            ]
+            .concat(),
+        })
+        .collect()
+}
+
+struct Expansion<'a> {
+    decl: &'a MacroDecl,
+    bindings: &'a Bindings,
+    expansion: usize,
+
+    span: TextRange,
+    lno: LineNo,
+}
+
+impl<'a> Expansion<'a> {
+    // the group of the pattern, which declared the placeholder
+    fn declaration(&self, index: u32) -> Option<GroupQuantifier> {
+        self.decl.pattern.iter().find_map(|segment| match segment {
+            Segment::Group {
+                segments,
+                quantifier,
+                ..
+            } if indices(segments).contains(&index) => Some(*quantifier),
+            _ => None,
+        })
+    }
+
+    // a group is repeated as often as the group in the pattern, joined by the separator
+    // of the group, or the separator of the group in the pattern if it has none.
+    fn repeat(&self, segments: &[Segment], quantifier: GroupQuantifier, tokens: &mut Vec<Token>) {
+        let indices = indices(segments);
+        let count = indices
+            .iter()
+            .map(|index| self.bindings.repetitions(*index))
+            .max()
+            .unwrap_or_default();
+
+        let separator = quantifier.separator().or_else(|| {
+            indices
+                .iter()
+                .find_map(|index| self.declaration(*index))
+                .and_then(|quantifier| quantifier.separator())
+        });
+        let separator = separator
+            .and_then(|separator| Lexer::new(&separator.to_string()).next())
+            .map(|token| relocate(&token, self.span, self.lno));
+
+        for repetition in 0..count {
+            if let (true, Some(separator)) = (repetition > 0, &separator) {
+                tokens.push(separator.clone());
+            }
+
+            self.substitute(segments, Some(repetition), tokens);
+        }
+    }
+
+    fn substitute(&self, segments: &[Segment], repetition: Option<usize>, tokens: &mut Vec<Token>) {
+        for segment in segments {
+            match segment {
+                Segment::Token(token) => tokens.push(relocate(token, self.span, self.lno)),
+                Segment::Placeholder {
+                    placeholder: Placeholder::Match { index, .. },
+                    ..
+                } => tokens
+                    .extend_from_slice(self.bindings.get(*index, repetition).unwrap_or_default()),
+                Segment::Placeholder {
+                    placeholder: Placeholder::Sub { index, .. },
+                    ..
+                } => {
+                    let ident = temporary(self.decl, self.expansion, *index);
+
+                    tokens.push(Token {
+                        kind: Kind::Ident(ident.clone()),
+                        content: ident,
+
+                        span: self.span,
+                        lno: self.lno,
+                    })
+                }
+                Segment::Group {
+                    segments,
+                    quantifier,
+                    ..
+                } => self.repeat(segments, *quantifier, tokens),
+                Segment::Conditional {
+                    condition,
+                    then,
+                    otherwise,
+                    ..
+                } => {
+                    if evaluate(condition, self.bindings, repetition) {
+                        self.substitute(then, repetition, tokens)
+                    } else {
+                        self.substitute(otherwise, repetition, tokens)
+                    }
+                }
+            }
+        }
+    }
+}
+
+// substitute the placeholders in the body of the macro,
+// every token that is not from the invocation is attributed to the invocation.
+pub(crate) fn expand(
+    decl: &MacroDecl,
+    bindings: &Bindings,
+    invocation: &[Token],
+    expansion: usize,
+) -> Vec<Token> {
+    let (first, last) = match (invocation.first(), invocation.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return vec![],
+    };
+
+    let expansion = Expansion {
+        decl,
+        bindings,
+        expansion,
+
+        span: first.span.cover(last.span),
+        lno: first.lno.end_at(&last.lno),
+    };
+
+    let mut tokens = vec![];
+    expansion.substitute(&decl.sub, None, &mut tokens);
+
+    tokens
+}
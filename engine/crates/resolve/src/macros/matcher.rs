@@ -0,0 +1,370 @@
+use crate::macros::trace::Failure;
+use lexer::{
+    GroupQuantifier, Keyword, Kind, MacroModifier, Placeholder, PlaceholderVariant, Token,
+};
+use module::mcr::{Delimiter, Segment};
+use module::MacroDecl;
+use parser::Openers;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+// tokens captured by the placeholders, keyed by their index
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Bindings {
+    tokens: HashMap<u32, Vec<Token>>,
+    // placeholders in a group capture once for every repetition of the group
+    repetitions: HashMap<u32, Vec<Vec<Token>>>,
+}
+
+impl Bindings {
+    // the captured tokens, placeholders in a group need the repetition
+    pub(crate) fn get(&self, index: u32, repetition: Option<usize>) -> Option<&[Token]> {
+        match (self.tokens.get(&index), repetition) {
+            (Some(tokens), _) => Some(tokens),
+            (None, Some(repetition)) => self
+                .repetitions
+                .get(&index)?
+                .get(repetition)
+                .map(Vec::as_slice),
+            (None, None) => None,
+        }
+    }
+
+    // number of times the group, which declared the placeholder, has been matched
+    pub(crate) fn repetitions(&self, index: u32) -> usize {
+        self.repetitions.get(&index).map_or(0, Vec::len)
+    }
+
+    // return: if the capture has been inserted, None if the placeholder is already bound to
+    // different tokens, as the same placeholder used twice needs to match the same tokens
+    fn bind(&mut self, index: u32, repetition: Option<usize>, capture: &[Token]) -> Option<bool> {
+        let bound = match repetition {
+            None => self.tokens.get(&index),
+            Some(repetition) => self
+                .repetitions
+                .get(&index)
+                .and_then(|captures| captures.get(repetition)),
+        };
+
+        match bound {
+            Some(bound) => {
+                let equal = bound.len() == capture.len()
+                    && bound.iter().zip(capture).all(|(a, b)| a.kind == b.kind);
+
+                if equal {
+                    Some(false)
+                } else {
+                    None
+                }
+            }
+            None => {
+                match repetition {
+                    None => {
+                        self.tokens.insert(index, capture.to_vec());
+                    }
+                    Some(_) => self
+                        .repetitions
+                        .entry(index)
+                        .or_default()
+                        .push(capture.to_vec()),
+                }
+
+                Some(true)
+            }
+        }
+    }
+
+    fn unbind(&mut self, index: u32, repetition: Option<usize>) {
+        match repetition {
+            None => {
+                self.tokens.remove(&index);
+            }
+            Some(_) => {
+                if let Some(captures) = self.repetitions.get_mut(&index) {
+                    captures.pop();
+
+                    if captures.is_empty() {
+                        self.repetitions.remove(&index);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Match {
+    pub(crate) bindings: Bindings,
+
+    // number of tokens consumed
+    pub(crate) len: usize,
+}
+
+pub(crate) fn is_sep(token: &Token) -> bool {
+    matches!(token.kind, Kind::Newline | Kind::Semicolon)
+}
+
+fn skip_sep(tokens: &[Token], pos: usize) -> usize {
+    tokens[pos..]
+        .iter()
+        .position(|token| !is_sep(token))
+        .map_or(tokens.len(), |offset| pos + offset)
+}
+
+// DO ... END and multiline macros need to be closed in the same capture
+fn is_balanced(tokens: &[Token], openers: &Openers) -> bool {
+    let mut depth = 0usize;
+
+    for token in tokens {
+        match &token.kind {
+            Kind::Keyword(Keyword::Do) => depth += 1,
+            Kind::Keyword(Keyword::End) if depth == 0 => return false,
+            Kind::Keyword(Keyword::End) => depth -= 1,
+            kind if openers.get(kind) == Some(Delimiter::End) => depth += 1,
+            _ => {}
+        }
+    }
+
+    depth == 0
+}
+
+fn is_single(variant: PlaceholderVariant) -> bool {
+    !variant.intersects(
+        PlaceholderVariant::EXPR | PlaceholderVariant::BLOCK | PlaceholderVariant::TERMS,
+    )
+}
+
+fn accepts(variant: PlaceholderVariant, capture: &[Token], openers: &Openers) -> bool {
+    if let [token] = capture {
+        let accepted = match token.kind {
+            Kind::Ident(_) => variant.contains(PlaceholderVariant::IDENT),
+            Kind::Number(_) => variant.contains(PlaceholderVariant::NUMBER),
+            Kind::Comp(_) => variant.contains(PlaceholderVariant::COMP),
+            Kind::Op(_) => variant.contains(PlaceholderVariant::OP),
+            _ => false,
+        };
+
+        if accepted {
+            return true;
+        }
+    }
+
+    if capture.is_empty() || matches!(capture.last(), Some(token) if is_sep(token)) {
+        return false;
+    }
+
+    if !is_balanced(capture, openers) {
+        return false;
+    }
+
+    let block = match capture {
+        [first, inner @ .., last] => {
+            first.kind == Kind::Keyword(Keyword::Do)
+                && last.kind == Kind::Keyword(Keyword::End)
+                && is_balanced(inner, openers)
+        }
+        _ => false,
+    };
+
+    (variant.contains(PlaceholderVariant::BLOCK) && block)
+        || (variant.contains(PlaceholderVariant::EXPR) && !capture.iter().any(is_sep))
+        || variant.contains(PlaceholderVariant::TERMS)
+}
+
+// called with the position after the matched segments, to match the rest of the pattern
+type Next<'n> = &'n mut dyn FnMut(usize, &mut Bindings) -> Option<usize>;
+
+struct Matcher<'a> {
+    tokens: &'a [Token],
+    openers: &'a Openers,
+
+    // literal idents of the pattern are compared case-insensitive, set by @macro/i
+    case_insensitive: bool,
+
+    // the failure which got the furthest into the tokens, the most likely cause
+    failure: RefCell<Option<(usize, Failure)>>,
+}
+
+impl<'a> Matcher<'a> {
+    fn fail(&self, pos: usize, failure: Failure) {
+        let mut current = self.failure.borrow_mut();
+
+        if !matches!(&*current, Some((furthest, _)) if *furthest > pos) {
+            *current = Some((pos, failure));
+        }
+    }
+
+    fn segments(
+        &self,
+        segments: &[Segment],
+        pos: usize,
+        repetition: Option<usize>,
+        bindings: &mut Bindings,
+        next: Next,
+    ) -> Option<usize> {
+        let (segment, rest) = match segments.split_first() {
+            Some(value) => value,
+            None => return next(pos, bindings),
+        };
+
+        let start = skip_sep(self.tokens, pos);
+
+        match segment {
+            Segment::Token(expected) => match self.tokens.get(start) {
+                Some(token) if expected.kind.matches(&token.kind, self.case_insensitive) => {
+                    self.segments(rest, start + 1, repetition, bindings, next)
+                }
+                found => {
+                    self.fail(
+                        start,
+                        Failure::Token {
+                            expected: expected.clone(),
+                            found: found.cloned(),
+                        },
+                    );
+                    None
+                }
+            },
+            Segment::Placeholder {
+                placeholder: placeholder @ Placeholder::Match { variant, index },
+                ..
+            } => {
+                let mut captured = false;
+                let end = if is_single(*variant) {
+                    (start + 1).min(self.tokens.len())
+                } else {
+                    self.tokens.len()
+                };
+
+                // captures are lazy, the shortest capture that lets the rest match is taken
+                for stop in start + 1..=end {
+                    let capture = &self.tokens[start..stop];
+                    if !accepts(*variant, capture, self.openers) {
+                        continue;
+                    }
+
+                    let inserted = match bindings.bind(*index, repetition, capture) {
+                        Some(inserted) => inserted,
+                        None => continue,
+                    };
+
+                    captured = true;
+                    if let Some(pos) = self.segments(rest, stop, repetition, bindings, next) {
+                        return Some(pos);
+                    }
+
+                    if inserted {
+                        bindings.unbind(*index, repetition);
+                    }
+                }
+
+                if !captured {
+                    self.fail(
+                        start,
+                        Failure::Placeholder {
+                            placeholder: *placeholder,
+                            found: self.tokens.get(start).cloned(),
+                        },
+                    );
+                }
+
+                None
+            }
+            Segment::Group {
+                segments: inner,
+                quantifier,
+                ..
+            } => self.group(
+                inner,
+                *quantifier,
+                0,
+                pos,
+                bindings,
+                &mut |pos, bindings| self.segments(rest, pos, repetition, bindings, next),
+            ),
+            // substitution placeholders and conditionals are only valid in the substitution
+            Segment::Placeholder { .. } | Segment::Conditional { .. } => None,
+        }
+    }
+
+    // groups are greedy, the most repetitions that let the rest match are taken
+    fn group(
+        &self,
+        segments: &[Segment],
+        quantifier: GroupQuantifier,
+        count: usize,
+        pos: usize,
+        bindings: &mut Bindings,
+        next: Next,
+    ) -> Option<usize> {
+        let (min, max) = quantifier.bounds();
+
+        if !matches!(max, Some(max) if count >= max) {
+            let start = match (count, quantifier.separator()) {
+                (0, _) | (_, None) => Some(pos),
+                (_, Some(sep)) => {
+                    let start = skip_sep(self.tokens, pos);
+
+                    match self.tokens.get(start) {
+                        Some(token) if token.content == sep.to_string() => Some(start + 1),
+                        _ => None,
+                    }
+                }
+            };
+
+            if let Some(start) = start {
+                let matched = self.segments(
+                    segments,
+                    start,
+                    Some(count),
+                    bindings,
+                    &mut |stop, bindings| {
+                        // a repetition which does not consume anything would repeat forever
+                        if stop == start {
+                            return None;
+                        }
+
+                        self.group(segments, quantifier, count + 1, stop, bindings, next)
+                    },
+                );
+
+                if matched.is_some() {
+                    return matched;
+                }
+            }
+        }
+
+        if count >= min {
+            next(pos, bindings)
+        } else {
+            None
+        }
+    }
+}
+
+// match the pattern of the macro against the start of the tokens,
+// otherwise return why the pattern does not match
+pub(crate) fn matches(
+    decl: &MacroDecl,
+    tokens: &[Token],
+    openers: &Openers,
+) -> Result<Match, Box<Failure>> {
+    let matcher = Matcher {
+        tokens,
+        openers,
+        case_insensitive: decl.modifier.contains(MacroModifier::CASE_INSENSITIVE),
+        failure: RefCell::new(None),
+    };
+    let mut bindings = Bindings::default();
+
+    match matcher.segments(&decl.pattern, 0, None, &mut bindings, &mut |pos, _| {
+        Some(pos)
+    }) {
+        Some(len) => Ok(Match { bindings, len }),
+        None => Err(matcher
+            .failure
+            .into_inner()
+            .map_or(Failure::Unmatched, |(_, failure)| failure)
+            .into()),
+    }
+}
@@ -0,0 +1,1173 @@
+use crate::macros::expand::expand;
+use crate::macros::matcher::{is_sep, matches, Match};
+use crate::macros::scope::{Entry, Scope, Scopes};
+use crate::macros::trace::{Attempt, Expansion, Failure, Region, Trace};
+use ctrl::Control;
+use hir::Hir;
+use lexer::Token;
+use mcr::Unknown;
+use module::MacroDecl;
+use parser::Openers;
+use std::mem;
+use variants::err::{ErrorKind, ErrorKindMacro};
+use variants::{Error, Errors};
+
+mod expand;
+mod matcher;
+pub mod scope;
+pub mod trace;
+mod validate;
+
+// expansions which produce unknown tokens are resolved again,
+// this is the maximum depth before a macro is considered to never resolve.
+const RECURSION_LIMIT: usize = 64;
+
+// recognizes a statement of the language the macros are used in at the start of the tokens and
+// returns the number of tokens it spans.
+pub type Builtin = fn(&[Token]) -> Option<usize>;
+
+pub struct Resolver {
+    scopes: Scopes,
+    openers: Openers,
+    // statements no macro matches are left unresolved if they are recognized
+    builtin: Option<Builtin>,
+
+    // number of expansions, used to create unique temporary variables
+    counter: usize,
+    // every attempted macro, only recorded if requested
+    trace: Option<Trace>,
+}
+
+impl Resolver {
+    pub fn new(scopes: Scopes) -> Self {
+        let macros: Vec<MacroDecl> = scopes.entries().map(|entry| entry.decl.clone()).collect();
+        let openers = Openers::from(macros.as_slice());
+
+        Self {
+            scopes,
+            openers,
+            builtin: None,
+            counter: 0,
+            trace: None,
+        }
+    }
+
+    // leave the statements of the language to the caller, as unknown tokens
+    pub fn with_builtin(mut self, builtin: Builtin) -> Self {
+        self.builtin = Some(builtin);
+        self
+    }
+
+    // record the macros tried for every unknown region, to debug why a macro does not match
+    pub fn with_trace(mut self) -> Self {
+        self.trace = Some(Trace::default());
+        self
+    }
+
+    pub fn trace(&self) -> Option<&Trace> {
+        self.trace.as_ref()
+    }
+
+    // the openers need to be known while parsing, to correctly delimit multiline macros
+    pub fn openers(&self) -> Openers {
+        self.openers.clone()
+    }
+
+    // resolve the unknown terms of the main module
+    pub fn resolve(&mut self, hir: Hir) -> Result<Hir, Errors> {
+        let scope = self
+            .scopes
+            .get(&Scopes::main())
+            .cloned()
+            .unwrap_or_default();
+
+        self.resolve_hir(hir, &scope, 0)
+    }
+
+    fn resolve_hir(&mut self, hir: Hir, scope: &Scope, depth: usize) -> Result<Hir, Errors> {
+        match hir {
+            Hir::Unknown(Unknown::Token(token)) => self.resolve_unknown(vec![token], scope, depth),
+            Hir::Unknown(Unknown::Tokens(tokens)) => self.resolve_unknown(tokens, scope, depth),
+            Hir::Control(Control::Block { terms }) => {
+                let mut block = vec![];
+                let mut errors = Errors::new();
+
+                for term in terms {
+                    let unknown = matches!(term, Hir::Unknown(_));
+
+                    match self.resolve_hir(term, scope, depth) {
+                        // splice the expansion into the surrounding block
+                        Ok(Hir::Control(Control::Block { terms })) if unknown => {
+                            block.extend(terms)
+                        }
+                        Ok(term) => block.push(term),
+                        Err(err) => errors += err,
+                    }
+                }
+
+                if !errors.is_empty() {
+                    return Err(errors);
+                }
+
+                Ok(Hir::Control(Control::Block { terms: block }))
+            }
+            Hir::Control(Control::Loop { lno, ident, terms }) => Ok(Hir::Control(Control::Loop {
+                lno,
+                ident,
+                terms: Box::new(self.resolve_hir(*terms, scope, depth)?),
+            })),
+            Hir::Control(Control::While { lno, comp, terms }) => Ok(Hir::Control(Control::While {
+                lno,
+                comp,
+                terms: Box::new(self.resolve_hir(*terms, scope, depth)?),
+            })),
+            hir => Ok(hir),
+        }
+    }
+
+    fn record(&mut self, region: Region) {
+        if let Some(trace) = &mut self.trace {
+            trace.regions.push(region);
+        }
+    }
+
+    // match the tokens statement by statement and parse the expansions again
+    fn resolve_unknown(
+        &mut self,
+        tokens: Vec<Token>,
+        scope: &Scope,
+        depth: usize,
+    ) -> Result<Hir, Errors> {
+        let mut block = vec![];
+        let mut pos = 0;
+
+        while pos < tokens.len() {
+            if is_sep(&tokens[pos]) {
+                pos += 1;
+                continue;
+            }
+
+            let results: Vec<_> = scope
+                .iter()
+                .map(|entry| (entry, matches(&entry.decl, &tokens[pos..], &self.openers)))
+                .collect();
+
+            // the highest priority wins, then the most specific one, then the one consuming
+            // the most tokens. Candidates which are ranked equally are ambiguous.
+            let mut candidates: Vec<_> = results
+                .iter()
+                .filter_map(|(entry, result)| result.as_ref().ok().map(|m| (*entry, m)))
+                .collect();
+
+            let rank =
+                |entry: &Entry, m: &Match| (entry.decl.priority, entry.decl.specificity(), m.len);
+            if let Some(best) = candidates.iter().map(|(entry, m)| rank(entry, m)).max() {
+                candidates.retain(|(entry, m)| rank(entry, m) == best);
+            }
+
+            // the same macro can be visible under multiple names
+            let mut seen = vec![];
+            candidates.retain(|(entry, _)| {
+                let key = (&entry.module, &entry.decl.ident);
+                let unique = !seen.contains(&key);
+                seen.push(key);
+
+                unique
+            });
+
+            let lno = tokens[pos].lno.end_at(&tokens[tokens.len() - 1].lno);
+            let mut region = Region {
+                lno,
+                tokens: tokens[pos..].to_vec(),
+                attempts: results
+                    .iter()
+                    .map(|(entry, result)| {
+                        let candidate = candidates.iter().any(|(candidate, _)| {
+                            candidate.module == entry.module
+                                && candidate.decl.ident == entry.decl.ident
+                        });
+
+                        let failure = match result {
+                            Err(failure) => Some(*failure.clone()),
+                            Ok(_) if candidate && candidates.len() == 1 => None,
+                            Ok(_) if candidate => Some(Failure::Ambiguous),
+                            Ok(_) => Some(Failure::Outranked),
+                        };
+
+                        Attempt {
+                            ident: entry.decl.ident.clone(),
+                            module: entry.module.clone(),
+                            failure,
+                        }
+                    })
+                    .collect(),
+                expansion: None,
+            };
+
+            let builtin = match candidates.as_slice() {
+                [] => self.builtin.and_then(|builtin| builtin(&tokens[pos..])),
+                _ => None,
+            };
+            if let Some(len) = builtin {
+                let statement = tokens[pos..pos + len].to_vec();
+                block.push(Hir::Unknown(Unknown::Tokens(statement)));
+
+                pos += len;
+                continue;
+            }
+
+            let kind = match candidates.as_slice() {
+                [] => Some(ErrorKindMacro::NoMatch),
+                [(entry, _)] if depth >= RECURSION_LIMIT => Some(ErrorKindMacro::RecursionLimit {
+                    ident: entry.decl.ident.clone(),
+                }),
+                [_] => None,
+                _ => Some(ErrorKindMacro::Ambiguous {
+                    candidates: candidates
+                        .iter()
+                        .map(|(entry, _)| (entry.module.join("::"), entry.decl.ident.clone()))
+                        .collect(),
+                }),
+            };
+
+            if let Some(kind) = kind {
+                self.record(region);
+
+                return Err(Errors::from(Error::new_from_kind(
+                    Some(lno),
+                    ErrorKind::Macro(kind),
+                )));
+            }
+
+            let (entry, m) = candidates[0];
+
+            self.counter += 1;
+            let invocation = &tokens[pos..pos + m.len];
+            let expanded = expand(&entry.decl, &m.bindings, invocation, self.counter);
+
+            // the expansion is resolved with the macros visible to the invocation
+            // and the macros visible in the module the macro has been declared in.
+            let mut inner = scope.clone();
+            for visible in self.scopes.get(&entry.module).cloned().unwrap_or_default() {
+                if inner.iter().all(|existing| existing.name != visible.name) {
+                    inner.push(visible);
+                }
+            }
+
+            // the regions of the expansion are nested in the region of the invocation
+            let parent = self.trace.as_mut().map(mem::take);
+            let resolved = parser::parse_terms(expanded.clone(), self.openers.clone())
+                .and_then(|hir| self.resolve_hir(hir, &inner, depth + 1));
+
+            if let (Some(parent), Some(trace)) = (parent, self.trace.as_mut()) {
+                region.lno = invocation[0].lno.end_at(&invocation[m.len - 1].lno);
+                region.tokens = invocation.to_vec();
+                region.expansion = Some(Expansion {
+                    ident: entry.decl.ident.clone(),
+                    module: entry.module.clone(),
+                    tokens: expanded,
+                    regions: mem::replace(trace, parent).regions,
+                });
+
+                self.record(region);
+            }
+
+            match resolved? {
+                Hir::Control(Control::Block { terms }) => block.extend(terms),
+                hir => block.push(hir),
+            }
+
+            pos += m.len;
+        }
+
+        Ok(Hir::Control(Control::Block { terms: block }))
+    }
+}
+
+//region Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dir::Directory;
+    use indoc::indoc;
+    use lexer::Lexer;
+    use parser::disp::CompactRepresentation;
+
+    fn resolve_in(directory: &Directory, main: &str, code: &str) -> Result<Hir, Errors> {
+        let module = parser::parse_library(main)?;
+        let mut resolver = Resolver::new(Scopes::load(&module, directory)?);
+
+        let hir = parser::parse_terms(Lexer::new(code).collect(), resolver.openers())?;
+        resolver.resolve(hir)
+    }
+
+    fn resolve(macros: &str, code: &str) -> Result<Hir, Errors> {
+        resolve_in(&Directory::new(), macros, code)
+    }
+
+    fn std() -> Directory {
+        let mut directory = Directory::new();
+        directory.insert(
+            &["std", "macros", "inline"],
+            include_str!("../../../../lib/std/macros/inline.lp"),
+        );
+        directory.insert(
+            &["std", "macros", "assign"],
+            include_str!("../../../../lib/std/macros/assign.lp"),
+        );
+        directory.insert(
+            &["std", "macros", "ifelse"],
+            include_str!("../../../../lib/std/macros/ifelse.lp"),
+        );
+
+        directory
+    }
+
+    // the macro the statement is expanded with and its substitution, the substitution is resolved
+    // as well but not part of the result.
+    fn substitution(module: &str, code: &str) -> (String, Vec<String>) {
+        let main = format!("from std::macros::{} macro import *", module);
+        let module = parser::parse_library(&main).unwrap();
+        let mut resolver = Resolver::new(Scopes::load(&module, &std()).unwrap()).with_trace();
+
+        let hir = parser::parse_terms(Lexer::new(code).collect(), resolver.openers()).unwrap();
+        resolver.resolve(hir).unwrap();
+
+        let trace = resolver.trace().unwrap();
+        let expansion = trace.regions[0].expansion.as_ref().unwrap();
+        (expansion.ident.clone(), trace::lines(&expansion.tokens))
+    }
+
+    #[test]
+    fn resolve_simple() {
+        let hir = resolve(
+            indoc!(
+                "
+                @macro inlineIncrement
+                    %1.i++
+                @sub
+                    %1.i := %1.i + 1
+                @end"
+            ),
+            "x++",
+        )
+        .unwrap();
+
+        assert_eq!(hir.compact(None), "Assign@[0:0->0:3]: x := x + 1")
+    }
+
+    #[test]
+    fn resolve_nested_expansion() {
+        let hir = resolve(
+            indoc!(
+                "
+                @macro inlineIncrement
+                    %1.i++
+                @sub
+                    %1.i := %1.i + 1
+                @end
+
+                @macro inlineDoubleIncrement
+                    %1.i+++
+                @sub
+                    %1.i++
+                    %1.i++
+                @end"
+            ),
+            "LOOP y DO\n x+++\n END",
+        )
+        .unwrap();
+
+        assert_eq!(
+            hir.compact(None),
+            indoc!(
+                "
+                Loop@[0:0->2:4]:
+                  Ident: y
+                  Terms:
+                  Assign@[1:1->1:5]: x := x + 1
+                  Assign@[1:1->1:5]: x := x + 1
+                "
+            )
+        )
+    }
+
+    #[test]
+    fn resolve_hygiene() {
+        let hir = resolve(
+            indoc!(
+                "
+                @macro copy
+                    %1.i <- %2.i
+                @sub
+                    $3.i := %2.i + 0
+                    %1.i := $3.i + 0
+                @end"
+            ),
+            "x <- y\nz <- x",
+        )
+        .unwrap();
+
+        assert_eq!(
+            hir.compact(None),
+            indoc!(
+                "
+                Assign@[0:0->0:6]: _copy_1_3 := y + 0
+                Assign@[0:0->0:6]: x := _copy_1_3 + 0
+                Assign@[1:0->1:6]: _copy_2_3 := x + 0
+                Assign@[1:0->1:6]: z := _copy_2_3 + 0"
+            )
+        )
+    }
+
+    #[test]
+    fn resolve_priority() {
+        let macros = indoc!(
+            "
+            @macro/1 low
+                %1.i++
+            @sub
+                %1.i := %1.i + 1
+            @end
+
+            @macro/2 high
+                %1.i++
+            @sub
+                %1.i := %1.i + 2
+            @end"
+        );
+
+        let hir = resolve(macros, "x++").unwrap();
+        assert_eq!(hir.compact(None), "Assign@[0:0->0:3]: x := x + 2")
+    }
+
+    #[test]
+    fn resolve_specificity() {
+        let macros = indoc!(
+            "
+            @macro addValue
+                %1.i += %2.p
+            @sub
+                %1.i := %1.i + 2
+            @end
+
+            @macro addOne
+                %1.i += 1
+            @sub
+                %1.i := %1.i + 1
+            @end"
+        );
+
+        let hir = resolve(macros, "x += 1").unwrap();
+        assert_eq!(hir.compact(None), "Assign@[0:0->0:6]: x := x + 1");
+
+        let hir = resolve(macros, "x += y").unwrap();
+        assert_eq!(hir.compact(None), "Assign@[0:0->0:6]: x := x + 2");
+    }
+
+    #[test]
+    fn resolve_ambiguous() {
+        let mut directory = Directory::new();
+        directory.insert(
+            &["course", "inline"],
+            indoc!(
+                "
+                @macro inlineIncrement
+                    %1.i++
+                @sub
+                    %1.i := %1.i + 1
+                @end"
+            ),
+        );
+        directory.insert(
+            &["course", "util"],
+            indoc!(
+                "
+                @macro increment
+                    %1.i++
+                @sub
+                    %1.i := %1.i + 1
+                @end"
+            ),
+        );
+
+        let main = indoc!(
+            "
+            from course::inline macro import *
+            from course::util macro import *"
+        );
+
+        let errors = resolve_in(&directory, main, "x++").unwrap_err();
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.kind.clone())
+                .collect::<Vec<_>>(),
+            vec![ErrorKind::Macro(ErrorKindMacro::Ambiguous {
+                candidates: vec![
+                    ("course::inline".to_string(), "inlineIncrement".to_string()),
+                    ("course::util".to_string(), "increment".to_string())
+                ]
+            })]
+        );
+    }
+
+    #[test]
+    fn resolve_case_insensitive() {
+        let macros = |modifier: &str| {
+            format!(
+                indoc!(
+                    "
+                    @macro{} ifNotZero
+                        IF %1.i != 0 THEN
+                            %2.t
+                        END
+                    @sub
+                        LOOP %1.i DO
+                            %2.t
+                        END
+                    @end"
+                ),
+                modifier
+            )
+        };
+        let code = indoc!(
+            "
+            if x != 0 Then
+                y := y + 1
+            END"
+        );
+
+        let hir = resolve(&macros("/i"), code).unwrap();
+        assert_eq!(
+            hir.compact(None),
+            indoc!(
+                "
+                Loop@[0:0->2:3]:
+                  Ident: x
+                  Terms:
+                  Assign@[1:4->1:14]: y := y + 1\n"
+            )
+        );
+
+        // without the modifier `if` does not even start the macro
+        assert!(resolve(&macros(""), code).is_err());
+        assert!(resolve(
+            &macros(""),
+            &code.replace("if", "IF").replace("Then", "THEN")
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn resolve_trace() {
+        let module = parser::parse_library(indoc!(
+            "
+            @macro inlineIncrement
+                %1.i++
+            @sub
+                %1.i := %1.i + 1
+            @end
+
+            @macro inlineDouble
+                %1.i += 2
+            @sub
+                %1.i++
+                %1.i++
+            @end"
+        ))
+        .unwrap();
+        let mut resolver =
+            Resolver::new(Scopes::load(&module, &Directory::new()).unwrap()).with_trace();
+
+        let hir = parser::parse_terms(Lexer::new("x += 2\ny += 3").collect(), resolver.openers())
+            .unwrap();
+        assert!(resolver.resolve(hir).is_err());
+
+        // the trace is kept, even if the resolution failed
+        assert_eq!(
+            resolver.trace().unwrap().compact(None),
+            indoc!(
+                "
+                Region@[1:1->1:7]: x += 2
+                  - main::inlineIncrement: expected `+`, found `=`
+                  + main::inlineDouble
+                    x ++
+                    x ++
+                    Region@[1:1->1:7]: x ++
+                      + main::inlineIncrement
+                      - main::inlineDouble: expected `=`, found `+`
+                        x := x + 1
+                    Region@[1:1->1:7]: x ++
+                      + main::inlineIncrement
+                      - main::inlineDouble: expected `=`, found `+`
+                        x := x + 1
+                Region@[2:1->2:7]: y += 3
+                  - main::inlineIncrement: expected `+`, found `=`
+                  - main::inlineDouble: expected `2`, found `3`
+                "
+            )
+        );
+    }
+
+    #[test]
+    fn resolve_multiline() {
+        let macros = indoc!(
+            "
+            @macro ifNotZero
+                IF %1.i != 0 THEN
+                    %2.t
+                END
+            @sub
+                $3.i := %1.i + 0
+                LOOP $3.i DO
+                    %2.t
+                END
+            @end"
+        );
+        let code = indoc!(
+            "
+            LOOP y DO
+                IF x != 0 THEN
+                    IF z != 0 THEN
+                        z := z - 1
+                    END
+                END
+            END"
+        );
+
+        let hir = resolve(macros, code).unwrap();
+        assert_eq!(
+            hir.compact(None),
+            indoc!(
+                "
+                Loop@[0:0->6:3]:
+                  Ident: y
+                  Terms:
+                  Assign@[1:4->5:7]: _ifNotZero_1_3 := x + 0
+                    Loop@[1:4->5:7]:
+                      Ident: _ifNotZero_1_3
+                      Terms:
+                        Assign@[2:8->4:11]: _ifNotZero_2_3 := z + 0
+                            Loop@[2:8->4:11]:
+                              Ident: _ifNotZero_2_3
+                              Terms:
+                                  Assign@[3:12->3:22]: z := z - 1\n\n\n"
+            )
+        )
+    }
+
+    #[test]
+    fn resolve_module_scope() {
+        let mut directory = Directory::new();
+        directory.insert(
+            &["course", "inline"],
+            indoc!(
+                "
+                @macro inlineIncrement
+                    %1.i++
+                @sub
+                    %1.i := %1.i + 1
+                @end"
+            ),
+        );
+        directory.insert(
+            &["course", "double"],
+            indoc!(
+                "
+                from course::inline macro import *
+
+                @macro inlineDouble
+                    %1.i += %1.i
+                @sub
+                    $2.i := %1.i + 0
+                    LOOP $2.i DO
+                        %1.i++
+                    END
+                @end"
+            ),
+        );
+
+        let main = "from course::double macro import *";
+
+        // inlineIncrement is only visible to the module which declared inlineDouble
+        let hir = resolve_in(&directory, main, "x += x").unwrap();
+        assert_eq!(
+            hir.compact(None),
+            indoc!(
+                "
+                Assign@[0:0->0:6]: _inlineDouble_1_2 := x + 0
+                Loop@[0:0->0:6]:
+                  Ident: _inlineDouble_1_2
+                  Terms:
+                  Assign@[0:0->0:6]: x := x + 1\n"
+            )
+        );
+
+        let errors = resolve_in(&directory, main, "x++").unwrap_err();
+        assert!(errors
+            .iter()
+            .all(|error| error.kind == ErrorKind::Macro(ErrorKindMacro::NoMatch)))
+    }
+
+    #[test]
+    fn resolve_no_match() {
+        let errors = resolve(
+            indoc!(
+                "
+                @macro inlineIncrement
+                    %1.i++
+                @sub
+                    %1.i := %1.i + 1
+                @end"
+            ),
+            "x--",
+        )
+        .unwrap_err();
+
+        assert!(errors
+            .iter()
+            .all(|error| error.kind == ErrorKind::Macro(ErrorKindMacro::NoMatch)))
+    }
+
+    #[test]
+    fn resolve_placeholder_type() {
+        let macros = indoc!(
+            "
+            @macro addValue
+                %1.i += %2.n
+            @sub
+                %1.i := %1.i + %2.n
+            @end"
+        );
+
+        assert!(resolve(macros, "x += 2").is_ok());
+
+        let errors = resolve(macros, "x += y").unwrap_err();
+        assert!(errors
+            .iter()
+            .all(|error| error.kind == ErrorKind::Macro(ErrorKindMacro::NoMatch)))
+    }
+
+    #[test]
+    fn resolve_invalid_placeholder() {
+        let errors = resolve(
+            indoc!(
+                "
+                @macro inlineIncrement
+                    %1.i++
+                @sub
+                    %2.i := %1.i + 1
+                @end"
+            ),
+            "x++",
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.kind.clone())
+                .collect::<Vec<_>>(),
+            vec![ErrorKind::Macro(ErrorKindMacro::UndefinedPlaceholder {
+                ident: "inlineIncrement".to_string(),
+                placeholder: "%2/i".to_string()
+            })]
+        )
+    }
+
+    #[test]
+    fn resolve_conditional() {
+        let directory = std();
+        let main = "from std::macros::assign macro import *";
+
+        // @if %2.n == 0 decrements until zero, otherwise it resets and adds the value
+        let hir = resolve_in(&directory, main, "x := 0").unwrap();
+        assert_eq!(
+            hir.compact(None),
+            indoc!(
+                "
+                Loop@[0:0->0:6]:
+                  Ident: x
+                  Terms:
+                  Assign@[0:0->0:6]: x := x - 1
+                "
+            )
+        );
+
+        let hir = resolve_in(&directory, main, "x := 3").unwrap();
+        assert_eq!(
+            hir.compact(None),
+            indoc!(
+                "
+                Loop@[0:0->0:6]:
+                  Ident: x
+                  Terms:
+                  Assign@[0:0->0:6]: x := x - 1
+
+                Assign@[0:0->0:6]: x := x + 3"
+            )
+        );
+
+        // the value is stored in a temporary, the product of two identifiers is a macro as well
+        let hir = resolve_in(&directory, main, "x := y * 2").unwrap();
+        assert!(hir.compact(None).contains(
+            "Assign@[0:0->0:10]: _assignToIdentMulValue_1_4 := _assignToIdentMulValue_1_4 + 2"
+        ));
+
+        // y is not a value, so inlineOp takes the @else branch and copies it
+        let main = "from std::macros::inline macro import *";
+        let hir = resolve_in(&directory, main, "x += y").unwrap();
+        assert!(hir
+            .compact(None)
+            .contains("Assign@[0:0->0:6]: _inlineOp_1_3 := y + 0"));
+    }
+
+    #[test]
+    fn resolve_group_optional() {
+        let directory = std();
+        let main = "from std::macros::ifelse macro import *";
+
+        // without ELSE the optional group of the substitution is not repeated
+        let hir = resolve_in(&directory, main, "IF x != 0 THEN\n y := y + 1\nEND").unwrap();
+        let compact = hir.compact(None);
+        assert!(compact.ends_with(indoc!(
+            "
+            Loop@[0:0->2:3]:
+              Ident: _ifElseNotZero_1_6
+              Terms:
+              Assign@[1:1->1:11]: y := y + 1
+            "
+        )));
+        assert!(!compact.contains("y := y + 2"));
+
+        let hir = resolve_in(
+            &directory,
+            main,
+            "IF x != 0 THEN\n y := y + 1\nELSE\n y := y + 2\nEND",
+        )
+        .unwrap();
+        assert!(hir.compact(None).ends_with(indoc!(
+            "
+            Loop@[0:0->4:3]:
+              Ident: _ifElseNotZero_1_6
+              Terms:
+              Assign@[1:1->1:11]: y := y + 1
+
+            Loop@[0:0->4:3]:
+              Ident: _ifElseNotZero_1_7
+              Terms:
+              Assign@[3:1->3:11]: y := y + 2
+            "
+        )));
+    }
+
+    #[test]
+    fn resolve_group_repetition() {
+        let macros = indoc!(
+            "
+            @macro decrementAll
+                DEC @( %1.i @),+
+            @sub
+                @( %1.i := %1.i - 1 @);
+            @end
+
+            @macro decrementTwice
+                DEC2 @( %1.i @),+
+            @sub
+                DEC @( %1.i @)
+                DEC @( %1.i @)
+            @end"
+        );
+
+        let hir = resolve(macros, "DEC a, b, c").unwrap();
+        assert_eq!(
+            hir.compact(None),
+            indoc!(
+                "
+                Assign@[0:4->0:11]: a := a - 1
+                Assign@[0:7->0:11]: b := b - 1
+                Assign@[0:10->0:11]: c := c - 1"
+            )
+        );
+
+        // the group without a separator is joined with the separator of the pattern
+        let hir = resolve(macros, "DEC2 a, b").unwrap();
+        assert_eq!(
+            hir.compact(None),
+            indoc!(
+                "
+                Assign@[0:5->0:9]: a := a - 1
+                Assign@[0:8->0:9]: b := b - 1
+                Assign@[0:5->0:9]: a := a - 1
+                Assign@[0:8->0:9]: b := b - 1"
+            )
+        );
+
+        assert!(resolve(macros, "DEC").is_err());
+    }
+
+    #[test]
+    fn resolve_recursion_limit() {
+        let errors = resolve(
+            indoc!(
+                "
+                @macro forever
+                    %1.i++
+                @sub
+                    %1.i++
+                @end"
+            ),
+            "x++",
+        )
+        .unwrap_err();
+
+        assert!(errors.iter().all(|error| error.kind
+            == ErrorKind::Macro(ErrorKindMacro::RecursionLimit {
+                ident: "forever".to_string()
+            })))
+    }
+
+    #[test]
+    fn resolve_builtin() {
+        let module = parser::parse_library(indoc!(
+            "
+            @macro double
+                %1.i *= 2
+            @sub
+                %1.i := %1.i + 0
+                PRINT %1.i
+            @end"
+        ))
+        .unwrap();
+
+        // PRINT <ident> is a statement of the language the macros are used in
+        let builtin: Builtin = |tokens| match tokens {
+            [print, _, ..] if print.content == "PRINT" => Some(2),
+            _ => None,
+        };
+        let mut resolver =
+            Resolver::new(Scopes::load(&module, &Directory::new()).unwrap()).with_builtin(builtin);
+
+        let code = Lexer::new(
+            "x *= 2
+PRINT y",
+        )
+        .collect();
+        let hir = parser::parse_terms(code, resolver.openers()).unwrap();
+        let terms = match resolver.resolve(hir).unwrap() {
+            Hir::Control(Control::Block { terms }) => terms,
+            hir => panic!("expected a block, got {:?}", hir),
+        };
+
+        let unknown: Vec<_> = terms
+            .iter()
+            .filter_map(|term| match term {
+                Hir::Unknown(Unknown::Tokens(tokens)) => Some(
+                    tokens
+                        .iter()
+                        .map(|token| token.content.as_str())
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                ),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(terms.len(), 3);
+        assert_eq!(unknown, vec!["PRINT x", "PRINT y"]);
+
+        // without the builtin statements nothing matches
+        let errors = resolve(
+            indoc!(
+                "
+                @macro double
+                    %1.i *= 2
+                @sub
+                    PRINT %1.i
+                @end"
+            ),
+            "x *= 2",
+        )
+        .unwrap_err();
+        assert!(errors
+            .iter()
+            .all(|error| error.kind == ErrorKind::Macro(ErrorKindMacro::NoMatch)));
+    }
+
+    #[test]
+    fn resolve_std_assign() {
+        let cases = [
+            ("x := y", "assignToIdent", vec!["x := y + 0"]),
+            (
+                "x := 0",
+                "assignToValue",
+                vec!["LOOP x DO", "x := x - 1", "END"],
+            ),
+            ("x := 3", "assignToValue", vec!["x := 0", "x := x + 3"]),
+            (
+                "x := y - z",
+                "assignToIdentOpIdent",
+                vec!["x := y", "LOOP z DO", "x := x - 1", "END"],
+            ),
+            // the product used to be matched by assignToIdentOpIdent, which added z
+            (
+                "x := y * z",
+                "assignToIdentMulIdent",
+                vec!["x := 0", "LOOP y DO", "x := x + z", "END"],
+            ),
+            (
+                "x := y * 3",
+                "assignToIdentMulValue",
+                vec![
+                    "_assignToIdentMulValue_1_4 := 3",
+                    "x := y * _assignToIdentMulValue_1_4",
+                ],
+            ),
+        ];
+
+        for (code, ident, lines) in cases {
+            let (expanded, substituted) = substitution("assign", code);
+            assert_eq!(expanded, ident, "{}", code);
+            assert_eq!(substituted, lines, "{}", code);
+        }
+    }
+
+    #[test]
+    fn resolve_std_inline() {
+        let cases = [
+            ("x++", "inlineIncrement", vec!["x := x + 1"]),
+            ("x--", "inlineDecr", vec!["x := x - 1"]),
+            // the temporary is assigned, it used to be incremented by the value
+            (
+                "x += 3",
+                "inlineOp",
+                vec![
+                    "_inlineOp_1_3 := 3",
+                    "LOOP _inlineOp_1_3 DO",
+                    "x := x + 1",
+                    "END",
+                ],
+            ),
+            (
+                "x -= y",
+                "inlineOp",
+                vec![
+                    "_inlineOp_1_3 := y + 0",
+                    "LOOP _inlineOp_1_3 DO",
+                    "x := x - 1",
+                    "END",
+                ],
+            ),
+        ];
+
+        for (code, ident, lines) in cases {
+            let (expanded, substituted) = substitution("inline", code);
+            assert_eq!(expanded, ident, "{}", code);
+            assert_eq!(substituted, lines, "{}", code);
+        }
+    }
+
+    #[test]
+    fn resolve_std_ifelse() {
+        let cases = [
+            (
+                "IF x != 0 THEN\n y := 1\nELSE\n y := 2\nEND",
+                "ifElseNotZero",
+                vec![
+                    "_ifElseNotZero_1_5 := x",
+                    "_ifElseNotZero_1_6 := 0",
+                    "_ifElseNotZero_1_7 := 1",
+                    "LOOP _ifElseNotZero_1_5 DO",
+                    "_ifElseNotZero_1_6 := 1",
+                    "_ifElseNotZero_1_7 := 0",
+                    "END",
+                    "LOOP _ifElseNotZero_1_6 DO",
+                    "y := 1",
+                    "END",
+                    "LOOP _ifElseNotZero_1_7 DO",
+                    "y := 2",
+                    "END",
+                ],
+            ),
+            (
+                "IF x > y THEN\n z := 1\nEND",
+                "ifElseGreater",
+                vec![
+                    "_ifElseGreater_1_5 := x",
+                    "_ifElseGreater_1_6 := _ifElseGreater_1_5 - y",
+                    "IF _ifElseGreater_1_6 != 0 THEN",
+                    "z := 1",
+                    "END",
+                ],
+            ),
+            (
+                "IF x < y THEN\n z := 1\nEND",
+                "ifElseLess",
+                vec!["IF y > x THEN", "z := 1", "END"],
+            ),
+            (
+                "IF x >= y THEN\n z := 1\nEND",
+                "ifElseGreaterThan",
+                vec![
+                    "_ifElseGreaterThan_1_5 := x + 1",
+                    "IF _ifElseGreaterThan_1_5 > y THEN",
+                    "z := 1",
+                    "END",
+                ],
+            ),
+            (
+                "IF x <= y THEN\n z := 1\nEND",
+                "ifElseLessThan",
+                vec![
+                    "_ifElseLessThan_1_5 := y + 1",
+                    "IF x < _ifElseLessThan_1_5 THEN",
+                    "z := 1",
+                    "END",
+                ],
+            ),
+            // without ELSE the inner conditional used to be missing its END
+            (
+                "IF x == y THEN\n z := 1\nEND",
+                "ifElseEqual",
+                vec!["IF x >= y THEN", "IF x <= y THEN", "z := 1", "END", "END"],
+            ),
+            (
+                "IF x == y THEN\n z := 1\nELSE\n z := 2\nEND",
+                "ifElseEqual",
+                vec![
+                    "IF x >= y THEN",
+                    "IF x <= y THEN",
+                    "z := 1",
+                    "ELSE",
+                    "z := 2",
+                    "END",
+                    "ELSE",
+                    "z := 2",
+                    "END",
+                ],
+            ),
+            // a != b used to be a == b with the branches swapped, which lost the THEN branch
+            (
+                "IF x != y THEN\n z := 1\nEND",
+                "ifElseNotEqual",
+                vec![
+                    "_ifElseNotEqual_1_5 := x",
+                    "_ifElseNotEqual_1_6 := _ifElseNotEqual_1_5 - y",
+                    "_ifElseNotEqual_1_7 := y",
+                    "_ifElseNotEqual_1_8 := _ifElseNotEqual_1_7 - x",
+                    "_ifElseNotEqual_1_6 := _ifElseNotEqual_1_6 + _ifElseNotEqual_1_8",
+                    "IF _ifElseNotEqual_1_6 != 0 THEN",
+                    "z := 1",
+                    "END",
+                ],
+            ),
+        ];
+
+        for (code, ident, lines) in cases {
+            let (expanded, substituted) = substitution("ifelse", code);
+            assert_eq!(expanded, ident, "{}", code);
+            assert_eq!(substituted, lines, "{}", code);
+        }
+    }
+}
+//endregion
@@ -0,0 +1,342 @@
+use crate::dir::Directory;
+use crate::macros::validate::validate;
+use either::Either;
+use module::imp::Stmt;
+use module::{MacroDecl, Module};
+use std::collections::HashMap;
+use variants::err::{ErrorKind, ErrorKindMacro};
+use variants::{Error, Errors, LineNo};
+
+pub const MAIN: &str = "main";
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    // the name the macro is visible as, differs from the declaration if aliased
+    pub name: String,
+    // the module the macro has been declared in
+    pub module: Vec<String>,
+
+    pub decl: MacroDecl,
+}
+
+// every macro visible in a module, either declared or imported
+pub type Scope = Vec<Entry>;
+
+fn display(path: &[String]) -> String {
+    path.join("::")
+}
+
+fn error(lno: LineNo, kind: ErrorKindMacro) -> Errors {
+    Errors::from(Error::new_from_kind(Some(lno), ErrorKind::Macro(kind)))
+}
+
+fn push(scope: &mut Scope, entry: Entry, module: &[String], lno: LineNo) -> Result<(), Errors> {
+    match scope.iter().find(|existing| existing.name == entry.name) {
+        // the same macro, imported through different modules
+        Some(existing)
+            if existing.module == entry.module && existing.decl.ident == entry.decl.ident =>
+        {
+            Ok(())
+        }
+        Some(_) => Err(error(
+            lno,
+            ErrorKindMacro::Collision {
+                module: display(module),
+                ident: entry.name,
+            },
+        )),
+        None => {
+            scope.push(entry);
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Scopes {
+    scopes: HashMap<Vec<String>, Scope>,
+}
+
+impl Scopes {
+    pub fn main() -> Vec<String> {
+        vec![MAIN.to_string()]
+    }
+
+    // resolve the scope of the main module and every module it imports macros from
+    pub fn load(main: &Module, directory: &Directory) -> Result<Self, Errors> {
+        let mut scopes = Self::default();
+        scopes.insert(&Self::main(), main, directory, &mut vec![])?;
+
+        Ok(scopes)
+    }
+
+    pub fn get(&self, module: &[String]) -> Option<&Scope> {
+        self.scopes.get(module)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.scopes.values().flatten()
+    }
+
+    fn load_module(
+        &mut self,
+        stmt: &Stmt,
+        directory: &Directory,
+        history: &mut Vec<Vec<String>>,
+    ) -> Result<(), Errors> {
+        if self.scopes.contains_key(&stmt.path) {
+            return Ok(());
+        }
+
+        if history.contains(&stmt.path) {
+            return Err(error(
+                stmt.lno,
+                ErrorKindMacro::CircularImport {
+                    module: display(&stmt.path),
+                    history: history.iter().map(|path| display(path)).collect(),
+                },
+            ));
+        }
+
+        let source = directory.get(&stmt.path).ok_or_else(|| {
+            error(
+                stmt.lno,
+                ErrorKindMacro::ModuleNotFound {
+                    module: display(&stmt.path),
+                },
+            )
+        })?;
+        let module = parser::parse_library(source)?;
+
+        self.insert(&stmt.path, &module, directory, history)
+    }
+
+    fn insert(
+        &mut self,
+        path: &[String],
+        module: &Module,
+        directory: &Directory,
+        history: &mut Vec<Vec<String>>,
+    ) -> Result<(), Errors> {
+        let mut scope = Scope::new();
+        let mut errors = Errors::new();
+
+        for decl in &module.decl.macr {
+            if let Err(err) = validate(decl) {
+                errors += err;
+            }
+
+            let entry = Entry {
+                name: decl.ident.clone(),
+                module: path.to_vec(),
+                decl: decl.clone(),
+            };
+
+            if let Err(err) = push(&mut scope, entry, path, decl.lno) {
+                errors += err;
+            }
+        }
+
+        history.push(path.to_vec());
+        for stmt in &module.imp.macr {
+            if let Err(err) = self.load_module(stmt, directory, history) {
+                errors += err;
+                continue;
+            }
+
+            let imported = self.scopes[&stmt.path].clone();
+            let entries = match &stmt.imports {
+                // only the declared macros are imported, unless the module does not declare any,
+                // then it is only used to bundle other modules (like the prelude)
+                Either::Right(_) if imported.iter().any(|entry| entry.module == stmt.path) => {
+                    imported
+                        .into_iter()
+                        .filter(|entry| entry.module == stmt.path)
+                        .collect()
+                }
+                Either::Right(_) => imported,
+                Either::Left(aliases) => {
+                    let mut entries = vec![];
+
+                    for alias in aliases {
+                        match imported.iter().find(|entry| entry.name == alias.ident) {
+                            Some(entry) => entries.push(Entry {
+                                name: alias.alias.clone().unwrap_or_else(|| entry.name.clone()),
+                                ..entry.clone()
+                            }),
+                            None => {
+                                errors += error(
+                                    stmt.lno,
+                                    ErrorKindMacro::NotFound {
+                                        module: display(&stmt.path),
+                                        ident: alias.ident.clone(),
+                                    },
+                                )
+                            }
+                        }
+                    }
+
+                    entries
+                }
+            };
+
+            for entry in entries {
+                if let Err(err) = push(&mut scope, entry, path, stmt.lno) {
+                    errors += err;
+                }
+            }
+        }
+        history.pop();
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        self.scopes.insert(path.to_vec(), scope);
+        Ok(())
+    }
+}
+
+//region Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    static INCREMENT: &str = indoc!(
+        "
+        @macro inlineIncrement
+            %1.i++
+        @sub
+            %1.i := %1.i + 1
+        @end
+        "
+    );
+
+    fn load(main: &str, directory: &Directory) -> Result<Scopes, Errors> {
+        let module = parser::parse_library(main)?;
+
+        Scopes::load(&module, directory)
+    }
+
+    fn kinds(errors: Errors) -> Vec<ErrorKind> {
+        errors.iter().map(|error| error.kind.clone()).collect()
+    }
+
+    #[test]
+    fn scope_wildcard() {
+        let mut directory = Directory::new();
+        directory.insert(&["course", "inline"], INCREMENT);
+
+        let scopes = load("from course::inline macro import *", &directory).unwrap();
+        let main = scopes.get(&Scopes::main()).unwrap();
+
+        assert_eq!(main.len(), 1);
+        assert_eq!(main[0].name, "inlineIncrement");
+        assert_eq!(main[0].module, vec!["course", "inline"]);
+    }
+
+    #[test]
+    fn scope_alias() {
+        let mut directory = Directory::new();
+        directory.insert(&["course", "inline"], INCREMENT);
+
+        let scopes = load(
+            "from course::inline macro import inlineIncrement as incr",
+            &directory,
+        )
+        .unwrap();
+        let main = scopes.get(&Scopes::main()).unwrap();
+
+        assert_eq!(main[0].name, "incr");
+        assert_eq!(main[0].decl.ident, "inlineIncrement");
+    }
+
+    #[test]
+    fn scope_reexport() {
+        let mut directory = Directory::new();
+        directory.insert(&["course", "inline"], INCREMENT);
+        directory.insert(&["course", "a"], "from course::inline macro import *");
+        directory.insert(&["course", "b"], "from course::inline macro import *");
+
+        // the same macro imported twice is not a collision
+        let scopes = load(
+            "from course::a macro import *\nfrom course::b macro import *",
+            &directory,
+        )
+        .unwrap();
+
+        assert_eq!(scopes.get(&Scopes::main()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn scope_module_not_found() {
+        let errors = load("from course::inline macro import *", &Directory::new()).unwrap_err();
+
+        assert_eq!(
+            kinds(errors),
+            vec![ErrorKind::Macro(ErrorKindMacro::ModuleNotFound {
+                module: "course::inline".to_string()
+            })]
+        );
+    }
+
+    #[test]
+    fn scope_not_found() {
+        let mut directory = Directory::new();
+        directory.insert(&["course", "inline"], INCREMENT);
+
+        let errors = load(
+            "from course::inline macro import inlineDecrement",
+            &directory,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            kinds(errors),
+            vec![ErrorKind::Macro(ErrorKindMacro::NotFound {
+                module: "course::inline".to_string(),
+                ident: "inlineDecrement".to_string()
+            })]
+        );
+    }
+
+    #[test]
+    fn scope_collision() {
+        let mut directory = Directory::new();
+        directory.insert(&["course", "inline"], INCREMENT);
+
+        let main = format!("from course::inline macro import *\n{}", INCREMENT);
+        let errors = load(&main, &directory).unwrap_err();
+
+        assert_eq!(
+            kinds(errors),
+            vec![ErrorKind::Macro(ErrorKindMacro::Collision {
+                module: "main".to_string(),
+                ident: "inlineIncrement".to_string()
+            })]
+        );
+    }
+
+    #[test]
+    fn scope_circular() {
+        let mut directory = Directory::new();
+        directory.insert(&["course", "a"], "from course::b macro import *");
+        directory.insert(&["course", "b"], "from course::a macro import *");
+
+        let errors = load("from course::a macro import *", &directory).unwrap_err();
+
+        assert_eq!(
+            kinds(errors),
+            vec![ErrorKind::Macro(ErrorKindMacro::CircularImport {
+                module: "course::a".to_string(),
+                history: vec![
+                    "main".to_string(),
+                    "course::a".to_string(),
+                    "course::b".to_string()
+                ]
+            })]
+        );
+    }
+}
+//endregion
@@ -0,0 +1,181 @@
+use crate::macros::matcher::is_sep;
+use lexer::{Placeholder, Token};
+use parser::disp::CompactRepresentation;
+use variants::LineNo;
+
+static INDENTATION_LEVEL: usize = 2;
+
+// why a macro has not been chosen for a statement
+#[derive(Debug, Clone, PartialEq)]
+pub enum Failure {
+    // a literal token of the pattern is not found, None if the tokens ended before
+    Token {
+        expected: Token,
+        found: Option<Token>,
+    },
+    // the placeholder does not accept the tokens at this position
+    Placeholder {
+        placeholder: Placeholder,
+        found: Option<Token>,
+    },
+    // the pattern does not match as a whole, like a group which does not consume anything
+    Unmatched,
+    // the pattern matches, but another macro is ranked higher
+    Outranked,
+    // the pattern matches, but another macro is ranked equally
+    Ambiguous,
+}
+
+#[derive(Debug, Clone)]
+pub struct Attempt {
+    pub ident: String,
+    pub module: Vec<String>,
+
+    // None if the macro has been chosen
+    pub failure: Option<Failure>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Expansion {
+    pub ident: String,
+    pub module: Vec<String>,
+
+    // the substitution, after every placeholder has been replaced
+    pub tokens: Vec<Token>,
+    // the unknown terms of the substitution, which are resolved again
+    pub regions: Vec<Region>,
+}
+
+// a statement of unknown tokens and every macro which has been tried to resolve it
+#[derive(Debug, Clone)]
+pub struct Region {
+    pub lno: LineNo,
+    pub tokens: Vec<Token>,
+
+    pub attempts: Vec<Attempt>,
+    // None if no macro matches or the candidates are ambiguous
+    pub expansion: Option<Expansion>,
+}
+
+// the expansion tree of every unknown region, in order of appearance
+#[derive(Debug, Clone, Default)]
+pub struct Trace {
+    pub regions: Vec<Region>,
+}
+
+fn is_symbol(token: &Token) -> bool {
+    !token
+        .content
+        .chars()
+        .any(|char| char.is_alphanumeric() || char == '_')
+}
+
+// the tokens as source code, every statement on a separate line.
+// Symbols are lexed separately (`+=` is `+` and `=`) and are therefore joined again.
+pub(crate) fn lines(tokens: &[Token]) -> Vec<String> {
+    tokens
+        .split(is_sep)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let mut text = line[0].content.clone();
+
+            for window in line.windows(2) {
+                if !(is_symbol(&window[0]) && is_symbol(&window[1])) {
+                    text.push(' ');
+                }
+
+                text += &window[1].content;
+            }
+
+            text
+        })
+        .collect()
+}
+
+fn quote(token: &Option<Token>) -> String {
+    match token {
+        Some(token) if is_sep(token) => "end of statement".to_string(),
+        Some(token) => format!("`{}`", token.content),
+        None => "end of input".to_string(),
+    }
+}
+
+impl CompactRepresentation for Failure {
+    fn compact(&self, _: Option<usize>) -> String {
+        match self {
+            Failure::Token { expected, found } => {
+                format!("expected `{}`, found {}", expected.content, quote(found))
+            }
+            Failure::Placeholder { placeholder, found } => {
+                format!("{} does not accept {}", placeholder, quote(found))
+            }
+            Failure::Unmatched => "pattern does not match".to_string(),
+            Failure::Outranked => "outranked".to_string(),
+            Failure::Ambiguous => "ambiguous".to_string(),
+        }
+    }
+}
+
+// rows and cols start at 1, like the locations of the errors in lit check
+fn location(lno: &LineNo) -> String {
+    format!(
+        "[{}:{}->{}:{}]",
+        u32::from(lno.row.start()) + 1,
+        u32::from(lno.col.start()) + 1,
+        u32::from(lno.row.end()) + 1,
+        u32::from(lno.col.end()) + 1
+    )
+}
+
+impl CompactRepresentation for Region {
+    fn compact(&self, indent: Option<usize>) -> String {
+        let indent = indent.unwrap_or(0);
+        let pad = " ".repeat(indent);
+        let inner = " ".repeat(indent + INDENTATION_LEVEL);
+
+        let mut text = format!(
+            "{}Region@{}: {}\n",
+            pad,
+            location(&self.lno),
+            lines(&self.tokens).join("; ")
+        );
+
+        for attempt in &self.attempts {
+            let module = attempt.module.join("::");
+
+            text += &match &attempt.failure {
+                Some(failure) => format!(
+                    "{}- {}::{}: {}\n",
+                    inner,
+                    module,
+                    attempt.ident,
+                    failure.compact(None)
+                ),
+                None => format!("{}+ {}::{}\n", inner, module, attempt.ident),
+            };
+        }
+
+        if let Some(expansion) = &self.expansion {
+            let body = " ".repeat(indent + 2 * INDENTATION_LEVEL);
+
+            for line in lines(&expansion.tokens) {
+                text += &format!("{}{}\n", body, line);
+            }
+
+            for region in &expansion.regions {
+                text += &region.compact(Some(indent + 2 * INDENTATION_LEVEL));
+            }
+        }
+
+        text
+    }
+}
+
+impl CompactRepresentation for Trace {
+    fn compact(&self, indent: Option<usize>) -> String {
+        self.regions
+            .iter()
+            .map(|region| region.compact(indent))
+            .collect()
+    }
+}
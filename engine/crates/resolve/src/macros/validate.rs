@@ -0,0 +1,414 @@
+use lexer::{Kind, Placeholder, PlaceholderVariant};
+use module::mcr::Segment;
+use module::MacroDecl;
+use std::collections::HashMap;
+use variants::err::{ErrorKind, ErrorKindMacro};
+use variants::{Error, Errors, LineNo};
+
+struct Usage {
+    lno: LineNo,
+    placeholder: Placeholder,
+
+    // directly followed by an assignment
+    assigned: bool,
+    // operand of the condition of @if
+    condition: bool,
+    // the enclosing group, in order of appearance
+    group: Option<usize>,
+}
+
+#[derive(Default)]
+struct Walk {
+    usages: Vec<Usage>,
+    // the line of every group and if it is nested in another group
+    groups: Vec<(LineNo, bool)>,
+}
+
+fn is_assigned(segments: &[Segment], idx: usize) -> bool {
+    matches!(segments.get(idx + 1), Some(Segment::Token(token)) if token.kind == Kind::Assign)
+}
+
+impl Walk {
+    // every placeholder in order of appearance, including the ones in conditionals and groups
+    fn segments(&mut self, segments: &[Segment], group: Option<usize>) {
+        for (idx, segment) in segments.iter().enumerate() {
+            match segment {
+                Segment::Token(_) => {}
+                Segment::Placeholder { lno, placeholder } => self.usages.push(Usage {
+                    lno: *lno,
+                    placeholder: *placeholder,
+                    assigned: is_assigned(segments, idx),
+                    condition: false,
+                    group,
+                }),
+                Segment::Group { lno, segments, .. } => {
+                    self.groups.push((*lno, group.is_some()));
+                    self.segments(segments, Some(self.groups.len() - 1));
+                }
+                Segment::Conditional {
+                    condition,
+                    then,
+                    otherwise,
+                    ..
+                } => {
+                    for operand in condition.operands() {
+                        if let Segment::Placeholder { lno, placeholder } = operand {
+                            self.usages.push(Usage {
+                                lno: *lno,
+                                placeholder: *placeholder,
+                                assigned: false,
+                                condition: true,
+                                group,
+                            })
+                        }
+                    }
+
+                    self.segments(then, group);
+                    self.segments(otherwise, group);
+                }
+            }
+        }
+    }
+}
+
+fn walk(segments: &[Segment]) -> Walk {
+    let mut walk = Walk::default();
+    walk.segments(segments, None);
+
+    walk
+}
+
+// check the placeholders of a macro declaration, so that errors are reported when loading
+// the macro instead of silently never matching or producing garbage on expansion.
+pub(crate) fn validate(decl: &MacroDecl) -> Result<(), Errors> {
+    let mut errors = Errors::new();
+    let mut error = |lno: LineNo, kind: ErrorKindMacro| {
+        errors += Errors::from(Error::new_from_kind(Some(lno), ErrorKind::Macro(kind)))
+    };
+
+    // the placeholders of the pattern, with the group they have been declared in
+    let mut declared: HashMap<u32, (PlaceholderVariant, Option<usize>)> = HashMap::new();
+    for Usage {
+        lno,
+        placeholder,
+        group,
+        ..
+    } in walk(&decl.pattern).usages
+    {
+        match placeholder {
+            Placeholder::Match { variant, index } => {
+                // the same placeholder can be used multiple times, to match the same tokens
+                match declared.get(&index) {
+                    Some((expected, _)) if *expected != variant => error(
+                        lno,
+                        ErrorKindMacro::InconsistentPlaceholder {
+                            ident: decl.ident.clone(),
+                            placeholder: placeholder.to_string(),
+                            expected: expected.to_string(),
+                        },
+                    ),
+                    Some(_) => {}
+                    None => {
+                        declared.insert(index, (variant, group));
+                    }
+                }
+            }
+            Placeholder::Sub { .. } => error(
+                lno,
+                ErrorKindMacro::MisplacedPlaceholder {
+                    ident: decl.ident.clone(),
+                    placeholder: placeholder.to_string(),
+                },
+            ),
+        }
+    }
+
+    // temporary variables need to be assigned before they are used
+    let mut temporaries: HashMap<u32, PlaceholderVariant> = HashMap::new();
+    let sub = walk(&decl.sub);
+    // the groups of the pattern, which are repeated by the groups of the substitution
+    let mut repeats: Vec<Vec<usize>> = vec![vec![]; sub.groups.len()];
+
+    for Usage {
+        lno,
+        placeholder,
+        assigned,
+        condition,
+        group,
+    } in sub.usages
+    {
+        let (expected, variant) = match placeholder {
+            Placeholder::Match { variant, index } => match (declared.get(&index), group) {
+                // placeholders of a group in the pattern can only be used in a group
+                (Some((_, Some(_))), None) => {
+                    error(
+                        lno,
+                        ErrorKindMacro::UngroupedPlaceholder {
+                            ident: decl.ident.clone(),
+                            placeholder: placeholder.to_string(),
+                        },
+                    );
+                    continue;
+                }
+                (Some((expected, Some(declaration))), Some(group)) => {
+                    if !repeats[group].contains(declaration) {
+                        repeats[group].push(*declaration);
+                    }
+
+                    (Some(*expected), variant)
+                }
+                (declaration, _) => (declaration.map(|(expected, _)| *expected), variant),
+            },
+            // temporary variables have no value at compile-time
+            Placeholder::Sub { .. } if condition => {
+                error(
+                    lno,
+                    ErrorKindMacro::MisplacedPlaceholder {
+                        ident: decl.ident.clone(),
+                        placeholder: placeholder.to_string(),
+                    },
+                );
+                continue;
+            }
+            Placeholder::Sub { variant, index } => match temporaries.get(&index) {
+                Some(expected) => (Some(*expected), variant),
+                None if assigned => {
+                    temporaries.insert(index, variant);
+                    (Some(variant), variant)
+                }
+                None => (None, variant),
+            },
+        };
+
+        match expected {
+            None => error(
+                lno,
+                ErrorKindMacro::UndefinedPlaceholder {
+                    ident: decl.ident.clone(),
+                    placeholder: placeholder.to_string(),
+                },
+            ),
+            Some(expected) if expected != variant => error(
+                lno,
+                ErrorKindMacro::InconsistentPlaceholder {
+                    ident: decl.ident.clone(),
+                    placeholder: placeholder.to_string(),
+                    expected: expected.to_string(),
+                },
+            ),
+            Some(_) => {}
+        }
+    }
+
+    // every group of the substitution needs to repeat exactly one group of the pattern
+    for ((lno, nested), repeats) in sub.groups.into_iter().zip(repeats) {
+        if nested || repeats.len() != 1 {
+            error(
+                lno,
+                ErrorKindMacro::InvalidGroup {
+                    ident: decl.ident.clone(),
+                },
+            )
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+//region Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    fn check(source: &str) -> Vec<(u32, ErrorKind)> {
+        let module = parser::parse_library(source).unwrap();
+
+        match validate(&module.decl.macr[0]) {
+            Ok(_) => vec![],
+            Err(errors) => errors
+                .iter()
+                .map(|error| (error.lno.unwrap().row.start().into(), error.kind.clone()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn validate_valid() {
+        let errors = check(indoc!(
+            "
+            @macro copy
+                %1.i <- %2.i
+            @sub
+                $3.i := %2.i + 0
+                %1.i := $3.i + 0
+            @end"
+        ));
+
+        assert_eq!(errors, vec![]);
+    }
+
+    #[test]
+    fn validate_pattern() {
+        let errors = check(indoc!(
+            "
+            @macro add
+                %1.i := %1.n + $2.i
+            @sub
+                %1.i := 0
+            @end"
+        ));
+
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    1,
+                    ErrorKind::Macro(ErrorKindMacro::InconsistentPlaceholder {
+                        ident: "add".to_string(),
+                        placeholder: "%1/n".to_string(),
+                        expected: "i".to_string()
+                    })
+                ),
+                (
+                    1,
+                    ErrorKind::Macro(ErrorKindMacro::MisplacedPlaceholder {
+                        ident: "add".to_string(),
+                        placeholder: "$2/i".to_string()
+                    })
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_conditional() {
+        let errors = check(indoc!(
+            "
+            @macro assignToValue
+                %1.i := %2.n
+            @sub
+                $3.i := 0
+                @if %2.n == 0 and %4.n == $3.i
+                    %1.i := %1.i - 1
+                @else
+                    %1.i := %1.i + %2.n
+                @end
+            @end"
+        ));
+
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    4,
+                    ErrorKind::Macro(ErrorKindMacro::UndefinedPlaceholder {
+                        ident: "assignToValue".to_string(),
+                        placeholder: "%4/n".to_string()
+                    })
+                ),
+                (
+                    4,
+                    ErrorKind::Macro(ErrorKindMacro::MisplacedPlaceholder {
+                        ident: "assignToValue".to_string(),
+                        placeholder: "$3/i".to_string()
+                    })
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_group() {
+        let errors = check(indoc!(
+            "
+            @macro swap
+                SWAP @( %1.i @),+ WITH @( %2.i @),+
+            @sub
+                %1.i := 0
+                @( %1.i := %2.i + 0 @);
+                @( @( %2.i := 0 @) @)
+            @end"
+        ));
+
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    3,
+                    ErrorKind::Macro(ErrorKindMacro::UngroupedPlaceholder {
+                        ident: "swap".to_string(),
+                        placeholder: "%1/i".to_string()
+                    })
+                ),
+                (
+                    4,
+                    ErrorKind::Macro(ErrorKindMacro::InvalidGroup {
+                        ident: "swap".to_string()
+                    })
+                ),
+                (
+                    5,
+                    ErrorKind::Macro(ErrorKindMacro::InvalidGroup {
+                        ident: "swap".to_string()
+                    })
+                ),
+                (
+                    5,
+                    ErrorKind::Macro(ErrorKindMacro::InvalidGroup {
+                        ident: "swap".to_string()
+                    })
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_sub() {
+        let errors = check(indoc!(
+            "
+            @macro add
+                %1.i := %2.i + %3.p
+            @sub
+                %1.i := %2.i
+                LOOP $4.i DO
+                    %1.i := %1.i + 1
+                END
+                %5.i := %3.i
+            @end"
+        ));
+
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    4,
+                    ErrorKind::Macro(ErrorKindMacro::UndefinedPlaceholder {
+                        ident: "add".to_string(),
+                        placeholder: "$4/i".to_string()
+                    })
+                ),
+                (
+                    7,
+                    ErrorKind::Macro(ErrorKindMacro::UndefinedPlaceholder {
+                        ident: "add".to_string(),
+                        placeholder: "%5/i".to_string()
+                    })
+                ),
+                (
+                    7,
+                    ErrorKind::Macro(ErrorKindMacro::InconsistentPlaceholder {
+                        ident: "add".to_string(),
+                        placeholder: "%3/i".to_string(),
+                        expected: "in".to_string()
+                    })
+                )
+            ]
+        );
+    }
+}
+//endregion
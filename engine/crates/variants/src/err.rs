@@ -23,12 +23,94 @@ impl ErrorKindInvalidToken {
     pub fn new(expected: String, got: String) -> Self {
         Self { expected, got }
     }
+
+    pub fn expected(&self) -> &str {
+        &self.expected
+    }
+
+    pub fn got(&self) -> &str {
+        &self.got
+    }
+}
+
+// errors of the combine parser, the excerpt is the source of the token (or statement) the error
+// is located at.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ErrorKindParse {
+    // the statement cannot be parsed from the token on, empty at the end of the statement
+    Syntax { excerpt: String },
+    // an END without a construct it closes
+    UnexpectedEnd,
+    // the opening token of a construct which is never closed by END
+    Unclosed { excerpt: String },
+    // the statement belongs to a section of the file which comes before the current one
+    Misplaced { excerpt: String },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ErrorKindMacro {
+    // no declared macro matches the tokens
+    NoMatch,
+    // the expansion of the macro never resolves
+    RecursionLimit {
+        ident: String,
+    },
+    // multiple macros with the same priority and specificity match the tokens,
+    // every candidate is the module it has been declared in and its identifier.
+    Ambiguous {
+        candidates: Vec<(String, String)>,
+    },
+
+    ModuleNotFound {
+        module: String,
+    },
+    NotFound {
+        module: String,
+        ident: String,
+    },
+    Collision {
+        module: String,
+        ident: String,
+    },
+    CircularImport {
+        module: String,
+        history: Vec<String>,
+    },
+
+    // substitution placeholders ($n) are only valid in the substitution, outside of conditions
+    MisplacedPlaceholder {
+        ident: String,
+        placeholder: String,
+    },
+    // %n is not declared in the pattern or $n is never assigned
+    UndefinedPlaceholder {
+        ident: String,
+        placeholder: String,
+    },
+    // placeholders of a group in the pattern need to be used in a group of the substitution
+    UngroupedPlaceholder {
+        ident: String,
+        placeholder: String,
+    },
+    // a group of the substitution needs to repeat exactly one group of the pattern,
+    // groups cannot be nested.
+    InvalidGroup {
+        ident: String,
+    },
+    // the placeholder is used with a different type than it has been declared with
+    InconsistentPlaceholder {
+        ident: String,
+        placeholder: String,
+        expected: String,
+    },
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum ErrorKind {
     Unsupported(ErrorKindUnsupported),
     InvalidToken(ErrorKindInvalidToken),
+    Parse(ErrorKindParse),
+    Macro(ErrorKindMacro),
 }
 
 #[derive(Debug, Clone)]
@@ -71,6 +153,10 @@ impl Errors {
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Error> {
+        self.0.iter()
+    }
 }
 
 impl Default for Errors {
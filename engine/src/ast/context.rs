@@ -2,6 +2,8 @@ use std::collections::HashMap;
 
 use crate::ast::hir::func::fs::Directory;
 use crate::ast::hir::func::module::map::ModuleMap;
+use crate::ast::hir::func::module::session::Session;
+use crate::ast::hir::func::stdlib::stdlib;
 
 use crate::ast::hir::func::structs::modname::ModuleName;
 use crate::ast::hir::func::structs::qualname::FuncQualName;
@@ -55,7 +57,12 @@ pub struct CompileContext {
 }
 
 impl CompileContext {
-    pub fn new(main: Module, flags: CompileFlags, fs: Option<Directory>) -> StdResult<Self> {
+    pub fn new(
+        main: Module,
+        flags: CompileFlags,
+        fs: Option<Directory>,
+        lib: Option<Directory>,
+    ) -> StdResult<Self> {
         let mainframe = Frame::default();
         let ctx = CompileContext {
             counter: 0,
@@ -64,7 +71,41 @@ impl CompileContext {
             fs: fs.clone().unwrap_or_default(),
             flags,
 
-            modules: ModuleMap::from(main, fs.unwrap_or_default())?,
+            modules: match lib {
+                Some(lib) => ModuleMap::from_with_lib(main, fs.unwrap_or_default(), lib)?,
+                None => ModuleMap::from(main, fs.unwrap_or_default())?,
+            },
+            stack: vec![],
+            mainframe,
+        };
+
+        Ok(ctx)
+    }
+
+    // reuses everything from the previous compile in the session, that is still valid
+    pub fn new_in_session(
+        main: Module,
+        flags: CompileFlags,
+        fs: Option<Directory>,
+        lib: Option<Directory>,
+        session: &mut Session,
+    ) -> StdResult<Self> {
+        session.prepare(flags);
+
+        let mainframe = Frame::default();
+        let ctx = CompileContext {
+            counter: 0,
+            inline_counter: session.inline_counter.clone(),
+
+            fs: fs.clone().unwrap_or_default(),
+            flags,
+
+            modules: ModuleMap::from_session(
+                main,
+                fs.unwrap_or_default(),
+                lib.unwrap_or_else(stdlib),
+                session,
+            )?,
             stack: vec![],
             mainframe,
         };
@@ -116,6 +157,10 @@ impl CompileContext {
         cur
     }
 
+    pub fn inline_counter(&self) -> &HashMap<String, usize> {
+        &self.inline_counter
+    }
+
     pub fn incr_inline(&mut self, qual: FuncQualName) -> usize {
         // instead of using the qual name, we just use the short version
         let name = qual.func_smol();
@@ -10,10 +10,11 @@ use crate::ast::hir::func::structs::qualname::FuncQualName;
 use crate::ast::hir::func::utils::prefix_ident;
 use crate::ast::variant::UInt;
 use crate::ast::verbs::{ComparisonVerb, OperatorVerb};
-use crate::errors::{Error, ErrorVariant, StdResult};
+use crate::errors::{Error, ErrorCode, StdResult};
 use crate::flags::CompileFlags;
 use crate::types::LineNo;
 use crate::utils::check_errors;
+use std::collections::HashSet;
 use std::string::ToString;
 
 pub static CONST_IDENT: [&str; 1] = ["_zero"];
@@ -168,16 +169,23 @@ impl Expr {
                     _ => unreachable!(),
                 };
 
-                if context.flags.contains(CompileFlags::CNF_CONST)
-                    && CONST_IDENT.contains(&ident.as_str())
-                {
-                    return Err(vec![Error::new(
-                        *lno,
-                        ErrorVariant::Message(format!(
-                            "Tried to assign a value to declared CONST {}, \
-                             not allowed with compilation flag CNF_CONST",
-                            ident
-                        )),
+                // builtin constants are only protected with CNF_CONST,
+                // constants declared in the module (or imported) always are.
+                let module = context.get_current_frame().module.clone();
+                let builtin = context.flags.contains(CompileFlags::CNF_CONST)
+                    && CONST_IDENT.contains(&ident.as_str());
+                let declared = context
+                    .modules
+                    .constant(&module, &ident.clone().into())
+                    .is_some();
+
+                if builtin || declared {
+                    return Err(vec![Error::new_from_code(
+                        Some(*lno),
+                        ErrorCode::AssignmentToConstant {
+                            module: module.to_string(),
+                            ident,
+                        },
                     )]);
                 }
 
@@ -214,6 +222,28 @@ impl Expr {
     }
 }
 
+impl Expr {
+    /// Collects every identifier used in the expression
+    pub fn idents(&self) -> HashSet<String> {
+        match self {
+            Expr::Ident(m) => vec![m.clone()].into_iter().collect(),
+            Expr::NaturalNumber(_) => HashSet::new(),
+            Expr::Comparison { lhs, rhs, .. }
+            | Expr::BinaryOp { lhs, rhs, .. }
+            | Expr::Assign { lhs, rhs, .. } => lhs.idents().union(&rhs.idents()).cloned().collect(),
+            Expr::Control(Control::Terms(terms)) => {
+                terms.iter().flat_map(|term| term.idents()).collect()
+            }
+            Expr::Control(Control::Loop { ident, terms, .. }) => {
+                ident.idents().union(&terms.idents()).cloned().collect()
+            }
+            Expr::Control(Control::While { comp, terms, .. }) => {
+                comp.idents().union(&terms.idents()).cloned().collect()
+            }
+        }
+    }
+}
+
 impl Expr {
     pub fn prefix(&self, context: &mut CompileContext, qual: &FuncQualName, count: &usize) -> Self {
         match self {
@@ -0,0 +1,770 @@
+// The decompiler reverts the macro expansions of macros/lower.rs and macros/comp.rs, the
+// lowered tree is turned back into the sugared statements, like x := 0, x := y or IF.
+// Only the exact shapes of the expansion are recognized, everything else is kept as is.
+// The result has the same semantics as the lowered tree, but not necessarily the same
+// statements as the source, IF x < y for example is returned as IF y > x.
+
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+use crate::ast::control::Control;
+use crate::ast::expr::Expr;
+use crate::ast::hir::macros::{Macro, MacroAssign};
+use crate::ast::hir::Hir;
+use crate::ast::variant::UInt;
+use crate::ast::verbs::{ComparisonVerb, OperatorVerb};
+use crate::types::LineNo;
+
+pub fn decompile(expr: &Expr) -> Hir {
+    Hir::Control(Control::Terms(block(flat(expr))))
+}
+
+//region Helpers
+fn flat(expr: &Expr) -> Vec<Expr> {
+    match expr {
+        Expr::Control(Control::Terms(terms)) => terms.iter().flat_map(flat).collect(),
+        _ => vec![expr.clone()],
+    }
+}
+
+// identifiers created with priv_ident, like _1, or _max_1__1 if inlined from a function
+fn private(ident: &str) -> bool {
+    let head = ident.trim_end_matches(|c: char| c.is_ascii_digit());
+
+    head.len() < ident.len() && (head == "_" || (head.starts_with('_') && head.ends_with("__")))
+}
+
+fn ident(expr: &Expr) -> Option<&str> {
+    match expr {
+        Expr::Ident(m) => Some(m.as_str()),
+        _ => None,
+    }
+}
+
+fn number(expr: &Expr) -> Option<&BigUint> {
+    match expr {
+        Expr::NaturalNumber(UInt(n)) => Some(n),
+        _ => None,
+    }
+}
+
+fn boxed(ident: &str) -> Box<Expr> {
+    Box::new(Expr::Ident(ident.to_string()))
+}
+
+fn lno(hir: &Hir) -> LineNo {
+    match hir {
+        Hir::Expr(Expr::Assign { lno, .. }) => *lno,
+        Hir::Macro(m) => m.lno().unwrap_or_default(),
+        Hir::Control(Control::Loop { lno, .. }) | Hir::Control(Control::While { lno, .. }) => *lno,
+        _ => LineNo::default(),
+    }
+}
+
+fn body(hir: &Hir) -> &[Hir] {
+    match hir {
+        Hir::Control(Control::Terms(terms)) => terms,
+        _ => std::slice::from_ref(hir),
+    }
+}
+
+// x := y op n
+fn assign(hir: &Hir) -> Option<(&str, &str, &OperatorVerb, &BigUint)> {
+    match hir {
+        Hir::Expr(Expr::Assign { lhs, rhs, .. }) => match &**rhs {
+            Expr::BinaryOp {
+                lhs: y,
+                verb,
+                rhs: n,
+            } => Some((ident(lhs)?, ident(y)?, verb, number(n)?)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// x := 0
+fn zeroed(hir: &Hir) -> Option<&str> {
+    match hir {
+        Hir::Macro(Macro::AssignToZero { lhs, .. }) => ident(lhs),
+        _ => None,
+    }
+}
+
+// x := n
+fn valued(hir: &Hir) -> Option<(&str, &BigUint)> {
+    match hir {
+        Hir::Macro(Macro::AssignToValue { lhs, rhs, .. }) => Some((ident(lhs)?, number(rhs)?)),
+        _ => None,
+    }
+}
+
+// x := y, the statement is kept as x := x + 0 if y is x
+fn copied(hir: &Hir) -> Option<(&str, &str)> {
+    match hir {
+        Hir::Macro(Macro::AssignToIdent { lhs, rhs, .. }) => Some((ident(lhs)?, ident(rhs)?)),
+        _ => match assign(hir)? {
+            (x, y, OperatorVerb::Plus, n) if n.is_zero() => Some((x, y)),
+            _ => None,
+        },
+    }
+}
+
+// x := y op z
+fn binop(hir: &Hir) -> Option<(&str, &str, &OperatorVerb, &str)> {
+    match hir {
+        Hir::Macro(Macro::AssignToIdentBinOpIdent {
+            lhs,
+            rhs:
+                MacroAssign {
+                    lhs: y,
+                    verb,
+                    rhs: z,
+                },
+            ..
+        }) => Some((ident(lhs)?, ident(y)?, verb, ident(z)?)),
+        _ => None,
+    }
+}
+
+fn looped(hir: &Hir) -> Option<(&str, &[Hir])> {
+    match hir {
+        Hir::Control(Control::Loop {
+            ident: i, terms, ..
+        }) => match &**i {
+            Hir::Expr(i) => Some((ident(i)?, body(terms))),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// the display of the statements, without the numbers of the generated identifiers,
+// the same statements lowered twice use different private identifiers and function prefixes.
+fn shape(terms: &Option<Hir>) -> Option<String> {
+    let display = terms.as_ref()?.display(0, None);
+    let mut shape = String::new();
+    let mut previous = ' ';
+    let mut generated = false;
+    let mut number = false;
+
+    for c in display.chars() {
+        if !(previous.is_alphanumeric() || previous == '_') {
+            generated = c == '_';
+        }
+
+        number = generated && c.is_ascii_digit() && (previous == '_' || number);
+        if !number {
+            shape.push(c);
+        }
+
+        previous = c;
+    }
+
+    Some(shape)
+}
+//endregion
+
+//region Statements
+// LOOP x DO x := x - 1 END is the expansion of x := 0
+fn r#loop(lno: LineNo, counter: Box<Expr>, terms: Vec<Hir>) -> Hir {
+    if let (Some(x), [statement]) = (ident(&counter), terms.as_slice()) {
+        if let Some((lhs, y, OperatorVerb::Minus, n)) = assign(statement) {
+            if lhs == x && y == x && n.is_one() {
+                return Hir::Macro(Macro::AssignToZero { lno, lhs: counter });
+            }
+        }
+    }
+
+    Hir::Control(Control::Loop {
+        lno,
+        ident: Box::new(Hir::Expr(*counter)),
+        terms: Box::new(Hir::Control(Control::Terms(terms))),
+    })
+}
+
+fn term(expr: &Expr) -> Hir {
+    match expr {
+        Expr::Control(Control::Loop { lno, ident, terms }) => {
+            r#loop(*lno, ident.clone(), block(flat(terms)))
+        }
+        Expr::Control(Control::While { lno, comp, terms }) => Hir::Control(Control::While {
+            lno: *lno,
+            comp: Box::new(Hir::Expr(*comp.clone())),
+            terms: Box::new(Hir::Control(Control::Terms(block(flat(terms))))),
+        }),
+        Expr::Assign { lno, lhs, rhs } => {
+            let hir = Hir::Expr(expr.clone());
+
+            match assign(&hir) {
+                // the expansions with OPT_ZERO
+                Some((_, "_zero", OperatorVerb::Plus, n)) if n.is_zero() => {
+                    Hir::Macro(Macro::AssignToZero {
+                        lno: *lno,
+                        lhs: lhs.clone(),
+                    })
+                }
+                Some((_, "_zero", OperatorVerb::Plus, n)) => Hir::Macro(Macro::AssignToValue {
+                    lno: *lno,
+                    lhs: lhs.clone(),
+                    rhs: Box::new(Expr::NaturalNumber(UInt(n.clone()))),
+                }),
+                Some((x, y, OperatorVerb::Plus, n)) if n.is_zero() && x != y => {
+                    let rhs = match &**rhs {
+                        Expr::BinaryOp { lhs, .. } => lhs.clone(),
+                        _ => unreachable!(),
+                    };
+
+                    Hir::Macro(Macro::AssignToIdent {
+                        lno: *lno,
+                        lhs: lhs.clone(),
+                        rhs,
+                    })
+                }
+                _ => hir,
+            }
+        }
+        _ => Hir::Expr(expr.clone()),
+    }
+}
+
+// merges the last two statements, if they are the expansion of a single statement
+fn reduce(terms: &[Hir]) -> Option<Hir> {
+    let (first, second) = match terms {
+        [.., first, second] => (first, second),
+        _ => return None,
+    };
+    let lno = lno(first);
+
+    // x := n is x := 0; x := x + n
+    if let (Some(x), Some((lhs, y, OperatorVerb::Plus, n))) = (zeroed(first), assign(second)) {
+        if lhs == x && y == x {
+            return Some(Hir::Macro(Macro::AssignToValue {
+                lno,
+                lhs: boxed(x),
+                rhs: Box::new(Expr::NaturalNumber(UInt(n.clone()))),
+            }));
+        }
+    }
+
+    // x := y +/- z is x := y; LOOP z DO x := x +/- 1 END
+    if let (Some((x, y)), Some((z, [statement]))) = (copied(first), looped(second)) {
+        if let Some((lhs, rhs, verb, n)) = assign(statement) {
+            let simple = matches!(verb, OperatorVerb::Plus | OperatorVerb::Minus);
+
+            if simple && lhs == x && rhs == x && n.is_one() {
+                return Some(Hir::Macro(Macro::AssignToIdentBinOpIdent {
+                    lno,
+                    lhs: boxed(x),
+                    rhs: MacroAssign {
+                        lhs: boxed(y),
+                        verb: verb.clone(),
+                        rhs: boxed(z),
+                    },
+                }));
+            }
+        }
+    }
+
+    // x := y * z is x := 0; LOOP y DO x := x + z END
+    if let (Some(x), Some((y, [statement]))) = (zeroed(first), looped(second)) {
+        if let Some((lhs, rhs, OperatorVerb::Plus, z)) = binop(statement) {
+            if lhs == x && rhs == x {
+                return Some(Hir::Macro(Macro::AssignToIdentBinOpIdent {
+                    lno,
+                    lhs: boxed(x),
+                    rhs: MacroAssign {
+                        lhs: boxed(y),
+                        verb: OperatorVerb::Multiply,
+                        rhs: boxed(z),
+                    },
+                }));
+            }
+        }
+    }
+
+    // x := y * n is _1 := n; x := y * _1
+    if let (Some((tmp, n)), Some((x, y, OperatorVerb::Multiply, z))) =
+        (valued(first), binop(second))
+    {
+        if private(tmp) && z == tmp && y != tmp {
+            return Some(Hir::Macro(Macro::AssignToIdentExtBinOpValue {
+                lno,
+                lhs: boxed(x),
+                rhs: MacroAssign {
+                    lhs: boxed(y),
+                    verb: OperatorVerb::Multiply,
+                    rhs: Box::new(Expr::NaturalNumber(UInt(n.clone()))),
+                },
+            }));
+        }
+    }
+
+    // LOOP x is lowered into _1 := x; WHILE _1 != 0 DO ...; _1 := _1 - 1 END with WHILE only
+    if let (Some((tmp, x)), Hir::Control(Control::While { comp, terms, .. })) =
+        (copied(first), second)
+    {
+        let counter = match &**comp {
+            Hir::Expr(Expr::Comparison {
+                lhs,
+                verb: ComparisonVerb::NotEqual,
+                rhs,
+            }) if matches!(number(rhs), Some(n) if n.is_zero()) => ident(lhs),
+            _ => None,
+        };
+
+        if let (Some(counter), [statements @ .., last]) = (counter, body(terms)) {
+            let decrement = match assign(last) {
+                Some((lhs, y, OperatorVerb::Minus, n)) => lhs == tmp && y == tmp && n.is_one(),
+                _ => false,
+            };
+            let used = statements
+                .iter()
+                .any(|statement| statement.display(0, None).contains(tmp));
+
+            if private(tmp) && counter == tmp && decrement && !used {
+                return Some(r#loop(lno, boxed(x), statements.to_vec()));
+            }
+        }
+    }
+
+    None
+}
+//endregion
+
+//region Conditionals
+struct Conditional {
+    lno: LineNo,
+    lhs: Expr,
+    verb: ComparisonVerb,
+    rhs: Expr,
+    if_terms: Hir,
+    else_terms: Option<Hir>,
+}
+
+// _1 := 0; _2 := 1; LOOP x DO _1 := 1; _2 := 0 END; LOOP _1 DO ... END; LOOP _2 DO ... END
+fn flags(terms: &[Hir], x: &str) -> Option<(usize, Hir, Option<Hir>)> {
+    let (f1, f2, set, then) = match terms {
+        [f1, f2, set, then, ..] => (zeroed(f1)?, valued(f2)?, looped(set)?, looped(then)?),
+        _ => return None,
+    };
+    let (f2, one) = f2;
+
+    let switched = match set.1 {
+        [a, b] => valued(a) == Some((f1, &BigUint::one())) && zeroed(b) == Some(f2),
+        _ => false,
+    };
+    let valid = private(f1) && private(f2) && f1 != f2 && one.is_one() && switched;
+    if !valid || set.0 != x || then.0 != f1 {
+        return None;
+    }
+
+    let if_terms = Hir::Control(Control::Terms(then.1.to_vec()));
+    match terms.get(4).and_then(looped) {
+        Some((ident, else_terms)) if ident == f2 => Some((
+            5,
+            if_terms,
+            Some(Hir::Control(Control::Terms(else_terms.to_vec()))),
+        )),
+        _ => Some((4, if_terms, None)),
+    }
+}
+
+fn conditional(terms: &[Hir]) -> Option<(usize, Conditional)> {
+    let first = terms.first()?;
+
+    // IF x > y is _1 := x - y followed by IF _1 != 0
+    if let Some((tmp, x, OperatorVerb::Minus, y)) = binop(first) {
+        if private(tmp) {
+            if let Some((consumed, if_terms, else_terms)) = flags(&terms[1..], tmp) {
+                return Some((
+                    consumed + 1,
+                    Conditional {
+                        lno: lno(first),
+                        lhs: Expr::Ident(x.to_string()),
+                        verb: ComparisonVerb::GreaterThan,
+                        rhs: Expr::Ident(y.to_string()),
+                        if_terms,
+                        else_terms,
+                    },
+                ));
+            }
+        }
+    }
+
+    let (x, _) = terms.get(2).and_then(looped)?;
+    let (consumed, if_terms, else_terms) = flags(terms, x)?;
+    Some((
+        consumed,
+        Conditional {
+            lno: lno(first),
+            lhs: Expr::Ident(x.to_string()),
+            verb: ComparisonVerb::NotEqual,
+            rhs: Expr::NaturalNumber(UInt::zero()),
+            if_terms,
+            else_terms,
+        },
+    ))
+}
+
+// the values of the comparison are stored in private identifiers before the comparison
+fn substitute(output: &mut Vec<Hir>, conditional: &mut Conditional) {
+    let replace = |output: &mut Vec<Hir>, side: &mut Expr, gte: bool| {
+        let tmp = match ident(side) {
+            Some(tmp) if private(tmp) => tmp.to_string(),
+            _ => return false,
+        };
+
+        let replaced = match output.last() {
+            Some(last) => match (valued(last), assign(last)) {
+                (Some((lhs, n)), _) if lhs == tmp => {
+                    Some((Expr::NaturalNumber(UInt(n.clone())), false))
+                }
+                // IF x >= y is _1 := x + 1 followed by IF _1 > y
+                (_, Some((lhs, x, OperatorVerb::Plus, n))) if gte && lhs == tmp && n.is_one() => {
+                    Some((Expr::Ident(x.to_string()), true))
+                }
+                _ => None,
+            },
+            None => None,
+        };
+
+        match replaced {
+            Some((expr, incremented)) => {
+                output.pop();
+                *side = expr;
+                incremented
+            }
+            None => false,
+        }
+    };
+
+    let gt = conditional.verb == ComparisonVerb::GreaterThan;
+    replace(output, &mut conditional.rhs, false);
+    if replace(output, &mut conditional.lhs, gt) {
+        conditional.verb = ComparisonVerb::GreaterThanEqual;
+    }
+}
+
+// x >= y, x > y with a number x is x - 1 >= y
+fn at_least(comp: &Expr) -> Option<(Expr, Expr)> {
+    match comp {
+        Expr::Comparison {
+            lhs,
+            verb: ComparisonVerb::GreaterThanEqual,
+            rhs,
+        } => Some((*lhs.clone(), *rhs.clone())),
+        Expr::Comparison {
+            lhs,
+            verb: ComparisonVerb::GreaterThan,
+            rhs,
+        } => match number(lhs) {
+            Some(n) if !n.is_zero() => Some((Expr::NaturalNumber(UInt(n - 1u8)), *rhs.clone())),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// IF x == y is IF x >= y THEN IF x <= y THEN ... ELSE ... END ELSE ... END,
+// IF x != y is the same with the branches switched
+fn equal(conditional: Conditional) -> Hir {
+    let lno = conditional.lno;
+    let comp = Expr::Comparison {
+        lhs: Box::new(conditional.lhs),
+        verb: conditional.verb,
+        rhs: Box::new(conditional.rhs),
+    };
+
+    let inner = match body(&conditional.if_terms) {
+        [Hir::Macro(Macro::Conditional {
+            comp: inner,
+            if_terms,
+            else_terms,
+            ..
+        })] => Some((inner, if_terms, else_terms)),
+        _ => None,
+    };
+
+    if let (Some((x, y)), Some((inner, if_terms, else_terms))) = (at_least(&comp), inner) {
+        let same = shape(else_terms) == shape(&conditional.else_terms);
+
+        if same && at_least(inner) == Some((y.clone(), x.clone())) {
+            let comp = |verb| {
+                Box::new(Expr::Comparison {
+                    lhs: Box::new(x.clone()),
+                    verb,
+                    rhs: Box::new(y.clone()),
+                })
+            };
+
+            return match (body(if_terms), &conditional.else_terms) {
+                ([], Some(else_terms)) => Hir::Macro(Macro::Conditional {
+                    lno,
+                    comp: comp(ComparisonVerb::NotEqual),
+                    if_terms: Box::new(else_terms.clone()),
+                    else_terms: Box::new(None),
+                }),
+                _ => Hir::Macro(Macro::Conditional {
+                    lno,
+                    comp: comp(ComparisonVerb::Equal),
+                    if_terms: if_terms.clone(),
+                    else_terms: Box::new(conditional.else_terms),
+                }),
+            };
+        }
+    }
+
+    Hir::Macro(Macro::Conditional {
+        lno,
+        comp: Box::new(comp),
+        if_terms: Box::new(conditional.if_terms),
+        else_terms: Box::new(conditional.else_terms),
+    })
+}
+//endregion
+
+fn block(exprs: Vec<Expr>) -> Vec<Hir> {
+    let mut terms = vec![];
+
+    for expr in &exprs {
+        terms.push(term(expr));
+
+        while let Some(reduced) = reduce(&terms) {
+            terms.truncate(terms.len() - 2);
+            terms.push(reduced);
+        }
+    }
+
+    let mut output = vec![];
+    let mut index = 0;
+    while index < terms.len() {
+        match conditional(&terms[index..]) {
+            Some((consumed, mut conditional)) => {
+                substitute(&mut output, &mut conditional);
+                output.push(equal(conditional));
+                index += consumed;
+            }
+            None => {
+                output.push(terms[index].clone());
+                index += 1;
+            }
+        }
+    }
+
+    output
+}
+
+//region Tests
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use num_bigint::BigUint;
+
+    use crate::ast::hir::decompile::decompile;
+    use crate::build::Builder;
+    use crate::eval::types::Variables;
+    use crate::flags::CompileFlags;
+
+    fn sugar(source: &str, flags: CompileFlags) -> String {
+        let expr = Builder::parse_and_compile(source, Some(flags), None).unwrap();
+
+        decompile(&expr).display(4, None)
+    }
+
+    fn exec(source: &str, flags: CompileFlags, locals: &[(&str, u8)]) -> Variables {
+        let locals = locals
+            .iter()
+            .map(|(ident, value)| (ident.to_string(), BigUint::from(*value)))
+            .collect();
+        let mut runtime = Builder::ext_all(source, Some(flags), Some(locals), None).unwrap();
+
+        while runtime.is_running() {
+            runtime.step();
+        }
+
+        runtime.context()
+    }
+
+    // the decompiled source compiles to a program with the same result
+    fn round_trip(source: &str, flags: CompileFlags, locals: &[(&str, u8)]) {
+        let sugared = sugar(source, flags);
+        let expected = exec(source, flags, locals);
+        let actual = exec(&sugared, flags, locals);
+
+        let user = |variables: &Variables| {
+            let mut user: Vec<_> = variables
+                .iter()
+                .filter(|(ident, _)| !ident.starts_with('_'))
+                .map(|(ident, value)| (ident.clone(), value.clone()))
+                .collect();
+            user.sort();
+            user
+        };
+        assert_eq!(user(&expected), user(&actual), "{}", sugared);
+    }
+
+    #[test]
+    fn test_decompile_assign() {
+        let source = indoc! {"
+        x := 0
+        y := 5
+        z := x
+        z := x + y
+        z := z - y
+        z := x * y
+        z := x * 3
+        "};
+
+        assert_eq!(sugar(source, CompileFlags::LOOP), source.trim());
+        assert_eq!(
+            sugar(source, CompileFlags::LOOP | CompileFlags::OPT_ZERO),
+            source.trim()
+        );
+        assert_eq!(sugar(source, CompileFlags::WHILE), source.trim());
+        round_trip(source, CompileFlags::LOOP, &[("x", 2)]);
+    }
+
+    #[test]
+    fn test_decompile_keeps_core() {
+        let source = indoc! {"
+        x := y + 2
+        x := x + 0
+
+        LOOP x DO
+            y := y + 1
+        END
+
+        WHILE x != 0 DO
+            x := x - 1
+        END"};
+
+        assert_eq!(sugar(source, CompileFlags::LOOP_AND_WHILE), source);
+    }
+
+    #[test]
+    fn test_decompile_conditional() {
+        let source = indoc! {"
+        IF x != 0 THEN
+            y := 1
+        ELSE
+            y := 2
+        END
+
+        IF x > y THEN
+            z := x
+        END"};
+
+        assert_eq!(sugar(source, CompileFlags::LOOP), source);
+        assert_eq!(sugar(source, CompileFlags::WHILE), source);
+
+        let source = indoc! {"
+        IF x >= 3 THEN
+            y := 1
+        END
+
+        IF 3 > x THEN
+            y := 2
+        END"};
+        assert_eq!(sugar(source, CompileFlags::LOOP), source);
+
+        // x < y is y > x
+        let source = indoc! {"
+        IF x < y THEN
+            z := 1
+        END"};
+        assert_eq!(
+            sugar(source, CompileFlags::LOOP),
+            indoc! {"
+            IF y > x THEN
+                z := 1
+            END"}
+        );
+    }
+
+    #[test]
+    fn test_decompile_equal() {
+        let source = indoc! {"
+        IF x == y THEN
+            z := 1
+        ELSE
+            IF x > 2 THEN
+                z := 2
+            END
+        END
+
+        IF x != y THEN
+            z := z + 1
+        END
+
+        IF x == 4 THEN
+            z := 3
+        END"};
+
+        assert_eq!(sugar(source, CompileFlags::LOOP), source);
+
+        for (x, y) in [(1, 1), (1, 2), (3, 2), (4, 0)] {
+            round_trip(source, CompileFlags::LOOP, &[("x", x), ("y", y)]);
+        }
+    }
+
+    #[test]
+    fn test_decompile_function() {
+        let source = indoc! {"
+        FROM std::math IMPORT max
+
+        IF x == y THEN
+            z := max(x, 4)
+        ELSE
+            z := max(y, 4)
+        END"};
+
+        // the identifiers of the inlined function are kept
+        assert_eq!(
+            sugar(source, CompileFlags::LOOP),
+            indoc! {"
+            IF x == y THEN
+                _max_1_a := x
+                _max_1_b := 4
+
+                IF _max_1_a >= _max_1_b THEN
+                    _max_1_c := _max_1_a
+                ELSE
+                    _max_1_c := _max_1_b
+                END
+                z := _max_1_c
+            ELSE
+                _max_1_a := y
+                _max_1_b := 4
+
+                IF _max_1_a >= _max_1_b THEN
+                    _max_1_c := _max_1_a
+                ELSE
+                    _max_1_c := _max_1_b
+                END
+                z := _max_1_c
+            END"}
+        );
+        for (x, y) in [(1, 1), (5, 2), (2, 7)] {
+            round_trip(source, CompileFlags::LOOP, &[("x", x), ("y", y)]);
+        }
+    }
+
+    #[test]
+    fn test_decompile_nested() {
+        let source = indoc! {"
+        LOOP n DO
+            IF i != 0 THEN
+                r := r * i
+            ELSE
+                r := 1
+            END
+            i := i + 1
+        END"};
+
+        assert_eq!(sugar(source, CompileFlags::LOOP), source.trim());
+        assert_eq!(sugar(source, CompileFlags::WHILE), source.trim());
+        round_trip(source, CompileFlags::LOOP, &[("n", 4)]);
+    }
+}
+//endregion
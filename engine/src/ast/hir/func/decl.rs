@@ -1,10 +1,14 @@
+use num_traits::One;
 #[cfg(feature = "cli")]
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::ast::control::Control;
 use crate::ast::expr::Expr;
 use crate::ast::hir::func::utils::unwrap_ident;
 use crate::ast::hir::Hir;
+use crate::ast::variant::UInt;
+use crate::ast::verbs::OperatorVerb;
 use crate::errors::StdResult;
 use crate::types::LineNo;
 use crate::utils::check_errors;
@@ -13,6 +17,8 @@ use crate::utils::check_errors;
 #[cfg_attr(feature = "cli", derive(JsonSchema))]
 pub struct FuncDecl {
     pub lno: LineNo,
+    // declared with pub, see [`Module::is_exported`]
+    pub public: bool,
 
     pub ident: Box<Expr>,
     pub params: Vec<Expr>,
@@ -57,3 +63,54 @@ impl FuncDecl {
         check_errors(&params)
     }
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "cli", derive(JsonSchema))]
+pub struct ConstDecl {
+    pub lno: LineNo,
+    // declared with pub, see [`Module::is_exported`]
+    pub public: bool,
+
+    pub ident: Box<Expr>,
+    pub value: Box<Expr>,
+}
+
+impl ConstDecl {
+    pub fn get_ident(&self) -> StdResult<String> {
+        unwrap_ident(Some(self.lno), *self.ident.clone(), |expr| {
+            format!("Expected ident to be Expr::Ident, got {}", expr.to_string())
+        })
+    }
+
+    /// Creates the initialization for the constant, this is done directly in [`Expr`]
+    /// instead of going through the macro pipeline, as that would go through
+    /// [`Expr::verify`], which forbids any assignment to a constant.
+    pub fn init(&self, ident: &str) -> Expr {
+        let ident = Expr::Ident(ident.to_string());
+
+        Expr::Control(Control::Terms(vec![
+            Expr::Control(Control::Loop {
+                lno: self.lno,
+                ident: Box::new(ident.clone()),
+                terms: Box::new(Expr::Control(Control::Terms(vec![Expr::Assign {
+                    lno: self.lno,
+                    lhs: Box::new(ident.clone()),
+                    rhs: Box::new(Expr::BinaryOp {
+                        lhs: Box::new(ident.clone()),
+                        verb: OperatorVerb::Minus,
+                        rhs: Box::new(Expr::NaturalNumber(UInt::one())),
+                    }),
+                }]))),
+            }),
+            Expr::Assign {
+                lno: self.lno,
+                lhs: Box::new(ident.clone()),
+                rhs: Box::new(Expr::BinaryOp {
+                    lhs: Box::new(ident),
+                    verb: OperatorVerb::Plus,
+                    rhs: self.value.clone(),
+                }),
+            },
+        ]))
+    }
+}
@@ -9,6 +9,15 @@ use std::collections::hash_map::Iter;
 
 pub type FileContents = String;
 
+// there are multiple extension that we support, all of them are considered a module
+pub static EXTENSIONS: [&str; 4] = ["lp", "loop", "while", "wh"];
+
+pub fn is_module(path: &std::path::Path) -> bool {
+    path.extension()
+        .map(|ext| EXTENSIONS.contains(&ext.to_string_lossy().as_ref()))
+        .unwrap_or(false)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[cfg_attr(feature = "cli", derive(JsonSchema))]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
@@ -95,6 +104,83 @@ impl Directory {
         self.0.iter()
     }
 
+    /// Walks the directory along the module path,
+    /// only returns the contents if the path ends in a file.
+    pub fn lookup(&self, path: &[String]) -> Option<FileContents> {
+        let (head, tail) = path.split_first()?;
+        let Path(value) = &**self.0.get(head)?;
+
+        match value {
+            Either::Left(contents) if tail.is_empty() => Some(contents.clone()),
+            Either::Right(dir) if !tail.is_empty() => dir.lookup(tail),
+            _ => None,
+        }
+    }
+
+    /// Inserts a file along the module path, missing directories are created.
+    #[cfg(feature = "cli")]
+    pub fn insert_file(&mut self, path: &[String], contents: FileContents) {
+        match path {
+            [] => {}
+            [name] => {
+                self.insert(name.clone(), contents.into());
+            }
+            [head, tail @ ..] => {
+                let mut dir = match self.0.remove(head).map(|path| *path) {
+                    Some(Path(Either::Right(dir))) => *dir,
+                    _ => Directory::new(),
+                };
+
+                dir.insert_file(tail, contents);
+                self.insert(head.clone(), dir.into());
+            }
+        }
+    }
+
+    /// Reads a directory from disk, every file with a supported extension is a module named
+    /// after the file without its extension. Subdirectories are nested, hidden ones are skipped.
+    #[cfg(feature = "cli")]
+    pub fn read(root: &std::path::Path) -> std::io::Result<Self> {
+        let mut directory = Directory::new();
+
+        for entry in std::fs::read_dir(root)? {
+            let path = entry?.path();
+            let name = match path.file_name() {
+                Some(name) => name.to_string_lossy().to_string(),
+                None => continue,
+            };
+
+            if name.starts_with('.') {
+                continue;
+            }
+
+            if path.is_dir() {
+                directory.insert(name, Directory::read(&path)?.into());
+            } else if let (true, Some(stem)) = (is_module(&path), path.file_stem()) {
+                let contents = std::fs::read_to_string(&path)?;
+                directory.insert(stem.to_string_lossy().to_string(), contents.into());
+            }
+        }
+
+        Ok(directory)
+    }
+
+    /// Reads the directory a file is in, without the file itself, as it is the main module.
+    #[cfg(feature = "cli")]
+    pub fn siblings(file: &std::path::Path) -> std::io::Result<Self> {
+        let root = match file.parent() {
+            Some(root) if !root.as_os_str().is_empty() => root,
+            _ => std::path::Path::new("."),
+        };
+        let mut directory = Directory::read(root)?;
+
+        if let Some(stem) = file.file_stem() {
+            directory.remove(&stem.to_string_lossy());
+        }
+
+        Ok(directory)
+    }
+
     fn format(&self, indent: usize, level: Option<usize>) -> String {
         let level = level.unwrap_or(0);
         let spacing = " ".repeat(indent * level);
@@ -200,4 +286,44 @@ mod tests {
             ],
         );
     }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_directory_insert_file() {
+        let mut root = create();
+        root.insert_file(
+            &["X".to_string(), "C".to_string(), "F".to_string()],
+            "<new>".to_string(),
+        );
+        root.insert_file(&["Y".to_string(), "G".to_string()], "<new>".to_string());
+
+        let path = |path: &[&str]| path.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            root.lookup(&path(&["X", "C", "F"])),
+            Some("<new>".to_string())
+        );
+        assert_eq!(root.lookup(&path(&["Y", "G"])), Some("<new>".to_string()));
+        assert!(root.lookup(&path(&["X", "C", "D", "_0"])).is_some());
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_directory_read() -> std::io::Result<()> {
+        let root = std::env::temp_dir().join(format!("lit-read-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("a").join(".hidden"))?;
+        std::fs::write(root.join("main.lp"), "x := 1")?;
+        std::fs::write(root.join("a").join("b.while"), "y := 2")?;
+        std::fs::write(root.join("a").join(".hidden").join("c.lp"), "")?;
+        std::fs::write(root.join("notes.txt"), "")?;
+
+        let dir = Directory::read(&root);
+        std::fs::remove_dir_all(&root)?;
+
+        let flat: Vec<_> = dir?.walk().collect();
+        assert_eq!(flat.len(), 2);
+        assert_in(&flat, vec!["main".to_string()]);
+        assert_in(&flat, vec!["a".to_string(), "b".to_string()]);
+
+        Ok(())
+    }
 }
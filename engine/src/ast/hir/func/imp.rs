@@ -22,7 +22,39 @@ pub struct ImpFunc {
 #[cfg_attr(feature = "cli", derive(JsonSchema))]
 pub struct Imp {
     pub lno: LineNo,
+    // pub from x import y, re-exports the imports
+    pub public: bool,
 
     pub path: Vec<Expr>,
     pub funcs: Either<Vec<ImpFunc>, ImpWildcard>,
 }
+
+// Corresponds to
+// import path1::path2::path3 as alias
+// the functions of the module are accessible through alias::func,
+// if no alias is given the path is used instead.
+#[derive(Debug, Clone, Serialize, Deserialize, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "cli", derive(JsonSchema))]
+pub struct ImpModule {
+    pub lno: LineNo,
+
+    pub path: Vec<Expr>,
+    pub alias: Option<Box<Expr>>,
+}
+
+impl ImpModule {
+    /// The namespace under which the functions of the module are available
+    pub fn namespace(&self) -> String {
+        self.alias
+            .as_ref()
+            .map(|alias| vec![*alias.clone()])
+            .unwrap_or_else(|| self.path.clone())
+            .into_iter()
+            .map(|p| match p {
+                Expr::Ident(m) => m,
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>()
+            .join("::")
+    }
+}
@@ -7,7 +7,9 @@ use crate::ast::hir::func::decl::FuncDecl;
 use crate::ast::hir::func::structs::modname::ModuleName;
 use crate::ast::hir::func::structs::qualname::FuncQualName;
 use crate::ast::hir::func::structs::{FuncContext, FuncImport, FuncInline};
-use crate::ast::hir::func::utils::{could_not_find_function, could_not_find_module, prefix_ident};
+use crate::ast::hir::func::utils::{
+    could_not_find_function, could_not_find_module, init_consts, prefix_ident,
+};
 use crate::errors::{Error, ErrorCode, StdResult};
 
 pub trait Inline {
@@ -20,6 +22,14 @@ impl Inline for FuncContext {
             FuncContext::Import(imp) => imp.inline(context, module),
             FuncContext::Func(func) => func.inline(context, module),
             FuncContext::Inline(inline) => inline.inline(context, module),
+            // constants are not callable, there is no function of that name
+            FuncContext::Const(decl) => Err(could_not_find_function(
+                Some(decl.lno),
+                module,
+                &decl.get_ident()?.into(),
+            )),
+            // calls are never looked up as a namespace, see ModuleMap::lookup
+            FuncContext::Namespace(_) => unreachable!(),
         }
     }
 }
@@ -43,8 +53,22 @@ impl Inline for FuncImport {
 
         match func_ctx {
             FuncContext::Import(imp) => imp.inline(context, &self.module),
-            FuncContext::Func(func) => func.inline(context, module),
+            // the function is declared in the imported module, not in the caller,
+            // cache the result in there, so that every importer (and later compiles) can reuse it.
+            FuncContext::Func(func) => {
+                let inline = func.inline(context, &self.module)?;
+
+                if let Some(module_ctx) = context.modules.get_mut(&self.module) {
+                    module_ctx.insert(self.ident.clone(), FuncContext::Inline(inline.clone()));
+                }
+
+                Ok(inline)
+            }
             FuncContext::Inline(inline) => inline.inline(context, module),
+            // constants are not callable, there is no function of that name
+            FuncContext::Const(_) => Err(could_not_find_function(None, &self.module, &self.ident)),
+            // only functions and constants are exported, namespaces are not
+            FuncContext::Namespace(_) => unreachable!(),
         }
     }
 }
@@ -124,7 +148,8 @@ impl Inline for FuncDecl {
             let count = context.incr_inline(qual.clone());
 
             // Note(bmahmoud) this means that inner calls will be double prefixed!
-            let terms = self.terms.lower(context)?;
+            let terms = self.terms.lower(context)?.verify(context)?;
+            let terms = init_consts(context, module, terms);
             let terms = terms.prefix(context, &qual, &count);
 
             let inline = FuncInline {
@@ -18,40 +18,25 @@ pub fn lower_call(
 ) -> StdResult<Expr> {
     let module = context.get_current_frame().clone().module;
 
-    let (func_name, func_ctx) = {
-        let module_ctx = context
-            .modules
-            .get(&module)
-            .map_or(Err(utils::could_not_find_module(Some(lno), &module)), Ok)?
-            .clone();
-
-        let func_name: FuncName = rhs.get_ident()?.into();
-
-        let func_ctx = module_ctx
-            .get(&func_name)
-            .map_or(
-                Err(utils::could_not_find_function(
-                    Some(lno),
-                    &module,
-                    &func_name,
-                )),
-                Ok,
-            )?
-            .clone();
+    if !context.modules.contains_key(&module) {
+        return Err(utils::could_not_find_module(Some(lno), &module));
+    }
 
-        (func_name, func_ctx)
-    };
+    // namespaced calls (m::max) are looked up in the module imported under the namespace,
+    // the function is inlined and cached in there.
+    let func_name: FuncName = rhs.get_ident()?.into();
+    let (qual, func_ctx) = context.modules.lookup(Some(lno), &module, &func_name)?;
 
-    let inline = func_ctx.inline(context, &module)?;
+    let inline = func_ctx.inline(context, qual.module())?;
 
     {
         let module_ctx = context
             .modules
-            .get_mut(&module)
-            .map_or(Err(utils::could_not_find_module(Some(lno), &module)), Ok)?;
+            .get_mut(qual.module())
+            .ok_or_else(|| utils::could_not_find_module(Some(lno), qual.module()))?;
 
         // cache the inline result for further use
-        module_ctx.insert(func_name.clone(), FuncContext::Inline(inline.clone()));
+        module_ctx.insert(qual.func().clone(), FuncContext::Inline(inline.clone()));
     }
 
     // check param length
@@ -59,8 +44,8 @@ pub fn lower_call(
         return Err(vec![Error::new_from_code(
             Some(lno),
             ErrorCode::FunctionUnexpectedNumberOfArguments {
-                module: module.to_string(),
-                func: func_name.to_string(),
+                module: qual.module().to_string(),
+                func: qual.func().to_string(),
                 expected: inline.params.len(),
                 got: rhs.args.len(),
             },
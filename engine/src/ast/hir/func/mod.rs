@@ -17,6 +17,7 @@ pub mod imp;
 pub mod inline;
 pub mod lower;
 pub mod module;
+pub mod stdlib;
 pub mod structs;
 pub mod utils;
 
@@ -1,39 +1,49 @@
-use std::cmp::Ordering;
-use std::collections::HashMap;
-use std::fs::read_to_string;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
 
 use either::Either;
 use itertools::Itertools;
 
 use crate::ast::expr::Expr;
+use crate::ast::hir::func::decl::ConstDecl;
 use crate::ast::hir::func::fs::Directory;
 use crate::ast::hir::func::imp::{Imp, ImpFunc};
 use crate::ast::hir::func::module::ctx::{ModuleContext, ModuleContextHashMap};
+use crate::ast::hir::func::module::session::{deps, hash, Session};
+use crate::ast::hir::func::stdlib::stdlib;
 use crate::ast::hir::func::structs::funcname::FuncName;
 use crate::ast::hir::func::structs::modname::ModuleName;
-use crate::ast::hir::func::structs::{FuncContext, FuncImport};
+use crate::ast::hir::func::structs::qualname::FuncQualName;
+use crate::ast::hir::func::structs::{FuncContext, FuncImport, FuncNamespace};
+use crate::ast::hir::func::utils::could_not_find_function;
 use crate::ast::hir::Hir;
 use crate::ast::module::Module;
 use crate::build::Builder;
-use crate::errors::{Error, ErrorCode, ErrorVariant};
-use crate::utils::check_errors;
+use crate::errors::{Error, ErrorCode, ErrorVariant, StdResult};
+use crate::types::LineNo;
 
 pub type ModuleHashMap = HashMap<ModuleName, ModuleContext>;
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct ModuleMap(pub ModuleHashMap);
 
 NewtypeDeref! {() pub struct ModuleMap(pub ModuleHashMap); }
 NewtypeDerefMut! {() pub struct ModuleMap(pub ModuleHashMap); }
 
 type ImpFuncKeyFrom = (Vec<String>, Module);
-type ImpFuncKey = (ImpFuncKeyFrom, ImpFunc);
+// the errors of an import are located at the importing statement, which is part of the key
+type ImpFuncKey = (ImpFuncKeyFrom, ImpFunc, LineNo);
 type ResolveResult = Result<(ModuleContextHashMap, Vec<Vec<String>>), Vec<Error>>;
 
 #[derive(Debug)]
 struct Cache {
     wildcard: HashMap<(Vec<String>, Module), ResolveResult>,
     impfunc: HashMap<ImpFuncKey, ResolveResult>,
+
+    // these are carried over between compiles through the [`Session`]
+    parsed: HashMap<u64, Module>,
+    hashes: HashMap<Vec<String>, u64>,
+
+    // fs modules which could not be parsed, the errors are only reported once they are imported
+    broken: HashMap<Vec<String>, Vec<Error>>,
 }
 
 impl ModuleMap {
@@ -44,45 +54,137 @@ impl ModuleMap {
     pub fn insert(&mut self, key: ModuleName, value: ModuleContext) -> Option<ModuleContext> {
         self.0.insert(key, value)
     }
+
+    /// Follows the imports of a name in a module until it reaches its declaration,
+    /// returns the declaration only if it is a constant.
+    pub fn constant(&self, module: &ModuleName, name: &FuncName) -> Option<ConstDecl> {
+        match self.0.get(module)?.get(name)? {
+            FuncContext::Import(imp) => self.constant(&imp.module, &imp.ident),
+            FuncContext::Const(decl) => Some(decl.clone()),
+            _ => None,
+        }
+    }
+
+    /// Looks up the function a module calls, namespaced calls (m::max) are looked up in the
+    /// exports of the module imported under the namespace. The qualified name is the one of the
+    /// module the function is looked up in, the namespace is resolved to the imported module.
+    pub fn lookup(
+        &self,
+        lno: Option<LineNo>,
+        module: &ModuleName,
+        name: &FuncName,
+    ) -> StdResult<(FuncQualName, FuncContext)> {
+        let qual = FuncQualName::from_path(module, name);
+        let not_found =
+            |qual: &FuncQualName| could_not_find_function(lno, qual.module(), qual.func());
+        let ctx = self.0.get(module);
+
+        // namespaces themselves are not callable
+        if !name.contains("::") {
+            return match ctx.and_then(|ctx| ctx.get(name)) {
+                Some(FuncContext::Namespace(_)) | None => Err(not_found(&qual)),
+                Some(func) => Ok((qual, func.clone())),
+            };
+        }
+
+        let namespace: FuncName = qual.module().to_string().into();
+        let namespace = match ctx.and_then(|ctx| ctx.get(&namespace)) {
+            Some(FuncContext::Namespace(namespace)) => namespace,
+            _ => return Err(not_found(&qual)),
+        };
+
+        let qual: FuncQualName = (namespace.module.clone(), qual.func().clone()).into();
+        let func = self
+            .0
+            .get(qual.module())
+            .and_then(|ctx| ctx.get(qual.func()));
+        match func {
+            Some(func) if namespace.exports.contains(qual.func()) => Ok((qual, func.clone())),
+            // declared in the module but not pub, like plain imports report it
+            Some(FuncContext::Func(_) | FuncContext::Inline(_) | FuncContext::Const(_)) => {
+                Err(vec![Error::new_from_code(
+                    lno,
+                    ErrorCode::FunctionNotExported {
+                        module: qual.module().to_string(),
+                        func: qual.func().to_string(),
+                    },
+                )])
+            }
+            _ => Err(not_found(&qual)),
+        }
+    }
+
+    /// All constants visible in a module, keyed by the name they are visible under.
+    pub fn constants(&self, module: &ModuleName) -> HashMap<String, ConstDecl> {
+        self.0
+            .get(module)
+            .map(|ctx| {
+                ctx.keys()
+                    .filter_map(|name| {
+                        self.constant(module, name)
+                            .map(|decl| (name.to_string(), decl))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 }
 
 impl ModuleMap {
-    /// Parses a directory of modules into their AST representation,
-    /// strips their module body as a normalization step. They are only used as function/import referer.
-    fn parse(directory: Directory) -> Result<HashMap<Vec<String>, Module>, Vec<Error>> {
-        let preliminary: HashMap<_, _> = directory.walk().collect();
-        let files = preliminary
-            .iter()
-            .map(|(k, v)| (k, Builder::parse(v.as_str(), None)));
+    /// Parses a single module into its AST representation, the result is cached by the hash of
+    /// the contents. Strips the module body as a normalization step.
+    /// They are only used as function/import referer.
+    fn parse_module(contents: &str, cache: &mut Cache) -> Result<(u64, Module), Vec<Error>> {
+        let key = hash(contents);
+        if let Some(module) = cache.parsed.get(&key) {
+            return Ok((key, module.clone()));
+        }
 
-        let results: Vec<_> = files
-            .clone()
-            .map(|(_, v)| v.map_err(|err| vec![Error::new_from_parse(err)]))
-            .collect();
-        check_errors(&results)?;
+        let mut module =
+            Builder::parse(contents, None).map_err(|err| vec![Error::new_from_parse(err)])?;
+        // erase all code
+        module.code = Hir::NoOp;
 
-        // we know these are all safe thanks to check_errors
-        Ok(files
-            .map(|(k, v)| (k.clone(), v.unwrap()))
-            .map(|(k, v)| {
-                let mut module = v;
-                module.code = Hir::NoOp;
+        cache.parsed.insert(key, module.clone());
+        Ok((key, module))
+    }
 
-                (k, module)
-            })
-            .collect())
+    /// Parses a directory of modules into their AST representation,
+    /// every module is prefixed with fs. Modules which cannot be parsed are left out,
+    /// so that unrelated files in the same directory do not fail the compilation.
+    fn parse(directory: Directory, cache: &mut Cache) -> HashMap<Vec<String>, Module> {
+        let mut modules = HashMap::new();
+
+        for (name, contents) in directory.walk() {
+            let mut name = name;
+            name.insert(0, "fs".to_string());
+
+            match Self::parse_module(contents.as_str(), cache) {
+                Ok((key, module)) => {
+                    cache.hashes.insert(name.clone(), key);
+                    modules.insert(name, module);
+                }
+                Err(err) => {
+                    cache.broken.insert(name, err);
+                }
+            }
+        }
+
+        modules
     }
 
-    /// Utility function to find a specific module by [`Imp`]. THis uses modules to check,
+    /// Utility function to find a specific module by the path of an import. THis uses modules to check,
     /// if the target module does **not** start fs and the module is not yet present, search for it
-    /// in the local lib folder and add it to the `modules` HashMap.
+    /// in the library [`Directory`] and add it to the `modules` HashMap.
     fn find_module(
         modules: &mut HashMap<Vec<String>, Module>,
-        imp: Imp,
+        lib: &Directory,
+        path: &[Expr],
+        lno: LineNo,
+        cache: &mut Cache,
     ) -> Result<(Vec<String>, Module, bool), Vec<Error>> {
         let mut new = false;
-        let module_name: Vec<_> = imp
-            .path
+        let module_name: Vec<_> = path
             .iter()
             .map(|p| match p.clone() {
                 Expr::Ident(m) => m,
@@ -93,53 +195,28 @@ impl ModuleMap {
         // fs = filesystem, we cannot fetch those and early skip them.
         // if the module is none, this indicates that it isn't loaded, or not found,
         // Should the module not start with fs (<- indicates that it is locally available)
-        // then we search the ./lib directory for file. We do so by walking the tree and failing
-        // early if it does not exist.
-        // if nothing is found. We parse that module and add it to the modules to avoid unnecessary
-        // re-parsing.
+        // then we search the library directory (by default the embedded std) for the file.
+        // if something is found. We parse that module and add it to the modules to avoid
+        // unnecessary re-parsing.
         let mut module = modules.get(&module_name).cloned();
         if module.is_none() && module_name.first().cloned() != Some("fs".to_string()) {
-            let mut buffer = PathBuf::new();
-            buffer.push("./lib");
-            buffer.extend(module_name.clone());
-
-            let mut inserted = 0usize;
-            // there are multiple extension that we support, search all of them
-            for extension in &["lp", "loop", "while", "wh"] {
-                let mut buf = buffer.clone();
-                buf.set_extension(extension);
-
-                if buf.exists() && buf.is_file() {
-                    let contents =
-                        read_to_string(buf).map_err(|err| vec![Error::new_from_io(err)])?;
-                    let mut contents =
-                        Builder::parse(contents.as_str(), None).map_err(Error::new_from_parse)?;
-                    // erase all code
-                    contents.code = Hir::NoOp;
-
-                    modules.insert(module_name.clone(), contents.clone());
-                    module = Some(contents);
-                    inserted += 1;
-                }
-            }
+            if let Some(contents) = lib.lookup(&module_name) {
+                let (key, contents) = Self::parse_module(contents.as_str(), cache)?;
+                cache.hashes.insert(module_name.clone(), key);
 
-            if inserted > 1 {
-                return Err(vec![Error::new_from_code(
-                    Some(imp.lno),
-                    ErrorCode::MultipleModuleCandidates {
-                        module: module_name.join("::"),
-                        count: inserted,
-                    },
-                )]);
-            }
-            if inserted == 1 {
+                modules.insert(module_name.clone(), contents.clone());
+                module = Some(contents);
                 new = true;
             }
         }
 
+        if let (None, Some(errors)) = (&module, cache.broken.get(&module_name)) {
+            return Err(errors.clone());
+        }
+
         match module {
             None => Err(vec![Error::new_from_code(
-                Some(imp.lno),
+                Some(lno),
                 ErrorCode::CouldNotFindModule {
                     module: module_name.join("::"),
                 },
@@ -196,6 +273,7 @@ impl ModuleMap {
         from: (&Vec<String>, &Module),
         to: (&Vec<String>, &Module),
         modules: &mut HashMap<Vec<String>, Module>,
+        lib: &Directory,
         history: Option<Vec<Vec<String>>>,
         cache: &mut Cache,
     ) -> ResolveResult {
@@ -204,7 +282,6 @@ impl ModuleMap {
         let mut new = vec![];
 
         if let Some(cached) = cache.wildcard.get(&cache_key) {
-            println!("Hit the wildcard cache");
             return cached.clone();
         }
 
@@ -216,13 +293,36 @@ impl ModuleMap {
         let mut imports: ModuleContextHashMap = HashMap::new();
         let mut errors: Vec<Error> = vec![];
 
-        // add our functions declarations (if we have any)
+        // add our exported functions declarations (if we have any)
         for func in &to.1.decl {
             let name: FuncName = match *func.ident.clone() {
                 Expr::Ident(m) => m,
                 _ => unreachable!(),
             }
             .into();
+            if !to.1.is_exported(&name) {
+                continue;
+            }
+
+            imports.insert(
+                name.clone(),
+                FuncContext::Import(FuncImport {
+                    module: to.0.clone().into(),
+                    ident: name,
+                }),
+            );
+        }
+
+        // add our exported constants (if we have any), they are imported the same way as functions
+        for constant in &to.1.consts {
+            let name: FuncName = match *constant.ident.clone() {
+                Expr::Ident(m) => m,
+                _ => unreachable!(),
+            }
+            .into();
+            if !to.1.is_exported(&name) {
+                continue;
+            }
 
             imports.insert(
                 name.clone(),
@@ -235,8 +335,14 @@ impl ModuleMap {
 
         // add our imports (if we have any), either recursively calls ourselves
         // (with a guard in place to stop circular imports) or finds a single module.
-        for imp in &to.1.imp {
-            let res = Self::find_module(modules, imp.clone());
+        // Imports are only re-exported if they are pub, or the module exports everything.
+        let reexports: Vec<_> =
+            to.1.imp
+                .iter()
+                .filter(|imp| imp.public || to.1.exports_all())
+                .collect();
+        for imp in reexports {
+            let res = Self::find_module(modules, lib, &imp.path, imp.lno, cache);
             if let Err(err) = res {
                 errors.extend(err);
                 continue;
@@ -254,7 +360,9 @@ impl ModuleMap {
                             from,
                             (&module_name, &module),
                             &import,
+                            imp.lno,
                             modules,
+                            lib,
                             Some(history.clone()),
                             cache,
                         );
@@ -273,6 +381,7 @@ impl ModuleMap {
                         from,
                         (&module_name, &module),
                         modules,
+                        lib,
                         Some(history.clone()),
                         cache,
                     );
@@ -325,14 +434,15 @@ impl ModuleMap {
         from: (&Vec<String>, &Module),
         to: (&Vec<String>, &Module),
         target: &ImpFunc,
+        lno: LineNo,
         modules: &mut HashMap<Vec<String>, Module>,
+        lib: &Directory,
         history: Option<Vec<Vec<String>>>,
         cache: &mut Cache,
     ) -> ResolveResult {
-        let cache_key = ((to.0.clone(), to.1.clone()), target.clone());
+        let cache_key = ((to.0.clone(), to.1.clone()), target.clone(), lno);
         let mut new = vec![];
         if let Some(cached) = cache.impfunc.get(&cache_key) {
-            println!("Hit the impfunc cache");
             return cached.clone();
         }
 
@@ -345,14 +455,41 @@ impl ModuleMap {
 
         let mut errors = vec![];
 
-        // try if we have a function of that name
-        let decl: Vec<_> = to.1.decl.iter().filter(|f| match *f.ident.clone() {
-            Expr::Ident(m) => m,
-            _ => unreachable!()
-        } == match *target.ident.clone() {
+        // the target is known to the module, but not exported, no need to search any further.
+        // If it isn't known it could still be in a wildcard, which we only search if they are pub.
+        let target_name = match *target.ident.clone() {
             Expr::Ident(m) => m,
-            _ => unreachable!()
-        }).collect();
+            _ => unreachable!(),
+        };
+        let declared = to.1.is_declared(&target_name);
+        if !to.1.is_exported(&target_name) && (declared || !to.1.has_public_wildcard()) {
+            let code = if declared {
+                ErrorCode::FunctionNotExported {
+                    module: to.0.join("::"),
+                    func: target_name,
+                }
+            } else {
+                ErrorCode::CouldNotFindFunction {
+                    module: to.0.join("::"),
+                    func: target_name,
+                }
+            };
+
+            let res = Err(vec![Error::new_from_code(Some(lno), code)]);
+            cache.impfunc.insert(cache_key, res.clone());
+            return res;
+        }
+
+        // try if we have a function or constant of that name
+        let decl: Vec<_> = to.1.decl.iter().map(|f| f.ident.clone())
+            .chain(to.1.consts.iter().map(|c| c.ident.clone()))
+            .filter(|ident| match *ident.clone() {
+                Expr::Ident(m) => m,
+                _ => unreachable!()
+            } == match *target.ident.clone() {
+                Expr::Ident(m) => m,
+                _ => unreachable!()
+            }).collect();
 
         // if there is a declaration if the same name, then we can just use this.
         // This is always our end-state.
@@ -401,7 +538,8 @@ impl ModuleMap {
 
         // if there is an import matching our target alias/ident, then use that to find the correct target.
         if let Some((imp, func)) = local_imports {
-            let (module_name, module, created) = Self::find_module(modules, imp)?;
+            let (module_name, module, created) =
+                Self::find_module(modules, lib, &imp.path, imp.lno, cache)?;
             if created {
                 new.push(module_name.clone());
             }
@@ -410,7 +548,9 @@ impl ModuleMap {
                 from,
                 (&module_name, &module),
                 &func,
+                imp.lno,
                 modules,
+                lib,
                 Some(history),
                 cache,
             );
@@ -425,9 +565,14 @@ impl ModuleMap {
         // which is not optional and definitely something that could use a [REWORK].
         // You could imagine creating a cache of some sorts, due to code size and speed our current
         // implementation is good enough for educational purposes.
-        let wildcards: Vec<_> = to.1.imp.iter().filter(|i| i.funcs.is_right()).collect();
+        let wildcards: Vec<_> =
+            to.1.imp
+                .iter()
+                .filter(|i| i.funcs.is_right())
+                .filter(|i| i.public || to.1.exports_all())
+                .collect();
         for wildcard in wildcards {
-            let res = Self::find_module(modules, wildcard.clone());
+            let res = Self::find_module(modules, lib, &wildcard.path, wildcard.lno, cache);
             if let Err(err) = res {
                 errors.extend(err);
                 continue;
@@ -441,6 +586,7 @@ impl ModuleMap {
                 from,
                 (&module_name, &module),
                 modules,
+                lib,
                 Some(history.clone()),
                 cache,
             );
@@ -478,7 +624,7 @@ impl ModuleMap {
         // instead of immediately failing we try to be as late as possible, we know we're going
         // to fail, but wildcard also potentially adds new errors, so this is used to not overwrite them.
         errors.push(Error::new_from_code(
-            None,
+            Some(lno),
             ErrorCode::CouldNotFindFunction {
                 module: to.0.join("::"),
                 func: match *target.ident.clone() {
@@ -499,6 +645,7 @@ impl ModuleMap {
     fn resolve(
         from: (&Vec<String>, &Module),
         modules: &mut HashMap<Vec<String>, Module>,
+        lib: &Directory,
         cache: &mut Cache,
     ) -> ResolveResult {
         // wildcard means to add everything we have in the module
@@ -508,7 +655,7 @@ impl ModuleMap {
         let mut errors = vec![];
 
         for imp in &from.1.imp {
-            let res = Self::find_module(modules, imp.clone());
+            let res = Self::find_module(modules, lib, &imp.path, imp.lno, cache);
             if let Err(err) = res {
                 errors.extend(err);
                 continue;
@@ -526,7 +673,9 @@ impl ModuleMap {
                             from,
                             (&module_name, &module),
                             &func,
+                            imp.lno,
                             modules,
+                            lib,
                             None,
                             cache,
                         );
@@ -540,8 +689,14 @@ impl ModuleMap {
                     results
                 }
                 Either::Right(_) => {
-                    let res =
-                        Self::resolve_wildcard(from, (&module_name, &module), modules, None, cache);
+                    let res = Self::resolve_wildcard(
+                        from,
+                        (&module_name, &module),
+                        modules,
+                        lib,
+                        None,
+                        cache,
+                    );
                     if let Err(err) = res {
                         errors.extend(err);
                         vec![]
@@ -573,6 +728,50 @@ impl ModuleMap {
             }
         }
 
+        // namespaced imports, the namespace refers to the module, whose exported functions are
+        // called through it (m::max)
+        for imp in &from.1.imp_mod {
+            let res = Self::find_module(modules, lib, &imp.path, imp.lno, cache);
+            if let Err(err) = res {
+                errors.extend(err);
+                continue;
+            }
+            let (module_name, module, created) = res.unwrap();
+            if created {
+                new.push(module_name.clone());
+            }
+
+            let res =
+                Self::resolve_wildcard(from, (&module_name, &module), modules, lib, None, cache);
+            if let Err(err) = res {
+                errors.extend(err);
+                continue;
+            }
+            let (res, modules) = res.unwrap();
+            new.extend(modules);
+
+            let name: FuncName = imp.namespace().into();
+            if context.contains_key(&name) {
+                errors.push(Error::new_from_code(
+                    Some(imp.lno),
+                    ErrorCode::FunctionNameCollision {
+                        module: from.0.join("::"),
+                        func: name.to_string(),
+                        count: None,
+                    },
+                ));
+                continue;
+            }
+
+            context.insert(
+                name,
+                FuncContext::Namespace(FuncNamespace {
+                    module: module_name.into(),
+                    exports: res.into_keys().sorted().collect(),
+                }),
+            );
+        }
+
         if !errors.is_empty() {
             Err(errors)
         } else {
@@ -588,12 +787,15 @@ impl ModuleMap {
 
         for (name, module) in modules {
             let decl = module.clone().decl;
+            let consts = module.clone().consts;
             let imp = module.clone().imp;
 
-            // chain the declarations and import names together
+            // chain the declarations, constants and import names together
             let flat: HashMap<_, _> = decl
                 .into_iter()
-                .map(|d| match *d.ident {
+                .map(|d| d.ident)
+                .chain(consts.into_iter().map(|c| c.ident))
+                .map(|ident| match *ident {
                     Expr::Ident(m) => m,
                     _ => unreachable!(),
                 })
@@ -616,7 +818,7 @@ impl ModuleMap {
             // if there are any duplicates add them to the error list.
             for (func, count) in duplicates {
                 errors.push(Error::new(
-                    (0, 0),
+                    LineNo::default(),
                     ErrorVariant::ErrorCode(ErrorCode::FunctionNameCollision {
                         module: name.clone().join("::"),
                         func: func.clone(),
@@ -634,7 +836,7 @@ impl ModuleMap {
     }
 
     /// The [`ModuleMap.resolve`] function only resolves the imports,
-    /// but does not add the actual functions, this is done by this specific function.
+    /// but does not add the actual functions (and constants), this is done by this specific function.
     /// It fetches all declared functions for each loaded module and attaches them to the
     /// [`ModuleMap`]. This function also check potential collisions.
     fn insert_funcs(
@@ -677,6 +879,29 @@ impl ModuleMap {
                 ctx.0.insert(function_name, FuncContext::Func(func.clone()));
             }
 
+            for constant in &module.consts {
+                let const_name: FuncName = match *constant.clone().ident {
+                    Expr::Ident(m) => m,
+                    _ => unreachable!(),
+                }
+                .into();
+
+                if ctx.0.contains_key(&const_name) {
+                    errors.push(Error::new_from_code(
+                        Some(constant.lno),
+                        ErrorCode::FunctionNameCollision {
+                            module: name.join("::"),
+                            func: const_name.to_string(),
+                            count: None,
+                        },
+                    ));
+                    continue;
+                }
+
+                ctx.0
+                    .insert(const_name, FuncContext::Const(constant.clone()));
+            }
+
             context.0.insert(module_name, ctx);
         }
 
@@ -699,52 +924,99 @@ impl ModuleMap {
     /// Note(bmahmoud): currently we clone a lot, think about maybe using references to make
     ///                 memory footprint lower
     pub fn from(main: Module, directory: Directory) -> Result<ModuleMap, Vec<Error>> {
-        // The directory is always prefixed with fs::,
-        // while all others are looking into the /lib/ folder
-        let mut modules: HashMap<Vec<String>, Module> = Self::parse(directory)?;
+        Self::from_with_lib(main, directory, stdlib())
+    }
 
-        // prefix initial modules with the fs prefix
-        modules = modules
-            .iter()
-            .map(|(k, v)| {
-                let mut k_new = k.clone();
-                k_new.insert(0, "fs".to_string());
+    /// Same as [`ModuleMap::from`], but overrides the library root used for every module outside
+    /// of fs. The default is the embedded standard library (see [`stdlib`]).
+    pub fn from_with_lib(
+        main: Module,
+        directory: Directory,
+        lib: Directory,
+    ) -> Result<ModuleMap, Vec<Error>> {
+        Self::from_session(main, directory, lib, &mut Session::new())
+    }
 
-                (k_new, v.clone())
-            })
-            .collect();
+    /// Same as [`ModuleMap::from_with_lib`], but reuses the parsed modules and resolved contexts
+    /// of the previous compile in the [`Session`], only modules that changed (or depend on a changed
+    /// module) are resolved again.
+    pub fn from_session(
+        main: Module,
+        directory: Directory,
+        lib: Directory,
+        session: &mut Session,
+    ) -> Result<ModuleMap, Vec<Error>> {
+        let mut cache = Cache {
+            wildcard: HashMap::new(),
+            impfunc: HashMap::new(),
+
+            parsed: std::mem::take(&mut session.parsed),
+            hashes: HashMap::new(),
+            broken: HashMap::new(),
+        };
+
+        let res = Self::build(main, directory, lib, session, &mut cache);
+
+        // the parsed modules are always valid, even if we failed. Only the ones of this compile
+        // are kept, otherwise every edit of a file would add another module.
+        let current: HashSet<_> = cache.hashes.values().collect();
+        cache.parsed.retain(|key, _| current.contains(key));
+        session.parsed = cache.parsed;
+        if res.is_ok() {
+            session.hashes = cache.hashes;
+        }
+
+        res
+    }
+
+    fn build(
+        main: Module,
+        directory: Directory,
+        lib: Directory,
+        session: &mut Session,
+        cache: &mut Cache,
+    ) -> Result<ModuleMap, Vec<Error>> {
+        // The directory is always prefixed with fs::,
+        // while all others are looked up in the library directory
+        let mut modules: HashMap<Vec<String>, Module> = Self::parse(directory, cache);
 
         let main_module = (vec!["fs".to_string(), "main".to_string()], main);
         modules.insert(main_module.0.clone(), main_module.1.clone());
 
-        Self::basic_collision_check(&modules)?;
+        // library modules are loaded lazily, but we need to know if the ones
+        // from the previous compile changed.
+        let previous: Vec<_> = session
+            .hashes
+            .keys()
+            .filter(|name| name.first().cloned() != Some("fs".to_string()))
+            .cloned()
+            .collect();
+        for name in previous {
+            if let Some(contents) = lib.lookup(&name) {
+                let (key, module) = Self::parse_module(contents.as_str(), cache)?;
+
+                cache.hashes.insert(name.clone(), key);
+                modules.insert(name, module);
+            }
+        }
+
+        // modules that can be reused from the previous compile
+        let clean = session.clean(&modules, &cache.hashes);
 
         // import and resolve everything properly
         let mut errors = vec![];
         let mut map = ModuleMap::new();
-        let mut cache = Cache {
-            wildcard: HashMap::new(),
-            impfunc: HashMap::new(),
-        };
+        for name in &clean {
+            let name: ModuleName = name.clone().into();
+            if let Some(ctx) = session.map.get(&name) {
+                map.insert(name, ctx.clone());
+            }
+        }
 
         let mut ptr = 0;
-        // stack of modules to resolve()
-        let mut stack: Vec<_> = modules
-            .keys()
-            .cloned()
-            .clone()
-            .into_iter()
-            .sorted_by(|a, b| {
-                // Guarantee that the main module is always the first one in the stack
-                if a.cmp(&main_module.0) == Ordering::Equal {
-                    Ordering::Less
-                } else if b.cmp(&main_module.0) == Ordering::Equal {
-                    Ordering::Greater
-                } else {
-                    a.cmp(b)
-                }
-            })
-            .collect();
+        // stack of modules to resolve(), starting from the main module only the modules it
+        // (transitively) imports are resolved, other files in the directory are not used.
+        let mut stack: Vec<_> = vec![main_module.0];
 
         while ptr < stack.len() {
             // we know it is safe, panic if we cannot unwrap
@@ -763,15 +1035,20 @@ impl ModuleMap {
             }
             let module = module.unwrap().clone();
 
-            // iterate over all modules, not only Main so that we can be sure everything is included
-            let res = Self::resolve((&name, &module), &mut modules, &mut cache);
+            // the imported modules are resolved as well, so that we can be sure everything is included
+            let res = Self::resolve((&name, &module), &mut modules, &lib, cache);
 
             if let Err(err) = res {
                 errors.extend(err);
             } else {
                 let (ctx, modules) = res.unwrap();
 
-                let new: Vec<_> = modules.into_iter().filter(|m| stack.contains(m)).collect();
+                let new: Vec<_> = modules
+                    .into_iter()
+                    .chain(deps(&module))
+                    .filter(|m| !stack.contains(m) && !clean.contains(m))
+                    .unique()
+                    .collect();
                 stack.extend(new);
 
                 let context = ModuleContext(ctx);
@@ -779,11 +1056,22 @@ impl ModuleMap {
             }
         }
 
-        let res = Self::insert_funcs(&modules, &mut map);
+        // reused modules already have all their functions
+        let resolved: HashMap<_, _> = modules
+            .into_iter()
+            .filter(|(name, _)| stack.contains(name))
+            .collect();
+
+        // collisions take precedence, as they are likely the cause of the other errors
+        Self::basic_collision_check(&resolved)?;
+
+        let res = Self::insert_funcs(&resolved, &mut map);
         if let Err(err) = res {
             errors.extend(err)
         }
 
+        session.recompiled = stack;
+
         if !errors.is_empty() {
             Err(errors
                 .into_iter()
@@ -806,11 +1094,12 @@ mod test {
     use crate::ast::hir::func::module::ctx::ModuleContext;
     use crate::ast::hir::func::module::map::ModuleMap;
 
-    use crate::ast::hir::func::structs::FuncContext::{Func, Import};
+    use crate::ast::hir::func::structs::FuncContext::{Func, Import, Namespace};
     use crate::ast::hir::func::structs::FuncImport;
     use crate::ast::hir::Hir;
     use crate::build::Builder;
     use crate::errors::{Error, ErrorCode, ErrorVariant};
+    use crate::types::LineNo;
 
     #[test]
     fn test_fs_import() -> Result<(), Vec<Error>> {
@@ -848,7 +1137,8 @@ mod test {
             ctx.0.insert(
                 "b".into(),
                 Func(FuncDecl {
-                    lno: (1, 3),
+                    lno: LineNo::new((1, 1), (3, 4)),
+                    public: false,
 
                     ident: Box::new(Expr::Ident("b".into())),
                     params: vec![Expr::Ident("b".into())],
@@ -919,7 +1209,8 @@ mod test {
             ctx.0.insert(
                 "c".into(),
                 Func(FuncDecl {
-                    lno: (1, 3),
+                    lno: LineNo::new((1, 1), (3, 4)),
+                    public: false,
 
                     ident: Box::new(Expr::Ident("c".into())),
                     params: vec![Expr::Ident("d".into())],
@@ -968,6 +1259,47 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_std_namespace() -> Result<(), Vec<Error>> {
+        let snip = indoc! {"
+        IMPORT std::math AS m
+        "};
+
+        let dir = Directory::new();
+        let ast = Builder::parse(snip, None).map_err(|err| vec![Error::new_from_parse(err)])?;
+        let map = ModuleMap::from(ast, dir)?;
+
+        let module_name = vec!["fs", "main"].into();
+        let main = map.0.get(&module_name).unwrap().clone();
+        assert_eq!(main.0.len(), 1);
+
+        let namespace = match main.0.get(&"m".into()) {
+            Some(Namespace(namespace)) => namespace.clone(),
+            ctx => panic!("expected a namespace, got {:?}", ctx),
+        };
+        assert_eq!(namespace.module, vec!["std", "math"].into());
+        assert!(namespace.exports.contains(&"max".into()));
+
+        // calls through the namespace are looked up in the imported module
+        let (qual, func) = map.lookup(None, &module_name, &"m::max".into())?;
+        assert_eq!(qual.to_string(), "std::math::max");
+        assert!(matches!(func, Func(_)));
+
+        let errors = map
+            .lookup(None, &module_name, &"m::avg".into())
+            .unwrap_err();
+        assert_eq!(
+            errors[0].variant,
+            ErrorVariant::ErrorCode(ErrorCode::CouldNotFindFunction {
+                module: "std::math".to_string(),
+                func: "avg".to_string(),
+            })
+        );
+        assert!(map.lookup(None, &module_name, &"m".into()).is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_std_wildcard() -> Result<(), Vec<Error>> {
         let snip = indoc! {"
@@ -1000,6 +1332,40 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_lib_override() -> Result<(), Vec<Error>> {
+        let snip = indoc! {"
+        FROM course::util IMPORT inc
+        "};
+
+        let util = indoc! {"
+        FN inc(a) -> b DECL
+            b := a + 1
+        END
+        "};
+
+        let mut course = Directory::new();
+        course.insert("util".to_string(), util.to_string().into());
+        let mut lib = Directory::new();
+        lib.insert("course".to_string(), course.into());
+
+        let ast = Builder::parse(snip, None).map_err(|err| vec![Error::new_from_parse(err)])?;
+        let map = ModuleMap::from_with_lib(ast.clone(), Directory::new(), lib)?;
+
+        let util_name = vec!["course", "util"].into();
+        assert!(map.0.contains_key(&util_name));
+
+        // the override replaces the embedded library
+        let snip = indoc! {"
+        FROM std::math IMPORT max
+        "};
+        let ast = Builder::parse(snip, None).map_err(|err| vec![Error::new_from_parse(err)])?;
+        let map = ModuleMap::from_with_lib(ast, Directory::new(), Directory::new());
+        assert!(map.is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_fs_wildcard() -> Result<(), Vec<Error>> {
         let snip = indoc! {"
@@ -1343,6 +1709,163 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_wildcard_private() -> Result<(), Vec<Error>> {
+        let snip = indoc! {"
+        FROM fs::a IMPORT *
+
+        FN d(d) -> e DECL
+            ...
+        END
+        "};
+
+        let module_a = indoc! {"
+        PUB FN c(c) -> e DECL
+            ...
+        END
+
+        FN d(d) -> e DECL
+            ...
+        END
+        "};
+
+        let mut dir = Directory::new();
+        dir.insert("a".to_string(), module_a.to_string().into());
+
+        let ast = Builder::parse(snip, None).map_err(|err| vec![Error::new_from_parse(err)])?;
+        let map = ModuleMap::from(ast, dir)?;
+
+        let main = map
+            .0
+            .get(&vec!["fs", "main"].into())
+            .cloned()
+            .expect("Expected fs::main context, get None.");
+
+        // d is private in fs::a and therefore does not collide
+        assert!(main.0.contains_key(&"c".into()));
+        assert!(matches!(main.0.get(&"d".into()), Some(Func(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_private() -> Result<(), Vec<Error>> {
+        let snip = indoc! {"
+        FROM fs::a IMPORT d
+        "};
+
+        let module_a = indoc! {"
+        PUB FN c(c) -> e DECL
+            ...
+        END
+
+        FN d(d) -> e DECL
+            ...
+        END
+        "};
+
+        let mut dir = Directory::new();
+        dir.insert("a".to_string(), module_a.to_string().into());
+
+        let ast = Builder::parse(snip, None).map_err(|err| vec![Error::new_from_parse(err)])?;
+        let map = ModuleMap::from(ast, dir);
+
+        let err = map.expect_err("Expected error, but somehow d is exported?");
+        assert_eq!(err.len(), 1);
+        let err = err.first().cloned().expect("Expected at lease one error");
+        // the error points at the import statement
+        assert_eq!(err.lno.row, (1, 1));
+
+        match err.variant {
+            ErrorVariant::ErrorCode(ErrorCode::FunctionNotExported { module, func }) => {
+                assert_eq!(module, "fs::a".to_string());
+                assert_eq!(func, "d".to_string());
+            }
+            _ => panic!("Expected ErrorCode::FunctionNotExported"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_not_reexported() -> Result<(), Vec<Error>> {
+        let snip = indoc! {"
+        FROM fs::a IMPORT min
+        "};
+
+        let module_a = indoc! {"
+        PUB FROM std::math IMPORT max
+        FROM std::math IMPORT min
+        "};
+
+        let mut dir = Directory::new();
+        dir.insert("a".to_string(), module_a.to_string().into());
+
+        let ast = Builder::parse(snip, None).map_err(|err| vec![Error::new_from_parse(err)])?;
+        let map = ModuleMap::from(ast, dir);
+
+        let err = map.expect_err("Expected error, but somehow min is re-exported?");
+        assert_eq!(err.len(), 1);
+        let err = err.first().cloned().expect("Expected at lease one error");
+
+        match err.variant {
+            ErrorVariant::ErrorCode(ErrorCode::FunctionNotExported { module, func }) => {
+                assert_eq!(module, "fs::a".to_string());
+                assert_eq!(func, "min".to_string());
+            }
+            _ => panic!("Expected ErrorCode::FunctionNotExported"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_transitive_std_import() -> Result<(), Vec<Error>> {
+        let snip = indoc! {"
+        FROM fs::a IMPORT larger
+        "};
+
+        let module_a = indoc! {"
+        FROM std::math IMPORT max
+
+        PUB FN larger(a, b) -> c DECL
+            c := max(a, b)
+        END
+        "};
+
+        let mut dir = Directory::new();
+        dir.insert("a".to_string(), module_a.to_string().into());
+
+        let ast = Builder::parse(snip, None).map_err(|err| vec![Error::new_from_parse(err)])?;
+        let map = ModuleMap::from(ast, dir)?;
+
+        // modules found while resolving are resolved as well, not only the main module
+        let math = map.0.get(&vec!["std", "math"].into());
+        assert!(math.is_some());
+        assert!(matches!(math.unwrap().0.get(&"max".into()), Some(Func(_))));
+        assert!(map.0.contains_key(&vec!["fs", "a"].into()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_std_directory_not_module() -> Result<(), Vec<Error>> {
+        // std::macros is a directory of macro modules, not a module of functions
+        let snip = indoc! {"
+        FROM std::macros IMPORT *
+        "};
+
+        let ast = Builder::parse(snip, None).map_err(|err| vec![Error::new_from_parse(err)])?;
+        let err = ModuleMap::from(ast, Directory::new()).expect_err("Expected error");
+
+        assert!(matches!(
+            &err[0].variant,
+            ErrorVariant::ErrorCode(ErrorCode::CouldNotFindModule { module }) if module == "std::macros"
+        ));
+
+        Ok(())
+    }
+
     #[test]
     fn test_no_std_module() -> Result<(), Vec<Error>> {
         let snip = indoc! {"
@@ -1388,4 +1911,31 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_broken_fs_module() -> Result<(), Vec<Error>> {
+        let snip = indoc! {"
+        FROM fs::a IMPORT b
+        "};
+
+        let mut dir = Directory::new();
+        dir.insert("a".to_string(), "FN b(a) -> b DECL\n    ...\nEND".into());
+        dir.insert("c".to_string(), "FN d(a -> b DECL\n    ...\nEND".into());
+        dir.insert("e".to_string(), "FROM fs::x IMPORT y".into());
+
+        // unrelated modules are only parsed, but never resolved
+        let ast = Builder::parse(snip, None).map_err(|err| vec![Error::new_from_parse(err)])?;
+        ModuleMap::from(ast, dir.clone())?;
+
+        let snip = indoc! {"
+        FROM fs::c IMPORT d
+        "};
+
+        let ast = Builder::parse(snip, None).map_err(|err| vec![Error::new_from_parse(err)])?;
+        let err = ModuleMap::from(ast, dir).expect_err("Expected the parse error of fs::c");
+        assert_eq!(err.len(), 1);
+        assert!(matches!(err[0].variant, ErrorVariant::Parse(_)));
+
+        Ok(())
+    }
 }
@@ -1,2 +1,3 @@
 pub mod ctx;
 pub mod map;
+pub mod session;
@@ -0,0 +1,321 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use crate::ast::context::CompileContext;
+use crate::ast::expr::Expr;
+use crate::ast::hir::func::module::map::ModuleMap;
+use crate::ast::hir::func::structs::modname::ModuleName;
+use crate::ast::module::Module;
+use crate::flags::CompileFlags;
+
+/// Content hash used to key the parsed modules.
+pub fn hash(contents: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+/// The modules a module directly depends on, this includes namespaced imports.
+pub fn deps(module: &Module) -> HashSet<Vec<String>> {
+    module
+        .imp
+        .iter()
+        .map(|imp| &imp.path)
+        .chain(module.imp_mod.iter().map(|imp| &imp.path))
+        .map(|path| {
+            path.iter()
+                .map(|p| match p {
+                    Expr::Ident(m) => m.clone(),
+                    _ => unreachable!(),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// A compilation session, which persists between compiles.
+/// Every module is keyed by the hash of its contents, should a module change, only the module
+/// and modules that depend on it (transitively) are resolved and inlined again.
+/// The main module is always compiled again.
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    // content hash -> parsed module (with erased code)
+    pub(crate) parsed: HashMap<u64, Module>,
+
+    // content hashes of every module from the last compile
+    pub(crate) hashes: HashMap<Vec<String>, u64>,
+
+    // resolved contexts (including already inlined functions) from the last compile,
+    // these are only valid for the same flags.
+    pub(crate) map: ModuleMap,
+    flags: Option<CompileFlags>,
+    // inlined functions keep their prefixed names, we need to continue counting from there.
+    pub(crate) inline_counter: HashMap<String, usize>,
+
+    // modules that were resolved during the last compile, every other module has been reused.
+    pub recompiled: Vec<Vec<String>>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inlined functions depend on the flags, if they changed, nothing can be reused.
+    /// Without any inlined function left, the inline counter can start over.
+    pub(crate) fn prepare(&mut self, flags: CompileFlags) {
+        if self.flags != Some(flags) {
+            self.map = ModuleMap::new();
+            self.inline_counter = HashMap::new();
+            self.flags = Some(flags);
+        }
+    }
+
+    /// Determines which modules can be reused, a module is only reused if it did not change,
+    /// was successfully resolved previously and none of its dependencies changed.
+    pub(crate) fn clean(
+        &self,
+        modules: &HashMap<Vec<String>, Module>,
+        hashes: &HashMap<Vec<String>, u64>,
+    ) -> HashSet<Vec<String>> {
+        let main = ModuleName::main();
+
+        let mut dirty: HashSet<Vec<String>> = modules
+            .keys()
+            .filter(|name| {
+                let module_name: ModuleName = (*name).clone().into();
+
+                module_name == main
+                    || self.hashes.get(*name) != hashes.get(*name)
+                    || !self.map.contains_key(&module_name)
+            })
+            .cloned()
+            .collect();
+
+        // removed modules also invalidate their dependents
+        dirty.extend(
+            self.hashes
+                .keys()
+                .filter(|name| !hashes.contains_key(*name))
+                .cloned(),
+        );
+
+        // propagate along the reverse dependencies until nothing changes anymore
+        let graph: HashMap<_, _> = modules.iter().map(|(k, v)| (k, deps(v))).collect();
+        loop {
+            let next: Vec<_> = graph
+                .iter()
+                .filter(|(name, _)| !dirty.contains(**name))
+                .filter(|(_, deps)| deps.iter().any(|dep| dirty.contains(dep)))
+                .map(|(name, _)| (*name).clone())
+                .collect();
+
+            if next.is_empty() {
+                break;
+            }
+            dirty.extend(next);
+        }
+
+        modules
+            .keys()
+            .filter(|name| !dirty.contains(*name))
+            .cloned()
+            .collect()
+    }
+
+    /// Saves the state of the compilation for the next compile, the main module is never saved.
+    /// This is also done if the compilation failed, every inlined function is still valid.
+    ///
+    /// The inline counter is carried forward as is and only grows while the flags stay the same.
+    /// It is keyed by the short function name, which is shared by all modules, and the reused
+    /// modules still contain the prefixed variables of their inlined functions. Resetting the
+    /// counter of a recompiled module could therefore hand out a prefix a reused module uses.
+    pub(crate) fn store(&mut self, context: &CompileContext) {
+        let mut map = context.modules.clone();
+        map.remove(&ModuleName::main());
+
+        self.map = map;
+        self.inline_counter = context.inline_counter().clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use crate::ast::hir::func::fs::Directory;
+    use crate::ast::hir::func::module::session::Session;
+    use crate::build::Builder;
+    use crate::errors::Error;
+    use crate::flags::CompileFlags;
+
+    fn compile(
+        snip: &str,
+        dir: &Directory,
+        session: &mut Session,
+    ) -> Result<crate::ast::expr::Expr, Vec<Error>> {
+        let mut module =
+            Builder::parse(snip, None).map_err(|err| vec![Error::new_from_parse(err)])?;
+
+        Builder::compile_in_session(&mut module, None, Some(dir.clone()), None, session)
+    }
+
+    #[test]
+    fn test_session_reuse() -> Result<(), Vec<Error>> {
+        let snip = indoc! {"
+        from fs::a import inc
+        from fs::c import dec
+
+        x := inc(y)
+        z := dec(y)
+        "};
+
+        let module_a = indoc! {"
+        from fs::b import one
+
+        fn inc(a) -> b decl
+            c := one(a)
+            b := a + c
+        end
+        "};
+        let module_b = indoc! {"
+        fn one(a) -> b decl
+            b := 1
+        end
+        "};
+        let module_c = indoc! {"
+        fn dec(a) -> b decl
+            b := a - 1
+        end
+        "};
+
+        let mut dir = Directory::new();
+        dir.insert("a".to_string(), module_a.into());
+        dir.insert("b".to_string(), module_b.into());
+        dir.insert("c".to_string(), module_c.into());
+
+        let mut session = Session::new();
+        let initial = compile(snip, &dir, &mut session)?;
+        assert_eq!(session.recompiled.len(), 4);
+
+        // nothing changed, only main needs to be compiled
+        let cached = compile(snip, &dir, &mut session)?;
+        assert_eq!(session.recompiled, vec![vec!["fs", "main"]]);
+        assert_eq!(session.parsed.len(), 3);
+
+        // the program stays the same, but previously inlined functions are reused
+        let mut runtime = Builder::eval(cached);
+        let mut reference = Builder::eval(initial);
+        while runtime.is_running() {
+            runtime.step();
+        }
+        while reference.is_running() {
+            reference.step();
+        }
+        let (runtime, reference) = (runtime.context(), reference.context());
+        assert_eq!(runtime.get("x"), reference.get("x"));
+        assert_eq!(runtime.get("z"), reference.get("z"));
+
+        // b changed, this means b and everything that depends on b is invalidated
+        dir.insert(
+            "b".to_string(),
+            indoc! {"
+            fn one(a) -> b decl
+                b := 2
+            end
+            "}
+            .into(),
+        );
+        compile(snip, &dir, &mut session)?;
+
+        let mut recompiled = session.recompiled.clone();
+        recompiled.sort();
+        assert_eq!(
+            recompiled,
+            vec![vec!["fs", "a"], vec!["fs", "b"], vec!["fs", "main"]]
+        );
+
+        // the previous version of b is dropped, as well as removed modules
+        assert_eq!(session.parsed.len(), 3);
+        dir.remove("c");
+        compile("from fs::a import inc\n\nx := inc(y)\n", &dir, &mut session)?;
+        assert_eq!(session.parsed.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_session_change() -> Result<(), Vec<Error>> {
+        let snip = indoc! {"
+        from fs::a import inc
+
+        x := inc(x)
+        "};
+
+        let mut dir = Directory::new();
+        dir.insert(
+            "a".to_string(),
+            indoc! {"
+            fn inc(a) -> b decl
+                b := a + 1
+            end
+            "}
+            .into(),
+        );
+
+        let mut session = Session::new();
+        compile(snip, &dir, &mut session)?;
+
+        dir.insert(
+            "a".to_string(),
+            indoc! {"
+            fn inc(a) -> b decl
+                b := a + 2
+            end
+            "}
+            .into(),
+        );
+        let expr = compile(snip, &dir, &mut session)?;
+
+        let mut runtime = Builder::eval(expr);
+        while runtime.is_running() {
+            runtime.step();
+        }
+
+        let x = runtime.context().get("x").cloned();
+        assert_eq!(x, Some(2u8.into()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_session_flags() -> Result<(), Vec<Error>> {
+        let snip = "from fs::a import inc\n\nx := inc(x)\n";
+
+        let mut dir = Directory::new();
+        dir.insert(
+            "a".to_string(),
+            "fn inc(a) -> b decl\n    b := a + 1\nend\n".into(),
+        );
+
+        let mut session = Session::new();
+        compile(snip, &dir, &mut session)?;
+        let initial = session.inline_counter.clone();
+
+        // nothing is reused with other flags, the inline counter starts over
+        let mut module =
+            Builder::parse(snip, None).map_err(|err| vec![Error::new_from_parse(err)])?;
+        Builder::compile_in_session(
+            &mut module,
+            Some(CompileFlags::LOOP),
+            Some(dir.clone()),
+            None,
+            &mut session,
+        )?;
+        assert_eq!(session.inline_counter, initial);
+
+        Ok(())
+    }
+}
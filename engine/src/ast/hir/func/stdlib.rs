@@ -0,0 +1,105 @@
+use include_dir::{include_dir, Dir};
+
+use crate::ast::hir::func::fs::{is_module, Directory, Path};
+
+// The standard library is embedded at compile time, this way std::* resolution does not depend on
+// a filesystem, which we do not have in WASM.
+static STD: Dir = include_dir!("$CARGO_MANIFEST_DIR/lib/std");
+
+fn embed(dir: &Dir) -> Directory {
+    let mut directory = Directory::new();
+
+    for child in dir.dirs() {
+        let name = child
+            .path()
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string());
+
+        if let Some(name) = name {
+            directory.insert(name, embed(child).into());
+        }
+    }
+
+    for file in dir.files() {
+        let path = file.path();
+        if !is_module(path) {
+            continue;
+        }
+
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string());
+        let contents = file.contents_utf8();
+
+        if let (Some(name), Some(contents)) = (name, contents) {
+            directory.insert(name, Path::from(contents));
+        }
+    }
+
+    directory
+}
+
+/// Library root used to resolve every module that is not under the fs namespace,
+/// contains the embedded standard library under `std`.
+pub fn stdlib() -> Directory {
+    let mut root = Directory::new();
+    root.insert("std".to_string(), embed(&STD).into());
+
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::hir::func::fs::is_module;
+    use crate::ast::hir::func::stdlib::{stdlib, STD};
+    use include_dir::Dir;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_stdlib_embedded() {
+        let lib = stdlib();
+
+        assert!(lib
+            .lookup(&["std".to_string(), "math".to_string()])
+            .is_some());
+        assert!(lib
+            .lookup(&["std".to_string(), "prelude".to_string()])
+            .is_some());
+        assert!(lib
+            .lookup(&[
+                "std".to_string(),
+                "macros".to_string(),
+                "assign".to_string()
+            ])
+            .is_some());
+
+        // directories are not modules
+        assert!(lib
+            .lookup(&["std".to_string(), "macros".to_string()])
+            .is_none());
+    }
+
+    // embed() keys modules by their name, math.lp and math.loop would silently replace each other
+    fn assert_unique(dir: &Dir) {
+        let mut names = HashSet::new();
+
+        for child in dir.dirs() {
+            assert_unique(child);
+            assert!(names.insert(child.path().file_name().unwrap().to_os_string()));
+        }
+
+        for file in dir.files().filter(|file| is_module(file.path())) {
+            let path = file.path();
+            assert!(
+                names.insert(path.file_stem().unwrap().to_os_string()),
+                "{} is shadowed by another module of the same name",
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn test_stdlib_unique_names() {
+        assert_unique(&STD);
+    }
+}
@@ -2,7 +2,7 @@ use funcname::FuncName;
 use modname::ModuleName;
 
 use crate::ast::expr::Expr;
-use crate::ast::hir::func::decl::FuncDecl;
+use crate::ast::hir::func::decl::{ConstDecl, FuncDecl};
 use crate::types::LineNo;
 
 pub mod funcname;
@@ -27,6 +27,13 @@ pub struct FuncInline {
     pub terms: Expr,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FuncNamespace {
+    pub module: ModuleName,
+    // the functions exported by the module, sorted by name
+    pub exports: Vec<FuncName>,
+}
+
 sum_type! {
     #[derive(Debug, Clone, PartialEq, Eq, Hash)]
     pub enum FuncContext {
@@ -40,6 +47,14 @@ sum_type! {
         /// This means it is already inlined
         /// and can be used
         Inline(FuncInline),
+
+        /// This means it is a module level constant,
+        /// which cannot be called
+        Const(ConstDecl),
+
+        /// This means it is a namespaced import of a module,
+        /// its exported functions are called through the namespace
+        Namespace(FuncNamespace),
     }
 }
 
@@ -21,6 +21,19 @@ impl FuncQualName {
         &mut self.1
     }
 
+    /// Resolves the name of a call in a module, namespaced calls (m::max)
+    /// are split into their namespace and function.
+    pub fn from_path(module: &ModuleName, name: &FuncName) -> Self {
+        let mut path: Vec<String> = name.split("::").map(|p| p.to_string()).collect();
+        let func = path.pop().unwrap_or_default();
+
+        if path.is_empty() {
+            Self(module.clone(), func.into())
+        } else {
+            Self(path.into(), func.into())
+        }
+    }
+
     pub fn func_smol(&self) -> String {
         self.1.chars().into_iter().take(3).collect()
     }
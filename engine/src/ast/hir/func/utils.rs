@@ -1,3 +1,7 @@
+use itertools::Itertools;
+
+use crate::ast::context::CompileContext;
+use crate::ast::control::Control;
 use crate::ast::expr::Expr;
 use crate::ast::hir::func::structs::funcname::FuncName;
 use crate::ast::hir::func::structs::modname::ModuleName;
@@ -49,3 +53,24 @@ pub fn could_not_find_function(
 pub fn prefix_ident(qual: &FuncQualName, count: &usize, ident: &str) -> String {
     format!("_{}_{}_{}", qual.func_smol(), count, ident)
 }
+
+/// Prepends the initialization of every constant visible in the module and used by the terms,
+/// this needs to happen after [`Expr::verify`], as the initialization is an assignment.
+pub fn init_consts(context: &CompileContext, module: &ModuleName, terms: Expr) -> Expr {
+    let used = terms.idents();
+    let mut expr: Vec<_> = context
+        .modules
+        .constants(module)
+        .into_iter()
+        .filter(|(name, _)| used.contains(name))
+        .sorted_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(name, decl)| decl.init(&name))
+        .collect();
+
+    if expr.is_empty() {
+        return terms;
+    }
+
+    expr.push(terms);
+    Expr::Control(Control::Terms(expr))
+}
@@ -7,7 +7,7 @@ use crate::ast::hir::macros::Macro;
 use crate::ast::hir::Hir;
 use crate::ast::variant::UInt;
 use crate::ast::verbs::{ComparisonVerb, OperatorVerb};
-use crate::errors::{Error, ErrorCode, ErrorVariant, StdResult, StrictModeViolation};
+use crate::errors::{Error, ErrorCode, StdResult, StrictModeViolation};
 use crate::flags::CompileFlags;
 use crate::types::LineNo;
 use crate::utils;
@@ -31,11 +31,9 @@ pub(crate) fn lower_loop(
 
     let mut maybe = vec![maybe_ident, maybe_terms];
     if !context.flags.intersects(CompileFlags::LOOP_AND_WHILE) {
-        maybe.push(Err(vec![Error::new(
-            lno,
-            ErrorVariant::Message(String::from(
-                "Cannot use LOOP if LOOP and WHILE are not enabled!",
-            )),
+        maybe.push(Err(vec![Error::new_from_code(
+            Some(lno),
+            ErrorCode::LoopNotEnabled,
         )]))
     }
 
@@ -118,9 +116,9 @@ pub(crate) fn lower_while(
 
     let mut maybe = vec![maybe_comp, maybe_terms];
     if !context.flags.contains(CompileFlags::WHILE) {
-        maybe.push(Err(vec![Error::new(
-            lno,
-            ErrorVariant::Message(String::from("Cannot replicate WHILE in LOOP mode!")),
+        maybe.push(Err(vec![Error::new_from_code(
+            Some(lno),
+            ErrorCode::WhileNotEnabled,
         )]))
     }
 
@@ -98,7 +98,7 @@ impl Macro {
         }
     }
 
-    fn lno(&self) -> Option<LineNo> {
+    pub(crate) fn lno(&self) -> Option<LineNo> {
         match self {
             Macro::AssignToIdent { lno, .. } => Some(*lno),
             Macro::AssignToZero { lno, .. } => Some(*lno),
@@ -1,9 +1,10 @@
+use indoc::indoc;
 #[cfg(feature = "cli")]
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use func::Func;
-use macros::Macro;
+use macros::{Macro, MacroAssign};
 
 use crate::ast::context::CompileContext;
 use crate::ast::control::Control;
@@ -11,6 +12,7 @@ use crate::ast::expr::Expr;
 use crate::ast::hir::lower::{lower_loop, lower_terms, lower_while};
 use crate::errors::StdResult;
 
+pub mod decompile;
 pub mod func;
 pub mod lower;
 pub mod macros;
@@ -47,4 +49,118 @@ impl Hir {
 
         Ok(result)
     }
+
+    /* Display human friendly representation, like Expr::display but with macros */
+    pub fn display(&self, indent: u8, level: Option<u8>) -> String {
+        let level = level.or(Some(0));
+        let spacing = " ".repeat((indent * level.unwrap()) as usize);
+        let expr = |expr: &Expr| expr.display(indent, level);
+        let body = |terms: &Hir| terms.display(indent, level.map(|c| c + 1));
+
+        match self {
+            Hir::Expr(e) => expr(e),
+            Hir::Macro(Macro::AssignToIdent { lhs, rhs, .. })
+            | Hir::Macro(Macro::AssignToValue { lhs, rhs, .. }) => {
+                format!("{}{} := {}", spacing, expr(lhs), expr(rhs))
+            }
+            Hir::Macro(Macro::AssignToZero { lhs, .. }) => format!("{}{} := 0", spacing, expr(lhs)),
+            Hir::Macro(Macro::AssignToIdentBinOpIdent {
+                lhs,
+                rhs:
+                    MacroAssign {
+                        lhs: x,
+                        verb,
+                        rhs: y,
+                    },
+                ..
+            })
+            | Hir::Macro(Macro::AssignToIdentExtBinOpValue {
+                lhs,
+                rhs:
+                    MacroAssign {
+                        lhs: x,
+                        verb,
+                        rhs: y,
+                    },
+                ..
+            }) => format!(
+                "{}{} := {} {} {}",
+                spacing,
+                expr(lhs),
+                expr(x),
+                verb,
+                expr(y)
+            ),
+            Hir::Macro(Macro::Conditional {
+                comp,
+                if_terms,
+                else_terms,
+                ..
+            }) => match &**else_terms {
+                Some(else_terms) => format!(
+                    indoc!(
+                        "\n\
+                         {s}IF {comp} THEN
+                         {if_terms}
+                         {s}ELSE
+                         {else_terms}
+                         {s}END"
+                    ),
+                    comp = expr(comp),
+                    if_terms = body(if_terms),
+                    else_terms = body(else_terms),
+                    s = spacing
+                ),
+                None => format!(
+                    indoc!(
+                        "\n\
+                         {s}IF {comp} THEN
+                         {if_terms}
+                         {s}END"
+                    ),
+                    comp = expr(comp),
+                    if_terms = body(if_terms),
+                    s = spacing
+                ),
+            },
+            Hir::Function(Func::Call { lhs, rhs, .. }) => format!(
+                "{}{} := {}({})",
+                spacing,
+                expr(lhs),
+                expr(&rhs.ident),
+                rhs.args.iter().map(expr).collect::<Vec<_>>().join(", ")
+            ),
+            Hir::NoOp => String::new(),
+            // blocks are separated by an empty line, but not at the start of a body
+            Hir::Control(Control::Terms(terms)) => terms
+                .iter()
+                .map(|term| term.display(indent, level))
+                .collect::<Vec<String>>()
+                .join("\n")
+                .trim_start_matches('\n')
+                .to_string(),
+            Hir::Control(Control::Loop { ident, terms, .. }) => format!(
+                indoc!(
+                    "\n\
+                     {s}LOOP {ident} DO
+                     {terms}
+                     {s}END"
+                ),
+                ident = ident.display(indent, level),
+                terms = body(terms),
+                s = spacing
+            ),
+            Hir::Control(Control::While { comp, terms, .. }) => format!(
+                indoc!(
+                    "\n\
+                     {s}WHILE {comp} DO
+                     {terms}
+                     {s}END"
+                ),
+                comp = comp.display(indent, level),
+                terms = body(terms),
+                s = spacing
+            ),
+        }
+    }
 }
@@ -2,12 +2,15 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::ast::hir::func::decl::FuncDecl;
-use crate::ast::hir::func::imp::Imp;
+use crate::ast::expr::Expr;
+use crate::ast::hir::func::decl::{ConstDecl, FuncDecl};
+use crate::ast::hir::func::imp::{Imp, ImpModule};
 use crate::ast::hir::Hir;
 
 // Used as a container for all Module related codes, this contains:
 // - imports (imp)
+// - namespaced imports (imp_mod)
+// - constants (consts)
 // - declarations (decl)
 // - code (code)
 // Code is replaced with NoOp if it is loaded via an import
@@ -19,6 +22,14 @@ pub struct Module {
     // from path1::path2::path3 import (ident1 as alias1, ident2)
     // on top of the file.
     pub imp: Vec<Imp>,
+    // Namespaced imports correspond to
+    // import path1::path2::path3 as alias
+    // mixed with the other imports on top of the file.
+    pub imp_mod: Vec<ImpModule>,
+    // Consts corresponds to
+    // const ident = value
+    // after imports, before declarations
+    pub consts: Vec<ConstDecl>,
     // Decl corresponds to
     // fn ident(arg1, arg2, arg3, ...) -> ret decl
     //  terms
@@ -29,3 +40,61 @@ pub struct Module {
     // actual code that is run on execution
     pub code: Hir,
 }
+
+impl Module {
+    /// Modules without any pub declaration export everything (this includes their imports),
+    /// as soon as a single declaration or import is pub, only the pub declarations are exported.
+    pub fn exports_all(&self) -> bool {
+        !self.decl.iter().any(|func| func.public)
+            && !self.consts.iter().any(|c| c.public)
+            && !self.imp.iter().any(|imp| imp.public)
+    }
+
+    /// Checks if a name can be imported from this module.
+    pub fn is_exported(&self, name: &str) -> bool {
+        if self.exports_all() {
+            return true;
+        }
+
+        let ident = Expr::Ident(name.to_string());
+        self.decl
+            .iter()
+            .filter(|func| func.public)
+            .map(|func| &func.ident)
+            .chain(self.consts.iter().filter(|c| c.public).map(|c| &c.ident))
+            .chain(
+                self.imp
+                    .iter()
+                    .filter(|imp| imp.public)
+                    .filter_map(|imp| imp.funcs.as_ref().left())
+                    .flatten()
+                    .map(|func| func.alias.as_ref().unwrap_or(&func.ident)),
+            )
+            .any(|decl| **decl == ident)
+    }
+
+    /// pub wildcard imports could export any name, we only know after resolving them.
+    pub fn has_public_wildcard(&self) -> bool {
+        self.imp
+            .iter()
+            .any(|imp| imp.public && imp.funcs.is_right())
+    }
+
+    /// Checks if the name is known in the module, either through a declaration
+    /// or an explicit import, this ignores wildcard imports.
+    pub fn is_declared(&self, name: &str) -> bool {
+        let ident = Expr::Ident(name.to_string());
+
+        self.decl
+            .iter()
+            .map(|func| &func.ident)
+            .chain(self.consts.iter().map(|c| &c.ident))
+            .any(|decl| **decl == ident)
+            || self
+                .imp
+                .iter()
+                .filter_map(|imp| imp.funcs.as_ref().left())
+                .flatten()
+                .any(|func| **func.alias.as_ref().unwrap_or(&func.ident) == ident)
+    }
+}
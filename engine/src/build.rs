@@ -5,7 +5,10 @@ use serde::{Deserialize, Serialize};
 use crate::ast::context::CompileContext;
 
 use crate::ast::expr::Expr;
+use crate::ast::hir::decompile::decompile;
 use crate::ast::hir::func;
+use crate::ast::hir::func::module::session::Session;
+use crate::ast::hir::Hir;
 
 use crate::ast::module::Module;
 use crate::errors;
@@ -13,10 +16,14 @@ use crate::errors::StdResult;
 use crate::eval::exec::Exec;
 use crate::eval::types::Variables;
 use crate::flags::CompileFlags;
+use crate::frontend;
+use crate::frontend::Frontend;
 use crate::parser::Rule;
 use crate::parser::{LoopParser, ParseSettings};
 use crate::runtime::Runtime;
 use crate::types::LineNo;
+#[cfg(feature = "cli")]
+use resolve::macros::trace::Trace;
 
 #[derive(Serialize, Deserialize)]
 pub struct Builder {}
@@ -30,14 +37,97 @@ impl Builder {
         LoopParser::grammar(pair)
     }
 
+    // parse_with selects the parser, both frontends result in the same module
+    pub fn parse_with(
+        source: &str,
+        lno_overwrite: Option<LineNo>,
+        frontend: Frontend,
+    ) -> StdResult<Module> {
+        match frontend {
+            Frontend::Pest => Builder::parse(source, lno_overwrite)
+                .map_err(|err| vec![errors::Error::new_from_parse(err)]),
+            Frontend::Combine => frontend::parse(source, lno_overwrite, None),
+        }
+    }
+
+    // parse_checked reports every syntax error, the pest grammar stops at the first one,
+    // in that case the combine parser is used to find all of them. The pest grammar does not
+    // know macros, files using them are parsed with the combine parser, which imports the
+    // macros from the standard library and fs.
+    pub fn parse_checked(
+        source: &str,
+        frontend: Frontend,
+        fs: Option<&func::fs::Directory>,
+    ) -> StdResult<Module> {
+        match frontend {
+            Frontend::Pest => {
+                Builder::parse(source, None).or_else(|_| frontend::parse(source, None, fs))
+            }
+            Frontend::Combine => frontend::parse(source, None, fs),
+        }
+    }
+
+    // parse_recovering never fails, the module recovered from the errors is returned together
+    // with all of them. Broken statements are left out of the module.
+    #[cfg(feature = "cli")]
+    pub fn parse_recovering(
+        source: &str,
+        fs: Option<&func::fs::Directory>,
+    ) -> (Module, Vec<errors::Error>) {
+        frontend::parse_recovering(source, fs)
+    }
+
+    // fmt formats the source, the source is not changed if it contains errors. The macros
+    // imported from fs are needed to check the statements.
+    pub fn fmt(source: &str, fs: Option<&func::fs::Directory>) -> StdResult<String> {
+        frontend::fmt(source, fs)
+    }
+
     pub fn compile(
         module: &mut Module,
         flags: Option<CompileFlags>,
         fs: Option<func::fs::Directory>,
     ) -> StdResult<Expr> {
-        let mut context = CompileContext::new(module.clone(), flags.unwrap_or_default(), fs)?;
+        Builder::compile_with_lib(module, flags, fs, None)
+    }
 
-        Builder::ext_compile(module, &mut context)
+    // lib overrides the library root, which is used to resolve everything outside of fs::,
+    // if None is given the embedded standard library is used.
+    pub fn compile_with_lib(
+        module: &mut Module,
+        flags: Option<CompileFlags>,
+        fs: Option<func::fs::Directory>,
+        lib: Option<func::fs::Directory>,
+    ) -> StdResult<Expr> {
+        let mut context = CompileContext::new(module.clone(), flags.unwrap_or_default(), fs, lib)?;
+        let expr = Builder::ext_compile(module, &mut context);
+
+        let main = context.get_current_frame().module.clone();
+        Ok(func::utils::init_consts(&context, &main, expr?).flatten())
+    }
+
+    // compile_in_session reuses the parsed modules and inlined functions of previous compiles
+    // in the same session, used for fast recompiles in the editor.
+    pub fn compile_in_session(
+        module: &mut Module,
+        flags: Option<CompileFlags>,
+        fs: Option<func::fs::Directory>,
+        lib: Option<func::fs::Directory>,
+        session: &mut Session,
+    ) -> StdResult<Expr> {
+        let mut context = CompileContext::new_in_session(
+            module.clone(),
+            flags.unwrap_or_default(),
+            fs,
+            lib,
+            session,
+        )?;
+
+        let expr = Builder::ext_compile(module, &mut context);
+        session.store(&context);
+
+        let main = context.get_current_frame().module.clone();
+        Ok(func::utils::init_consts(&context, &main, expr?).flatten())
     }
 
     pub(crate) fn ext_compile(
@@ -50,6 +140,11 @@ impl Builder {
         Ok(expanded)
     }
 
+    // decompile reverts the macro expansions of a compiled program into the sugared statements
+    pub fn decompile(ast: &Expr) -> Hir {
+        decompile(ast)
+    }
+
     pub fn eval(ast: Expr) -> Runtime {
         Builder::ext_eval(ast, None)
     }
@@ -63,12 +158,21 @@ impl Builder {
         flags: Option<CompileFlags>,
         fs: Option<func::fs::Directory>,
     ) -> StdResult<Expr> {
-        Builder::compile(
-            &mut Builder::parse(source, None)
-                .map_err(|err| vec![errors::Error::new_from_parse(err)])?,
-            flags,
-            fs,
-        )
+        let frontend = if flags
+            .unwrap_or_default()
+            .contains(CompileFlags::CNF_COMBINE)
+        {
+            Frontend::Combine
+        } else {
+            Frontend::Pest
+        };
+
+        let mut module = match frontend {
+            Frontend::Pest => Builder::parse_with(source, None, frontend)?,
+            Frontend::Combine => frontend::parse(source, None, fs.as_ref())?,
+        };
+
+        Builder::compile(&mut module, flags, fs)
     }
 
     // parse_and_compile2 is an internal compile that also uses CompileContext
@@ -94,6 +198,16 @@ impl Builder {
         )?))
     }
 
+    // trace records every macro tried for the unknown terms of the source, including why they
+    // did not match. The trace is also returned if the resolution fails, to debug the failure.
+    #[cfg(feature = "cli")]
+    pub fn trace(
+        source: &str,
+        fs: Option<func::fs::Directory>,
+    ) -> (Trace, Result<(), Vec<errors::Error>>) {
+        frontend::trace(source, fs.as_ref())
+    }
+
     // ext_all is mostly used for tests only
     pub fn ext_all(
         source: &str,
@@ -1,20 +1,29 @@
 // This file should only be loaded if the cli feature is enabled.
 
 use clap::arg_enum;
-use std::path::PathBuf;
+use num_bigint::BigUint;
+use std::path::{Path, PathBuf};
 use structopt::StructOpt;
 
 use crate::ast::expr::Expr;
 use crate::ast::hir::func::fs::Directory;
 use crate::ast::hir::Hir;
 use crate::ast::module::Module;
-use crate::errors::Error;
+use crate::build::Builder;
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::errors::{Error, StdResult};
 use crate::eval::exec::Exec;
-use crate::eval::types::ExecutionResult;
+use crate::eval::types::{ExecutionResult, Variables};
+use crate::explain::Explanation;
+use crate::flags::CompileFlags;
+use crate::frontend::Frontend;
+use crate::lint::{Lints, Warning};
+use crate::runtime::Runtime;
+use combine_parser::disp::CompactRepresentation;
 use schemars::gen::SchemaSettings;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::fs::{create_dir, remove_dir_all, write};
+use std::fs::{create_dir, read_to_string, remove_dir_all, write};
 
 arg_enum! {
     #[derive(Debug)]
@@ -24,6 +33,14 @@ arg_enum! {
     }
 }
 
+arg_enum! {
+    #[derive(Debug, PartialEq)]
+    enum Format {
+        Text,
+        Json
+    }
+}
+
 #[derive(JsonSchema, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 struct RootSchema {
@@ -31,6 +48,8 @@ struct RootSchema {
     hir: Hir,
     exec: Exec,
     error: Error,
+    warning: Warning,
+    diagnostic: Diagnostic,
     directory: Directory,
     module: Module,
     execution_result: ExecutionResult,
@@ -47,13 +66,273 @@ enum Application {
         #[structopt(parse(from_os_str), short, long)]
         output: PathBuf,
     },
+    /// Run a file and print the final variables, internal variables (starting with _) are hidden
+    Run {
+        #[structopt(flatten)]
+        compile: Compile,
+
+        /// Initial value of a variable, like x=5
+        #[structopt(long = "set", parse(try_from_str = parse_set))]
+        set: Vec<(String, BigUint)>,
+    },
+    /// Step through a file in the terminal, showing the current line and the variables
+    Debug {
+        #[structopt(flatten)]
+        compile: Compile,
+
+        /// Initial value of a variable, like x=5
+        #[structopt(long = "set", parse(try_from_str = parse_set))]
+        set: Vec<(String, BigUint)>,
+    },
+    /// Report every error of a file
+    Check {
+        #[structopt(flatten)]
+        compile: Compile,
+    },
+    /// Report the lints of a file, like variables read before they are assigned
+    Lint {
+        #[structopt(flatten)]
+        compile: Compile,
+
+        /// Lints which are not reported, separated by commas, like unused_imports,loop_counter
+        #[structopt(long, default_value = "")]
+        allow: Lints,
+    },
+    /// Print the fully lowered program of a file
+    Expand {
+        #[structopt(flatten)]
+        compile: Compile,
+
+        /// Print the macro expansion tree instead
+        #[structopt(long)]
+        trace: bool,
+
+        /// Revert the expansions of the macros, like x := 0 or IF, in the printed program
+        #[structopt(long, conflicts_with = "trace")]
+        decompile: bool,
+    },
+    /// Format files in place
+    Fmt {
+        /// Only report the files which are not formatted, without changing them
+        #[structopt(long)]
+        check: bool,
+
+        #[structopt(parse(from_os_str), required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Start the language server, which communicates over stdio
+    Lsp,
+    /// Start an interactive session, fs:: imports are resolved from the current directory
+    Repl {
+        /// Compile flags separated by commas, like loop,while,cnf_const
+        #[structopt(long, default_value = "loop,while")]
+        flags: CompileFlags,
+    },
+    /// Explain an error code with an example, like E013
+    Explain { code: String },
+}
+
+#[derive(Debug, StructOpt)]
+struct Compile {
+    /// Compile flags separated by commas, like loop,while,cnf_const
+    #[structopt(long, default_value = "loop,while")]
+    flags: CompileFlags,
+
+    /// How errors are reported, json prints an array of diagnostics
+    #[structopt(long, default_value = "text", possible_values = &Format::variants(), case_insensitive = true)]
+    format: Format,
+
+    /// The main module, fs:: imports are resolved from the directory it is in
+    #[structopt(parse(from_os_str))]
+    file: PathBuf,
+}
+
+// reads a file, the error names the file which could not be read
+fn read(file: &Path) -> std::io::Result<String> {
+    read_to_string(file).map_err(|err| located(file, err))
+}
+
+fn located(path: &Path, err: std::io::Error) -> std::io::Error {
+    std::io::Error::new(err.kind(), format!("{}: {}", path.display(), err))
+}
+
+impl Compile {
+    fn source(&self) -> std::io::Result<String> {
+        read(&self.file)
+    }
+
+    fn frontend(&self) -> Frontend {
+        if self.flags.contains(CompileFlags::CNF_COMBINE) {
+            Frontend::Combine
+        } else {
+            Frontend::Pest
+        }
+    }
+
+    // the files next to the main file, which can be imported with fs::
+    fn fs(&self) -> std::io::Result<Directory> {
+        Directory::siblings(&self.file).map_err(|err| located(&self.file, err))
+    }
+
+    fn parse(&self, source: &str) -> std::io::Result<StdResult<Module>> {
+        Ok(Builder::parse_checked(
+            source,
+            self.frontend(),
+            Some(&self.fs()?),
+        ))
+    }
+
+    fn compile(&self, source: &str) -> std::io::Result<StdResult<Expr>> {
+        match self.parse(source)? {
+            Ok(mut module) => self.compile_module(&mut module),
+            Err(errors) => Ok(Err(errors)),
+        }
+    }
+
+    fn compile_module(&self, module: &mut Module) -> std::io::Result<StdResult<Expr>> {
+        Ok(Builder::compile(module, Some(self.flags), Some(self.fs()?)))
+    }
+}
+
+fn parse_set(value: &str) -> Result<(String, BigUint), String> {
+    let (ident, number) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected ident=value, got {}", value))?;
+    let number = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("expected a natural number, got {}", number))?;
+
+    Ok((ident.trim().to_string(), number))
+}
+
+// prints every error together with the lines of the source it refers to
+fn report(compile: &Compile, source: &str, errors: &[Error]) {
+    let file = compile.file.display().to_string();
+    let diagnostics: Vec<_> = errors
+        .iter()
+        .map(|error| Diagnostic::new(error, Some(&file), source))
+        .collect();
+
+    print(&compile.format, source, &diagnostics);
+}
+
+fn print(format: &Format, source: &str, diagnostics: &[Diagnostic]) {
+    match format {
+        Format::Text => {
+            for diagnostic in diagnostics {
+                eprintln!("{}", diagnostic.render(source));
+            }
+
+            let error = diagnostics
+                .iter()
+                .find(|diagnostic| diagnostic.severity == Severity::Error);
+            if let Some(diagnostic) = error {
+                eprintln!(
+                    "for more information about an error, try `lit explain {}`",
+                    diagnostic.code
+                );
+            }
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(diagnostics).unwrap()),
+    }
+}
+
+fn run(compile: Compile, set: Vec<(String, BigUint)>) -> std::io::Result<bool> {
+    let source = compile.source()?;
+    let expr = match compile.compile(&source)? {
+        Ok(expr) => expr,
+        Err(errors) => {
+            report(&compile, &source, &errors);
+            return Ok(false);
+        }
+    };
+
+    let mut runtime = Runtime::new(Exec::new(expr), Some(set.into_iter().collect()));
+    while runtime.is_running() {
+        runtime.step();
+    }
+
+    for (ident, value) in visible(runtime.context()) {
+        println!("{} = {}", ident, value);
+    }
+
+    Ok(true)
+}
+
+fn debug(compile: Compile, set: Vec<(String, BigUint)>) -> std::io::Result<bool> {
+    let source = compile.source()?;
+    let expr = match compile.compile(&source)? {
+        Ok(expr) => expr,
+        Err(errors) => {
+            report(&compile, &source, &errors);
+            return Ok(false);
+        }
+    };
+
+    let runtime = Runtime::new(Exec::new(expr), Some(set.into_iter().collect()));
+    crate::debug::debug(&compile.file.display().to_string(), &source, runtime)?;
+
+    Ok(true)
 }
 
-fn schema(args: Application) -> std::io::Result<()> {
-    let (mode, output) = match args {
-        Application::Schema { mode, output } => (mode, output),
+// the variables sorted by name, without the internal ones (starting with _)
+pub(crate) fn visible(variables: Variables) -> Vec<(String, BigUint)> {
+    let mut variables: Vec<_> = variables
+        .into_iter()
+        .filter(|(ident, _)| !ident.starts_with('_'))
+        .collect();
+    variables.sort();
+
+    variables
+}
+
+fn check(compile: Compile) -> std::io::Result<bool> {
+    let source = compile.source()?;
+
+    match compile.compile(&source)? {
+        Ok(_) => {
+            if compile.format == Format::Json {
+                println!("[]");
+            }
+
+            Ok(true)
+        }
+        Err(errors) => {
+            report(&compile, &source, &errors);
+            Ok(false)
+        }
+    }
+}
+
+// warnings do not fail the command, only errors of the compilation do
+fn lint(compile: Compile, allow: Lints) -> std::io::Result<bool> {
+    let source = compile.source()?;
+    let module = match compile.parse(&source)? {
+        Ok(module) => module,
+        Err(errors) => {
+            report(&compile, &source, &errors);
+            return Ok(false);
+        }
     };
 
+    // compiling checks the imports and strict mode, the lints are run on the parsed module
+    if let Err(errors) = compile.compile_module(&mut module.clone())? {
+        report(&compile, &source, &errors);
+        return Ok(false);
+    }
+
+    let file = compile.file.display().to_string();
+    let diagnostics: Vec<_> = crate::lint::lint(&module, Lints::all() - allow)
+        .iter()
+        .map(|warning| Diagnostic::from_warning(warning, Some(&file), &source))
+        .collect();
+
+    print(&compile.format, &source, &diagnostics);
+    Ok(true)
+}
+
+fn schema(mode: Mode, output: PathBuf) -> std::io::Result<()> {
     if output.is_file() {
         panic!("We need an output, not a directory")
     }
@@ -80,17 +359,190 @@ fn schema(args: Application) -> std::io::Result<()> {
     Ok(())
 }
 
+fn expand(compile: Compile, trace: bool, decompile: bool) -> std::io::Result<bool> {
+    let source = compile.source()?;
+
+    if trace {
+        let fs = Directory::siblings(&compile.file)?;
+        let (trace, result) = Builder::trace(&source, Some(fs));
+
+        print!("{}", trace.compact(None));
+        if let Err(errors) = result {
+            report(&compile, &source, &errors);
+            return Ok(false);
+        }
+
+        return Ok(true);
+    }
+
+    match compile.compile(&source)? {
+        Ok(expr) if decompile => {
+            println!("{}", Builder::decompile(&expr).display(4, None));
+            Ok(true)
+        }
+        Ok(expr) => {
+            println!("{}", expr.display(4, None));
+            Ok(true)
+        }
+        Err(errors) => {
+            report(&compile, &source, &errors);
+            Ok(false)
+        }
+    }
+}
+
+// returns whether every file is formatted (check) or could be formatted
+fn fmt(files: Vec<PathBuf>, check: bool) -> std::io::Result<bool> {
+    let mut success = true;
+
+    for file in files {
+        let source = read(&file)?;
+        let fs = Directory::siblings(&file).map_err(|err| located(&file, err))?;
+
+        let formatted = match Builder::fmt(&source, Some(&fs)) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                let name = file.display().to_string();
+                let diagnostics: Vec<_> = errors
+                    .iter()
+                    .map(|error| Diagnostic::new(error, Some(&name), &source))
+                    .collect();
+
+                print(&Format::Text, &source, &diagnostics);
+                success = false;
+                continue;
+            }
+        };
+
+        if formatted == source {
+            continue;
+        }
+
+        if check {
+            println!("{}", file.display());
+            success = false;
+        } else {
+            write(&file, formatted)?;
+        }
+    }
+
+    Ok(success)
+}
+
+// the explanation as plain text, with the examples indented
+fn render(explanation: &Explanation) -> String {
+    let indent = |source: &str| {
+        source
+            .lines()
+            .map(|line| format!("    {}", line).trim_end().to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let mut output = vec![
+        format!("{}: {}", explanation.id, explanation.title),
+        String::new(),
+        explanation.description.to_string(),
+    ];
+
+    let examples = [
+        ("Erroneous code example:", explanation.example),
+        ("Corrected:", explanation.fix),
+    ];
+    for (heading, source) in examples {
+        if let Some(source) = source {
+            output.extend([String::new(), heading.to_string(), String::new()]);
+            output.push(indent(source));
+        }
+    }
+
+    output.join("\n") + "\n"
+}
+
+fn explain(code: &str) -> bool {
+    match crate::explain::explain(code) {
+        Some(explanation) => {
+            print!("{}", render(explanation));
+            true
+        }
+        None => {
+            eprintln!("error: {} is not a known error code", code);
+            false
+        }
+    }
+}
+
+// exits with 1 if the command did not succeed
+fn exit<E: std::fmt::Display>(result: Result<bool, E>) {
+    match result {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            std::process::exit(1)
+        }
+    }
+}
+
 pub(crate) fn app() {
     let application = Application::from_args();
 
     match application {
-        Application::Schema { .. } => {
-            let result = schema(application);
+        Application::Schema { mode, output } => {
+            let result = schema(mode, output);
             assert!(
                 result.is_ok(),
                 "Error happened in Schema generation: {:?}",
                 result.err().unwrap()
             )
         }
+        Application::Run { compile, set } => exit(run(compile, set)),
+        Application::Debug { compile, set } => exit(debug(compile, set)),
+        Application::Check { compile } => exit(check(compile)),
+        Application::Lint { compile, allow } => exit(lint(compile, allow)),
+        Application::Expand {
+            compile,
+            trace,
+            decompile,
+        } => exit(expand(compile, trace, decompile)),
+        Application::Fmt { check, files } => exit(fmt(files, check)),
+        Application::Lsp => exit(crate::lsp::serve().map(|_| true)),
+        Application::Repl { flags } => exit(crate::repl::start(flags).map(|_| true)),
+        Application::Explain { code } => exit::<std::io::Error>(Ok(explain(&code))),
     };
 }
+
+//region Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::explain;
+
+    #[test]
+    fn test_render_explanation() {
+        let rendered = render(explain("E013").unwrap());
+        let lines: Vec<_> = rendered.lines().collect();
+
+        assert_eq!(lines[0], "E013: assignment to a constant");
+        assert!(
+            rendered.contains("Erroneous code example:\n\n    CONST LIMIT = 3\n\n    LIMIT := ")
+        );
+        assert!(rendered.contains("Corrected:\n\n    CONST LIMIT = 3\n"));
+
+        // codes without examples only have a description
+        let rendered = render(explain("E016").unwrap());
+        assert!(!rendered.contains("Erroneous"));
+    }
+
+    #[test]
+    fn test_read_missing() {
+        let file = std::env::temp_dir().join("lit-missing.lp");
+        let err = read(&file).unwrap_err();
+
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        assert!(err
+            .to_string()
+            .starts_with(&format!("{}: ", file.display())));
+    }
+}
+//endregion
@@ -0,0 +1,395 @@
+// This file should only be loaded if the cli feature is enabled.
+//
+// Terminal step debugger. The source is shown next to the variables, with the line of the last
+// executed statement highlighted. Execution is driven by Runtime::step, a breakpoint pauses the
+// execution after the first statement on its line has been executed.
+
+use std::collections::BTreeSet;
+use std::io::{self, IsTerminal, Write};
+use std::time::Duration;
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Print, PrintStyledContent, Stylize};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+
+use crate::cli::visible;
+use crate::eval::types::{ChangeLog, ExecutionResult};
+use crate::runtime::Runtime;
+
+// the number of steps executed between checking for input while continuing
+const BATCH: usize = 10_000;
+
+const KEYS: &str = " s step  c continue  p pause  b breakpoint  ↑↓ move  r restart  q quit";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum State {
+    Paused,
+    Running,
+    Finished,
+}
+
+pub(crate) struct Debugger {
+    lines: Vec<String>,
+    runtime: Runtime,
+    state: State,
+    steps: usize,
+
+    // the line of the last executed statement, 1-based
+    line: Option<usize>,
+    // the variables changed by the last step
+    changed: Vec<String>,
+
+    breakpoints: BTreeSet<usize>,
+    // the line breakpoints are toggled on, follows the execution, 1-based
+    cursor: usize,
+    // the first line shown, 0-based
+    scroll: usize,
+}
+
+impl Debugger {
+    pub(crate) fn new(source: &str, runtime: Runtime) -> Self {
+        Self {
+            lines: source
+                .lines()
+                .map(|line| line.replace('\t', "    "))
+                .collect(),
+            runtime,
+            state: State::Paused,
+            steps: 0,
+            line: None,
+            changed: vec![],
+            breakpoints: BTreeSet::new(),
+            cursor: 1,
+            scroll: 0,
+        }
+    }
+
+    fn step(&mut self) {
+        if self.state == State::Finished {
+            return;
+        }
+
+        match self.runtime.step() {
+            Some(ExecutionResult(lno, changes)) => {
+                self.steps += 1;
+                // statements of the standard library have no line in this file
+                self.line = Some(lno.row.0).filter(|line| (1..=self.lines.len()).contains(line));
+                self.changed = changes
+                    .into_iter()
+                    .filter_map(|change| match change {
+                        ChangeLog::Ident(ident) => Some(ident),
+                        ChangeLog::Internal(_) => None,
+                    })
+                    .collect();
+            }
+            None => {
+                self.state = State::Finished;
+                self.changed.clear();
+            }
+        }
+
+        if let Some(line) = self.line {
+            self.cursor = line;
+        }
+    }
+
+    // executes up to limit steps, stops early at a breakpoint or at the end of the program.
+    // A breakpoint only pauses when the execution enters its line, the sugar of a single line
+    // (like x := y * 5) is lowered into many statements.
+    fn advance(&mut self, limit: usize) {
+        for _ in 0..limit {
+            let previous = self.line;
+            self.step();
+
+            if self.state == State::Finished {
+                return;
+            }
+
+            let entered = self.line != previous;
+            if entered && matches!(self.line, Some(line) if self.breakpoints.contains(&line)) {
+                self.state = State::Paused;
+                return;
+            }
+        }
+    }
+
+    fn restart(&mut self) {
+        self.runtime.reset();
+
+        self.state = State::Paused;
+        self.steps = 0;
+        self.line = None;
+        self.changed.clear();
+    }
+
+    fn toggle(&mut self) {
+        if !self.breakpoints.remove(&self.cursor) {
+            self.breakpoints.insert(self.cursor);
+        }
+    }
+
+    // returns false if the debugger should be closed
+    fn key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('s') | KeyCode::Enter | KeyCode::Right
+                if self.state != State::Finished =>
+            {
+                self.state = State::Paused;
+                self.step();
+            }
+            KeyCode::Char('c') if self.state == State::Paused => self.state = State::Running,
+            KeyCode::Char('p') if self.state == State::Running => self.state = State::Paused,
+            KeyCode::Char('b') => self.toggle(),
+            KeyCode::Char('r') => self.restart(),
+            KeyCode::Up | KeyCode::Char('k') => self.cursor = self.cursor.saturating_sub(1).max(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.cursor = (self.cursor + 1).min(self.lines.len().max(1))
+            }
+            _ => {}
+        }
+
+        true
+    }
+
+    fn draw(&mut self, out: &mut impl Write, title: &str) -> io::Result<()> {
+        let (width, height) = terminal::size()?;
+        let (width, height) = (width as usize, height as usize);
+        let rows = height.saturating_sub(2);
+
+        // the variables take a third of the screen
+        let panel = width / 3;
+        let source = width.saturating_sub(panel + 1);
+
+        if self.cursor <= self.scroll {
+            self.scroll = self.cursor.saturating_sub(1);
+        } else if self.cursor > self.scroll + rows {
+            self.scroll = self.cursor - rows;
+        }
+
+        queue!(out, Clear(ClearType::All), MoveTo(0, 0))?;
+
+        let state = match self.state {
+            State::Paused => "paused",
+            State::Running => "running",
+            State::Finished => "finished",
+        };
+        let header = format!(" {}  step {}  {}", title, self.steps, state);
+        queue!(out, PrintStyledContent(fit(&header, width).reverse()))?;
+
+        let gutter = self.lines.len().to_string().len();
+        let variables = visible(self.runtime.context());
+
+        for row in 0..rows {
+            queue!(out, MoveTo(0, row as u16 + 1))?;
+
+            let number = self.scroll + row + 1;
+            if let Some(text) = self.lines.get(number - 1) {
+                let marker = match (self.breakpoints.contains(&number), number == self.cursor) {
+                    (true, _) => '●',
+                    (false, true) => '>',
+                    (false, false) => ' ',
+                };
+                let text = fit(
+                    &format!("{}{:>gutter$} {}", marker, number, text, gutter = gutter),
+                    source,
+                );
+
+                if self.line == Some(number) {
+                    queue!(out, PrintStyledContent(text.reverse()))?;
+                } else {
+                    queue!(out, Print(text))?;
+                }
+            }
+
+            queue!(out, MoveTo(source as u16, row as u16 + 1), Print('│'))?;
+
+            if let Some((ident, value)) = variables.get(row) {
+                let text = fit(&format!(" {} = {}", ident, value), panel);
+
+                if self.changed.contains(ident) {
+                    queue!(out, PrintStyledContent(text.bold().yellow()))?;
+                } else {
+                    queue!(out, Print(text))?;
+                }
+            }
+        }
+
+        queue!(
+            out,
+            MoveTo(0, height.saturating_sub(1) as u16),
+            PrintStyledContent(fit(KEYS, width).reverse())
+        )?;
+
+        out.flush()
+    }
+
+    fn run(&mut self, out: &mut impl Write, title: &str) -> io::Result<()> {
+        loop {
+            if self.state == State::Running {
+                self.advance(BATCH);
+            }
+
+            self.draw(out, title)?;
+
+            // while running the input is only checked in between batches, otherwise the screen
+            // is only redrawn after an event, like a key or a resize of the terminal
+            if self.state == State::Running && !event::poll(Duration::ZERO)? {
+                continue;
+            }
+
+            if let Event::Key(key) = event::read()? {
+                let interrupt =
+                    key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c');
+
+                if key.kind == KeyEventKind::Press && (interrupt || !self.key(key.code)) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+// pads or truncates the text to exactly width characters
+fn fit(text: &str, width: usize) -> String {
+    format!("{:width$.width$}", text, width = width)
+}
+
+pub(crate) fn debug(title: &str, source: &str, runtime: Runtime) -> io::Result<()> {
+    let mut out = io::stdout();
+    if !out.is_terminal() {
+        return Err(io::Error::other(
+            "the debugger needs to be run in a terminal",
+        ));
+    }
+
+    let mut debugger = Debugger::new(source, runtime);
+
+    terminal::enable_raw_mode()?;
+    execute!(out, EnterAlternateScreen, Hide)?;
+
+    let result = debugger.run(&mut out, title);
+
+    execute!(out, Show, LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+
+    result
+}
+
+//region Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::Builder;
+    use indoc::indoc;
+    use num_bigint::BigUint;
+
+    const SOURCE: &str = indoc! {"
+        x := y + 3
+        LOOP x DO
+            y := y + 2
+        END
+        z := y + 1
+    "};
+
+    fn debugger() -> Debugger {
+        let runtime = Builder::ext_all(SOURCE, None, None, None).unwrap();
+
+        Debugger::new(SOURCE, runtime)
+    }
+
+    fn value(debugger: &Debugger, ident: &str) -> Option<BigUint> {
+        debugger.runtime.context().get(ident).cloned()
+    }
+
+    #[test]
+    fn test_debug_step() {
+        let mut debugger = debugger();
+
+        debugger.key(KeyCode::Char('s'));
+        assert_eq!(debugger.line, Some(1));
+        assert_eq!(debugger.changed, vec!["x".to_string()]);
+        assert_eq!(value(&debugger, "x"), Some(BigUint::from(3u8)));
+
+        debugger.advance(usize::MAX);
+        assert_eq!(debugger.state, State::Finished);
+        assert_eq!(value(&debugger, "z"), Some(BigUint::from(7u8)));
+
+        // stepping a finished program does nothing
+        let steps = debugger.steps;
+        debugger.key(KeyCode::Enter);
+        assert_eq!(debugger.steps, steps);
+
+        assert!(!debugger.key(KeyCode::Char('q')));
+    }
+
+    #[test]
+    fn test_debug_breakpoints() {
+        let mut debugger = debugger();
+
+        debugger.cursor = 3;
+        debugger.key(KeyCode::Char('b'));
+        debugger.key(KeyCode::Down);
+        debugger.key(KeyCode::Down);
+        debugger.key(KeyCode::Down);
+        assert_eq!(debugger.cursor, 5);
+        debugger.key(KeyCode::Char('b'));
+        assert_eq!(debugger.breakpoints, vec![3, 5].into_iter().collect());
+
+        debugger.key(KeyCode::Char('c'));
+        assert_eq!(debugger.state, State::Running);
+
+        // the body of the loop is entered once, the iterations stay on the same line
+        debugger.advance(BATCH);
+        assert_eq!(debugger.state, State::Paused);
+        assert_eq!(debugger.line, Some(3));
+        assert_eq!(value(&debugger, "y"), Some(BigUint::from(2u8)));
+
+        debugger.advance(BATCH);
+        assert_eq!(debugger.line, Some(5));
+        assert_eq!(value(&debugger, "y"), Some(BigUint::from(6u8)));
+        assert_eq!(debugger.changed, vec!["z".to_string()]);
+
+        debugger.key(KeyCode::Char('r'));
+        assert_eq!(debugger.state, State::Paused);
+        assert_eq!(debugger.steps, 0);
+        assert_eq!(value(&debugger, "x"), None);
+    }
+
+    #[test]
+    fn test_debug_breakpoint_multi_step() {
+        let source = indoc! {"
+            y := 2
+            x := y * 5
+            z := x + 1
+        "};
+        let runtime = Builder::ext_all(source, None, None, None).unwrap();
+        let mut debugger = Debugger::new(source, runtime);
+
+        debugger.breakpoints.insert(2);
+        debugger.breakpoints.insert(3);
+        debugger.key(KeyCode::Char('c'));
+
+        // the multiplication is lowered into many steps, it only pauses once
+        debugger.advance(BATCH);
+        assert_eq!(debugger.state, State::Paused);
+        assert_eq!(debugger.line, Some(2));
+        assert_ne!(value(&debugger, "x"), Some(BigUint::from(10u8)));
+
+        debugger.key(KeyCode::Char('c'));
+        debugger.advance(BATCH);
+        assert_eq!(debugger.state, State::Paused);
+        assert_eq!(debugger.line, Some(3));
+        assert_eq!(value(&debugger, "x"), Some(BigUint::from(10u8)));
+    }
+
+    #[test]
+    fn test_debug_fit() {
+        assert_eq!(fit("abc", 5), "abc  ");
+        assert_eq!(fit("a●cde", 3), "a●c");
+        assert_eq!(fit("abc", 0), "");
+    }
+}
+//endregion
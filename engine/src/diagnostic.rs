@@ -0,0 +1,496 @@
+// Reports of errors for humans. A diagnostic resolves the rows of an error, and the columns of
+// syntax errors, against the source of the file. It is rendered as a snippet of the source for the
+// terminal or serialized as JSON for the web UI.
+
+use crate::errors::{Error, ErrorCode, ErrorVariant, PestErrorInfo};
+use crate::lint::Warning;
+use crate::types::LineNo;
+use either::Either;
+use itertools::Itertools;
+#[cfg(feature = "cli")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::iter::once;
+
+// rows and columns are 1-based, columns count characters
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "cli", derive(JsonSchema))]
+pub struct Position {
+    pub row: usize,
+    pub col: usize,
+}
+
+// the end is exclusive
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "cli", derive(JsonSchema))]
+pub struct Label {
+    pub start: Position,
+    pub end: Position,
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "cli", derive(JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "cli", derive(JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    pub severity: Severity,
+    // the stable identifier of an error, like E001, or the name of a lint
+    pub code: String,
+    pub file: Option<String>,
+    pub message: String,
+    // None if the error has no location or refers to another module
+    pub label: Option<Label>,
+    // the tokens a syntax error expected
+    pub expected: Vec<String>,
+    pub notes: Vec<String>,
+}
+
+fn lines(source: &str) -> Vec<&str> {
+    source
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect()
+}
+
+// the columns of the first character and after the last character, without whitespace
+fn columns(line: &str) -> (usize, usize) {
+    let start = line.chars().take_while(|char| char.is_whitespace()).count() + 1;
+    let end = line.trim_end().chars().count() + 1;
+
+    (start, end.max(start))
+}
+
+fn token(rule: &str) -> String {
+    match rule {
+        "EOI" => "end of file".to_string(),
+        _ => rule.to_string(),
+    }
+}
+
+// a, b or c
+fn list(items: &[String]) -> String {
+    match items.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+        None => String::new(),
+    }
+}
+
+impl Diagnostic {
+    fn located(
+        severity: Severity,
+        code: &str,
+        message: String,
+        lno: LineNo,
+        file: Option<&str>,
+        lines: &[&str],
+    ) -> Self {
+        let mut diagnostic = Diagnostic {
+            severity,
+            code: code.to_string(),
+            file: file.map(str::to_string),
+            message,
+            label: None,
+            expected: vec![],
+            notes: vec![],
+        };
+
+        // rows outside of the source refer to another module, locations without columns
+        // span their whole rows
+        let (start, end) = lno.row;
+        if (1..=lines.len()).contains(&start) {
+            let end = end.clamp(start, lines.len());
+            let (from, to) = match lno.col {
+                (0, _) | (_, 0) => (columns(lines[start - 1]).0, columns(lines[end - 1]).1),
+                col => col,
+            };
+
+            diagnostic.label = Some(Label {
+                start: Position {
+                    row: start,
+                    col: from,
+                },
+                end: Position { row: end, col: to },
+                text: None,
+            });
+        }
+
+        diagnostic
+    }
+
+    pub fn new(error: &Error, file: Option<&str>, source: &str) -> Self {
+        let lines = lines(source);
+        let mut diagnostic = Diagnostic::located(
+            Severity::Error,
+            error.id(),
+            error.message(),
+            error.lno,
+            file,
+            &lines,
+        );
+
+        match &error.variant {
+            ErrorVariant::Parse(PestErrorInfo::Error {
+                variant, line_col, ..
+            }) => diagnostic.syntax(variant, line_col, &lines),
+            ErrorVariant::ErrorCode(ErrorCode::CircularImport {
+                module, history, ..
+            }) => {
+                diagnostic.message = format!("circular import of {}", module);
+                diagnostic.notes.push(format!(
+                    "import chain: {}",
+                    history.iter().rev().chain(once(module)).join(" -> ")
+                ));
+            }
+            ErrorVariant::ErrorCode(ErrorCode::FunctionRecursionDetected {
+                module,
+                func,
+                stack,
+                ..
+            }) => {
+                diagnostic.message = format!("recursion detected in {}::{}", module, func);
+                diagnostic
+                    .notes
+                    .push(format!("call stack: {}", stack.join(" -> ")));
+                diagnostic
+                    .notes
+                    .push("functions are inlined, they cannot call themselves".to_string());
+            }
+            ErrorVariant::ErrorCode(ErrorCode::UnexpectedExprType { expected, got, .. }) => {
+                diagnostic
+                    .notes
+                    .push(format!("expected {}, got {}", expected, got));
+            }
+            _ => {}
+        }
+
+        diagnostic
+    }
+
+    pub fn from_warning(warning: &Warning, file: Option<&str>, source: &str) -> Self {
+        Diagnostic::located(
+            Severity::Warning,
+            &warning.lint,
+            warning.message.clone(),
+            warning.lno,
+            file,
+            &lines(source),
+        )
+    }
+
+    fn syntax(&mut self, variant: &PestErrorInfo, line_col: &PestErrorInfo, lines: &[&str]) {
+        let text = match variant {
+            PestErrorInfo::ErrorVariantParsingError {
+                positives,
+                negatives,
+            } => {
+                self.expected = positives.iter().map(|rule| token(rule)).collect();
+                let unexpected: Vec<_> = negatives.iter().map(|rule| token(rule)).collect();
+
+                match (self.expected.is_empty(), unexpected.is_empty()) {
+                    (false, _) => Some(format!("expected {}", list(&self.expected))),
+                    (true, false) => Some(format!("unexpected {}", list(&unexpected))),
+                    (true, true) => None,
+                }
+            }
+            _ => None,
+        };
+
+        if text.is_some() {
+            self.message = "syntax error".to_string();
+        }
+
+        let (start, end) = match line_col {
+            PestErrorInfo::LineColLocation(Either::Left((row, col))) => {
+                ((*row, *col), (*row, *col + 1))
+            }
+            PestErrorInfo::LineColLocation(Either::Right((start, end))) => (*start, *end),
+            _ => return,
+        };
+
+        if (1..=lines.len()).contains(&start.0) && (1..=lines.len()).contains(&end.0) {
+            self.label = Some(Label {
+                start: Position {
+                    row: start.0,
+                    col: start.1,
+                },
+                end: Position {
+                    row: end.0,
+                    col: end.1,
+                },
+                text,
+            });
+        } else if let Some(label) = &mut self.label {
+            label.text = text;
+        }
+    }
+
+    // renders the diagnostic with the lines of the source it refers to, like
+    //
+    // error[E001]: syntax error
+    //  --> main.lp:2:6
+    //   |
+    // 2 | x :=
+    //   |      ^ expected IDENT or VALUE
+    //   |
+    pub fn render(&self, source: &str) -> String {
+        let lines = lines(source);
+        let file = self.file.as_deref().unwrap_or("<source>");
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        let mut output = vec![format!("{}[{}]: {}", severity, self.code, self.message)];
+
+        let label = match &self.label {
+            Some(label) => label,
+            None => {
+                output.push(format!(" --> {}", file));
+                output.extend(self.notes.iter().map(|note| format!(" = note: {}", note)));
+
+                return output.join("\n") + "\n";
+            }
+        };
+
+        let width = label.end.row.to_string().len();
+        let gutter = " ".repeat(width);
+        output.push(format!(
+            "{}--> {}:{}:{}",
+            gutter, file, label.start.row, label.start.col
+        ));
+        output.push(format!("{} |", gutter));
+
+        for row in label.start.row..=label.end.row {
+            // long labels, like the body of a function, only show their first and last lines
+            if row > label.start.row + 1 && row + 1 < label.end.row {
+                if row == label.start.row + 2 {
+                    output.push("...".to_string());
+                }
+
+                continue;
+            }
+
+            let line = lines.get(row - 1).copied().unwrap_or_default();
+            let (first, last) = columns(line);
+
+            let from = if row == label.start.row {
+                label.start.col
+            } else {
+                first
+            };
+            let to = if row == label.end.row {
+                label.end.col
+            } else {
+                last
+            };
+
+            output.push(format!("{:>width$} | {}", row, line, width = width));
+
+            let mut underline = format!(
+                "{} | {}{}",
+                gutter,
+                " ".repeat(from.saturating_sub(1)),
+                "^".repeat(to.saturating_sub(from).max(1))
+            );
+            if let (true, Some(text)) = (row == label.end.row, &label.text) {
+                underline = format!("{} {}", underline, text);
+            }
+            output.push(underline);
+        }
+
+        output.push(format!("{} |", gutter));
+        output.extend(
+            self.notes
+                .iter()
+                .map(|note| format!("{} = note: {}", gutter, note)),
+        );
+
+        output.join("\n") + "\n"
+    }
+}
+
+//region Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::Builder;
+    use crate::types::LineNo;
+    use indoc::indoc;
+
+    fn diagnostics(source: &str) -> Vec<Diagnostic> {
+        Builder::ext_all(source, None, None, None)
+            .err()
+            .expect("expected an error")
+            .iter()
+            .map(|error| Diagnostic::new(error, Some("main.lp"), source))
+            .collect()
+    }
+
+    #[test]
+    fn test_diagnostic_syntax() {
+        let source = "x := 1\ny := \n";
+        let diagnostic = diagnostics(source).remove(0);
+
+        assert_eq!(diagnostic.message, "syntax error");
+        assert!(diagnostic.expected.contains(&"IDENT".to_string()));
+
+        let label = diagnostic.label.clone().unwrap();
+        assert_eq!(label.start, Position { row: 2, col: 6 });
+        assert!(label.text.unwrap().starts_with("expected "));
+
+        let rendered = diagnostic.render(source);
+        let lines: Vec<_> = rendered.lines().collect();
+        assert_eq!(lines[0], "error[E001]: syntax error");
+        assert_eq!(lines[1], " --> main.lp:2:6");
+        assert_eq!(lines[3], "2 | y := ");
+        assert!(lines[4].starts_with("  |      ^ expected "));
+    }
+
+    #[test]
+    fn test_diagnostic_rows() {
+        let source = indoc! {"
+            x := 1
+            y := min(x, 2)
+        "};
+        let diagnostic = diagnostics(source).remove(0);
+
+        let label = diagnostic.label.clone().unwrap();
+        assert_eq!(label.start, Position { row: 2, col: 1 });
+        assert_eq!(label.end, Position { row: 2, col: 15 });
+        assert_eq!(
+            diagnostic.render(source),
+            indoc! {"
+                error[E004]: could not find min in module fs::main
+                 --> main.lp:2:1
+                  |
+                2 | y := min(x, 2)
+                  | ^^^^^^^^^^^^^^
+                  |
+            "}
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_recursion() {
+        let source = indoc! {"
+            FN a(b) -> c DECL
+                c := a(b)
+            END
+
+            x := a(2)
+        "};
+        let diagnostic = diagnostics(source).remove(0);
+
+        assert_eq!(diagnostic.message, "recursion detected in fs::main::a");
+        assert_eq!(
+            diagnostic.notes[0],
+            "call stack: fs::main::a -> fs::main::a"
+        );
+        assert_eq!(
+            diagnostic.render(source),
+            indoc! {"
+                error[E010]: recursion detected in fs::main::a
+                 --> main.lp:1:1
+                  |
+                1 | FN a(b) -> c DECL
+                  | ^^^^^^^^^^^^^^^^^
+                2 |     c := a(b)
+                  |     ^^^^^^^^^
+                3 | END
+                  | ^^^
+                  |
+                  = note: call stack: fs::main::a -> fs::main::a
+                  = note: functions are inlined, they cannot call themselves
+            "}
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_long_label() {
+        let source = "a\nb\nc\nd\ne\nf\n";
+        let error = Error::new_from_code(
+            Some(LineNo::rows(1, 6)),
+            ErrorCode::SyntaxError {
+                excerpt: "long".to_string(),
+            },
+        );
+        let rendered = Diagnostic::new(&error, None, source).render(source);
+
+        let rows: Vec<_> = rendered
+            .lines()
+            .filter(|line| !line.starts_with(" ") && !line.starts_with("error"))
+            .collect();
+        assert_eq!(rows, vec!["1 | a", "2 | b", "...", "5 | e", "6 | f"]);
+    }
+
+    #[test]
+    fn test_diagnostic_without_location() {
+        let error = Error::new_from_code(
+            None,
+            ErrorCode::CircularImport {
+                message: String::new(),
+                module: "fs::a".to_string(),
+                history: vec!["fs::b".to_string(), "fs::a".to_string()],
+                origin: "fs::b".to_string(),
+            },
+        );
+        let diagnostic = Diagnostic::new(&error, None, "");
+
+        assert_eq!(diagnostic.label, None);
+        assert_eq!(
+            diagnostic.render(""),
+            indoc! {"
+                error[E006]: circular import of fs::a
+                 --> <source>
+                 = note: import chain: fs::a -> fs::b -> fs::a
+            "}
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_warning() {
+        let source = "x := 1\ny := z + 1\n";
+        let warning = Warning {
+            lno: LineNo::new((2, 1), (2, 11)),
+            lint: "uninitialized".to_string(),
+            message: "z is read before it is assigned".to_string(),
+        };
+        let diagnostic = Diagnostic::from_warning(&warning, Some("main.lp"), source);
+
+        assert_eq!(diagnostic.severity, Severity::Warning);
+        assert_eq!(
+            diagnostic.render(source),
+            indoc! {"
+                warning[uninitialized]: z is read before it is assigned
+                 --> main.lp:2:1
+                  |
+                2 | y := z + 1
+                  | ^^^^^^^^^^
+                  |
+            "}
+        );
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_diagnostic_json() {
+        let diagnostic = diagnostics("x := 1\ny := \n").remove(0);
+        let json = serde_json::to_value(&diagnostic).unwrap();
+
+        assert_eq!(json["severity"], "error");
+        assert_eq!(json["code"], "E001");
+        assert_eq!(json["file"], "main.lp");
+        assert_eq!(json["label"]["start"]["row"], 2);
+        assert_eq!(json["label"]["start"]["col"], 6);
+        assert!(json["expected"].as_array().unwrap().len() > 1);
+    }
+}
+//endregion
@@ -6,6 +6,7 @@ use pest::error::{InputLocation, LineColLocation};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::io;
+use variants::err::{ErrorKind, ErrorKindMacro, ErrorKindParse, ErrorKindUnsupported};
 
 pub type StdResult<R> = std::result::Result<R, Vec<Error>>;
 
@@ -27,6 +28,10 @@ pub enum ErrorCode {
         module: String,
         func: String,
     },
+    FunctionNotExported {
+        module: String,
+        func: String,
+    },
     CircularImport {
         message: String,
         module: String,
@@ -38,10 +43,6 @@ pub enum ErrorCode {
         func: String,
         count: Option<usize>,
     },
-    MultipleModuleCandidates {
-        module: String,
-        count: usize,
-    },
     UnexpectedExprType {
         message: String,
         expected: String,
@@ -62,6 +63,72 @@ pub enum ErrorCode {
     StrictModeViolation {
         violation: StrictModeViolation,
     },
+    AssignmentToConstant {
+        module: String,
+        ident: String,
+    },
+    // errors of the combine parser, the excerpt is the source the error is located at
+    SyntaxError {
+        excerpt: String,
+    },
+    UnexpectedToken {
+        expected: String,
+        found: String,
+    },
+    UnexpectedEnd,
+    UnclosedBlock {
+        excerpt: String,
+    },
+    MisplacedStatement {
+        excerpt: String,
+    },
+    UnsupportedOperator,
+    UnsupportedComparison,
+    LoopNotEnabled,
+    WhileNotEnabled,
+    // errors of the macro resolution, candidates and history are qualified names
+    MacroNoMatch,
+    MacroAmbiguous {
+        candidates: Vec<String>,
+    },
+    MacroRecursionLimit {
+        ident: String,
+    },
+    MacroModuleNotFound {
+        module: String,
+    },
+    MacroNotFound {
+        module: String,
+        ident: String,
+    },
+    MacroNameCollision {
+        module: String,
+        ident: String,
+    },
+    MacroCircularImport {
+        module: String,
+        history: Vec<String>,
+    },
+    MacroMisplacedPlaceholder {
+        ident: String,
+        placeholder: String,
+    },
+    MacroUndefinedPlaceholder {
+        ident: String,
+        placeholder: String,
+    },
+    MacroUngroupedPlaceholder {
+        ident: String,
+        placeholder: String,
+    },
+    MacroInvalidGroup {
+        ident: String,
+    },
+    MacroInconsistentPlaceholder {
+        ident: String,
+        placeholder: String,
+        expected: String,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
@@ -81,7 +148,6 @@ pub struct Error {
 #[derive(new, Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "cli", derive(JsonSchema))]
 pub enum ErrorVariant {
-    Message(String),
     ErrorCode(ErrorCode),
     Parse(PestErrorInfo),
     Rust(RustError),
@@ -90,8 +156,8 @@ pub enum ErrorVariant {
 impl Error {
     pub fn new_from_parse(error: pest::error::Error<Rule>) -> Self {
         let lno = match error.line_col {
-            LineColLocation::Pos(pos) => (pos.0, pos.0),
-            LineColLocation::Span(start, end) => (start.0, end.0),
+            LineColLocation::Pos(pos) => LineNo::new(pos, (pos.0, pos.1 + 1)),
+            LineColLocation::Span(start, end) => LineNo::new(start, end),
         };
 
         Error {
@@ -101,7 +167,7 @@ impl Error {
     }
 
     pub fn new_from_code(lno: Option<LineNo>, code: ErrorCode) -> Self {
-        let lno: LineNo = lno.unwrap_or((0, 0));
+        let lno: LineNo = lno.unwrap_or_default();
 
         Error {
             lno,
@@ -111,17 +177,282 @@ impl Error {
 
     pub fn new_from_io(error: io::Error) -> Self {
         Error {
-            lno: (0, 0),
+            lno: LineNo::default(),
             variant: ErrorVariant::Rust(RustError::Io(format!("{:?}", error.kind()))),
         }
     }
+}
+
+impl StrictModeViolation {
+    fn describe(&self) -> &'static str {
+        match self {
+            StrictModeViolation::LoopToWhileForbidden => "LOOP cannot be lowered to WHILE",
+            StrictModeViolation::MacroForbidden => "macros are forbidden",
+            StrictModeViolation::FuncForbidden => "functions are forbidden",
+        }
+    }
+}
 
-    pub fn new_from_msg(lno: Option<LineNo>, msg: &str) -> Self {
-        let lno = lno.unwrap_or((0, 0));
+impl ErrorCode {
+    // the stable identifier of the error, explained by `lit explain`.
+    // identifiers are never reused or renumbered, new codes are appended.
+    // E002 (unsupported macro) is retired, the combine frontend expands macros.
+    // E008 (multiple module candidates) is retired, modules are looked up in a Directory.
+    pub fn id(&self) -> &'static str {
+        match self {
+            ErrorCode::SyntaxError { .. } | ErrorCode::UnexpectedToken { .. } => "E001",
+            ErrorCode::CouldNotFindModule { .. } => "E003",
+            ErrorCode::CouldNotFindFunction { .. } => "E004",
+            ErrorCode::FunctionNotExported { .. } => "E005",
+            ErrorCode::CircularImport { .. } => "E006",
+            ErrorCode::FunctionNameCollision { .. } => "E007",
+            ErrorCode::UnexpectedExprType { .. } => "E009",
+            ErrorCode::FunctionRecursionDetected { .. } => "E010",
+            ErrorCode::FunctionUnexpectedNumberOfArguments { .. } => "E011",
+            ErrorCode::StrictModeViolation { .. } => "E012",
+            ErrorCode::AssignmentToConstant { .. } => "E013",
+            ErrorCode::LoopNotEnabled => "E014",
+            ErrorCode::WhileNotEnabled => "E015",
+            ErrorCode::MacroNoMatch => "E017",
+            ErrorCode::MacroAmbiguous { .. } => "E018",
+            ErrorCode::MacroRecursionLimit { .. } => "E019",
+            ErrorCode::MacroModuleNotFound { .. } => "E020",
+            ErrorCode::MacroNotFound { .. } => "E021",
+            ErrorCode::MacroNameCollision { .. } => "E022",
+            ErrorCode::MacroCircularImport { .. } => "E023",
+            ErrorCode::MacroMisplacedPlaceholder { .. } => "E024",
+            ErrorCode::MacroUndefinedPlaceholder { .. } => "E025",
+            ErrorCode::MacroUngroupedPlaceholder { .. } => "E026",
+            ErrorCode::MacroInvalidGroup { .. } => "E027",
+            ErrorCode::MacroInconsistentPlaceholder { .. } => "E028",
+            ErrorCode::UnexpectedEnd => "E029",
+            ErrorCode::UnclosedBlock { .. } => "E030",
+            ErrorCode::MisplacedStatement { .. } => "E031",
+            ErrorCode::UnsupportedOperator => "E032",
+            ErrorCode::UnsupportedComparison => "E033",
+        }
+    }
 
-        Error {
-            lno,
-            variant: ErrorVariant::Message(msg.to_string()),
+    pub fn message(&self) -> String {
+        match self {
+            ErrorCode::CouldNotFindModule { module } => format!("could not find module {}", module),
+            ErrorCode::CouldNotFindFunction { module, func } => {
+                format!("could not find {} in module {}", func, module)
+            }
+            ErrorCode::FunctionNotExported { module, func } => {
+                format!("{} is not exported by module {}", func, module)
+            }
+            ErrorCode::CircularImport { message, .. } => message.clone(),
+            ErrorCode::FunctionNameCollision {
+                module,
+                func,
+                count: Some(count),
+            } => format!("{} is declared {} times in module {}", func, count, module),
+            ErrorCode::FunctionNameCollision { module, func, .. } => {
+                format!("{} is already declared in module {}", func, module)
+            }
+            ErrorCode::UnexpectedExprType { message, .. } => message.clone(),
+            ErrorCode::FunctionRecursionDetected {
+                stack,
+                module,
+                func,
+                ..
+            } => format!(
+                "recursion detected in {}::{} ({})",
+                module,
+                func,
+                stack.join(" -> ")
+            ),
+            ErrorCode::FunctionUnexpectedNumberOfArguments {
+                module,
+                func,
+                expected,
+                got,
+            } => format!(
+                "{}::{} expects {} arguments, got {}",
+                module, func, expected, got
+            ),
+            ErrorCode::StrictModeViolation { violation } => {
+                format!("strict mode: {}", violation.describe())
+            }
+            ErrorCode::AssignmentToConstant { module, ident } => {
+                format!(
+                    "cannot assign to the constant {} of module {}",
+                    ident, module
+                )
+            }
+            ErrorCode::SyntaxError { excerpt } if excerpt.is_empty() => {
+                "unexpected end of statement".to_string()
+            }
+            ErrorCode::SyntaxError { excerpt } => format!("unexpected `{}`", excerpt),
+            ErrorCode::UnexpectedToken { expected, found } => {
+                format!("expected {}, found {}", expected, found)
+            }
+            ErrorCode::UnexpectedEnd => "END does not close a block".to_string(),
+            ErrorCode::UnclosedBlock { excerpt } => {
+                format!("{} is never closed by END", excerpt)
+            }
+            ErrorCode::MisplacedStatement { excerpt } => format!(
+                "`{}` is out of order, imports, constants, macros and functions come before the code",
+                excerpt
+            ),
+            ErrorCode::UnsupportedOperator => "unsupported operator".to_string(),
+            ErrorCode::UnsupportedComparison => "unsupported comparison".to_string(),
+            ErrorCode::LoopNotEnabled => {
+                "LOOP cannot be used if neither LOOP nor WHILE are enabled".to_string()
+            }
+            ErrorCode::WhileNotEnabled => {
+                "WHILE cannot be used if WHILE is not enabled".to_string()
+            }
+            ErrorCode::MacroNoMatch => "no macro matches the statement".to_string(),
+            ErrorCode::MacroAmbiguous { candidates } => {
+                format!(
+                    "the statement matches multiple macros: {}",
+                    candidates.join(", ")
+                )
+            }
+            ErrorCode::MacroRecursionLimit { ident } => {
+                format!("the expansion of macro {} does not terminate", ident)
+            }
+            ErrorCode::MacroModuleNotFound { module } => {
+                format!("could not find macro module {}", module)
+            }
+            ErrorCode::MacroNotFound { module, ident } => {
+                format!("could not find macro {} in module {}", ident, module)
+            }
+            ErrorCode::MacroNameCollision { module, ident } => {
+                format!("macro {} is already declared in module {}", ident, module)
+            }
+            ErrorCode::MacroCircularImport { module, history } => format!(
+                "circular macro import of {} ({} -> {})",
+                module,
+                history.join(" -> "),
+                module
+            ),
+            ErrorCode::MacroMisplacedPlaceholder { ident, placeholder } => format!(
+                "{} can only be used in the substitution of macro {}",
+                placeholder, ident
+            ),
+            ErrorCode::MacroUndefinedPlaceholder { ident, placeholder } => {
+                format!("{} is not defined in macro {}", placeholder, ident)
+            }
+            ErrorCode::MacroUngroupedPlaceholder { ident, placeholder } => format!(
+                "{} is part of a group and must be used in a group of macro {}",
+                placeholder, ident
+            ),
+            ErrorCode::MacroInvalidGroup { ident } => {
+                format!("a group of macro {} must repeat exactly one group", ident)
+            }
+            ErrorCode::MacroInconsistentPlaceholder {
+                ident,
+                placeholder,
+                expected,
+            } => format!(
+                "{} is used as another type than {} in macro {}",
+                placeholder, expected, ident
+            ),
+        }
+    }
+}
+
+impl From<&ErrorKind> for ErrorCode {
+    fn from(kind: &ErrorKind) -> Self {
+        let qualified = |module: &str, ident: &str| format!("{}::{}", module, ident);
+
+        match kind {
+            ErrorKind::Parse(kind) => match kind.clone() {
+                ErrorKindParse::Syntax { excerpt } => ErrorCode::SyntaxError { excerpt },
+                ErrorKindParse::UnexpectedEnd => ErrorCode::UnexpectedEnd,
+                ErrorKindParse::Unclosed { excerpt } => ErrorCode::UnclosedBlock { excerpt },
+                ErrorKindParse::Misplaced { excerpt } => ErrorCode::MisplacedStatement { excerpt },
+            },
+            ErrorKind::InvalidToken(token) => ErrorCode::UnexpectedToken {
+                expected: token.expected().to_string(),
+                found: token.got().to_string(),
+            },
+            ErrorKind::Unsupported(ErrorKindUnsupported::BinOp) => ErrorCode::UnsupportedOperator,
+            ErrorKind::Unsupported(ErrorKindUnsupported::Comp) => ErrorCode::UnsupportedComparison,
+            ErrorKind::Macro(kind) => match kind.clone() {
+                ErrorKindMacro::NoMatch => ErrorCode::MacroNoMatch,
+                ErrorKindMacro::Ambiguous { candidates } => ErrorCode::MacroAmbiguous {
+                    candidates: candidates
+                        .iter()
+                        .map(|(module, ident)| qualified(module, ident))
+                        .collect(),
+                },
+                ErrorKindMacro::RecursionLimit { ident } => {
+                    ErrorCode::MacroRecursionLimit { ident }
+                }
+                ErrorKindMacro::ModuleNotFound { module } => {
+                    ErrorCode::MacroModuleNotFound { module }
+                }
+                ErrorKindMacro::NotFound { module, ident } => {
+                    ErrorCode::MacroNotFound { module, ident }
+                }
+                ErrorKindMacro::Collision { module, ident } => {
+                    ErrorCode::MacroNameCollision { module, ident }
+                }
+                ErrorKindMacro::CircularImport { module, history } => {
+                    ErrorCode::MacroCircularImport { module, history }
+                }
+                ErrorKindMacro::MisplacedPlaceholder { ident, placeholder } => {
+                    ErrorCode::MacroMisplacedPlaceholder { ident, placeholder }
+                }
+                ErrorKindMacro::UndefinedPlaceholder { ident, placeholder } => {
+                    ErrorCode::MacroUndefinedPlaceholder { ident, placeholder }
+                }
+                ErrorKindMacro::UngroupedPlaceholder { ident, placeholder } => {
+                    ErrorCode::MacroUngroupedPlaceholder { ident, placeholder }
+                }
+                ErrorKindMacro::InvalidGroup { ident } => ErrorCode::MacroInvalidGroup { ident },
+                ErrorKindMacro::InconsistentPlaceholder {
+                    ident,
+                    placeholder,
+                    expected,
+                } => ErrorCode::MacroInconsistentPlaceholder {
+                    ident,
+                    placeholder,
+                    expected,
+                },
+            },
+        }
+    }
+}
+
+impl PestErrorInfo {
+    fn message(&self) -> String {
+        match self {
+            PestErrorInfo::Error { variant, .. } => variant.message(),
+            PestErrorInfo::ErrorVariantParsingError {
+                positives,
+                negatives,
+            } => match (positives.is_empty(), negatives.is_empty()) {
+                (false, _) => format!("expected {}", positives.join(", ")),
+                (true, false) => format!("unexpected {}", negatives.join(", ")),
+                (true, true) => "syntax error".to_string(),
+            },
+            PestErrorInfo::ErrorVariantCustomError(message) => message.clone(),
+            _ => "syntax error".to_string(),
+        }
+    }
+}
+
+impl Error {
+    // syntax errors of the grammar share their identifier with those of the combine parser
+    pub fn id(&self) -> &'static str {
+        match &self.variant {
+            ErrorVariant::ErrorCode(code) => code.id(),
+            ErrorVariant::Parse(_) => "E001",
+            ErrorVariant::Rust(RustError::Io(_)) => "E016",
+        }
+    }
+
+    // a human readable description, without the location
+    pub fn message(&self) -> String {
+        match &self.variant {
+            ErrorVariant::ErrorCode(code) => code.message(),
+            ErrorVariant::Parse(info) => info.message(),
+            ErrorVariant::Rust(RustError::Io(kind)) => format!("io error: {}", kind),
         }
     }
 }
@@ -27,7 +27,7 @@ impl AssignExec {
         self.exhausted = true;
 
         Some(ExecutionResult(
-            self.lno.0,
+            self.lno,
             vec![ChangeLog::Ident(self.lhs.clone())],
         ))
     }
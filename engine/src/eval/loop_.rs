@@ -39,7 +39,7 @@ impl LoopExec {
             self.init = true;
 
             return Some(ExecutionResult(
-                self.lno.0,
+                self.lno,
                 vec![ChangeLog::Internal(InternalAction::LoopIteration)],
             ));
         }
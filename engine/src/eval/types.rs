@@ -2,6 +2,7 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::types::LineNo;
 use num_bigint::BigUint;
 use std::collections::HashMap;
 
@@ -24,4 +25,4 @@ pub type ChangeSet = Vec<String>;
 
 #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "cli", derive(JsonSchema))]
-pub struct ExecutionResult(pub usize, pub Vec<ChangeLog>);
+pub struct ExecutionResult(pub LineNo, pub Vec<ChangeLog>);
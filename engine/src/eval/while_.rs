@@ -30,7 +30,7 @@ impl WhileExec {
             self.check = false;
 
             return Some(ExecutionResult(
-                self.lno.0,
+                self.lno,
                 vec![ChangeLog::Internal(InternalAction::WhileComparison)],
             ));
         }
@@ -0,0 +1,568 @@
+// Long-form explanations of the error codes, shown by `lit explain` and the help of the web UI.
+// Every identifier returned by Error::id() has an entry and retired identifiers keep theirs, the
+// examples are written like programs of the course, other modules are separated with a comment
+// naming their file.
+
+use serde::Serialize;
+
+#[derive(Debug, Serialize, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Explanation {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    // a program which produces the error
+    pub example: Option<&'static str>,
+    // the same program without the error
+    pub fix: Option<&'static str>,
+}
+
+pub const EXPLANATIONS: [Explanation; 33] = [
+    Explanation {
+        id: "E001",
+        title: "syntax error",
+        description: "The source does not follow the grammar of the language. The error points to the \
+first token which could not be parsed and lists the tokens which would have been valid instead. \
+Assignments need a value on the right side, blocks are opened with DO, DECL or THEN and closed \
+with END.",
+        example: Some("x := \nLOOP x\n    y := y + 1\nEND\n"),
+        fix: Some("x := 2\nLOOP x DO\n    y := y + 1\nEND\n"),
+    },
+    Explanation {
+        id: "E002",
+        title: "unsupported macro (retired)",
+        description: "This code is no longer emitted. It was reported when the combine parser \
+(cnf_combine) could not convert macro imports or macro declarations, the combine frontend now \
+expands the declared and imported macros.",
+        example: None,
+        fix: None,
+    },
+    Explanation {
+        id: "E003",
+        title: "module not found",
+        description: "An import refers to a module which does not exist. Modules starting with fs:: \
+are files next to the main file without the .lp extension, modules starting with std:: are part \
+of the standard library.",
+        example: Some("FROM fs::helpers IMPORT double\n\nx := double(2)\n"),
+        fix: Some("# helpers.lp\nPUB FN double(a) -> b DECL\n    b := a + a\nEND\n\n# main.lp\nFROM fs::helpers IMPORT double\n\nx := double(2)\n"),
+    },
+    Explanation {
+        id: "E004",
+        title: "function not found",
+        description: "A function is called or imported which is not declared in the module it \
+should be in. Check the spelling of the function and whether it is declared in the module or \
+imported into it.",
+        example: Some("IMPORT std::math AS m\n\nz := m::avg(x, y)\n"),
+        fix: Some("IMPORT std::math AS m\n\nz := m::min(x, y)\n"),
+    },
+    Explanation {
+        id: "E005",
+        title: "function not exported",
+        description: "Once a module declares a function with PUB, only the functions declared \
+with PUB can be imported from it, every other function is private to the module. Modules \
+without any PUB declaration export all of their functions.",
+        example: Some(
+            "# a.lp\nPUB FN c(c) -> e DECL\n    e := c + 2\nEND\n\nFN d(d) -> e DECL\n    e := d + 1\nEND\n\n# main.lp\nFROM fs::a IMPORT d\n\nx := d(2)\n",
+        ),
+        fix: Some(
+            "# a.lp\nPUB FN c(c) -> e DECL\n    e := c + 2\nEND\n\nPUB FN d(d) -> e DECL\n    e := d + 1\nEND\n\n# main.lp\nFROM fs::a IMPORT d\n\nx := d(2)\n",
+        ),
+    },
+    Explanation {
+        id: "E006",
+        title: "circular import",
+        description: "Modules import each other, directly or over other modules. Functions are \
+inlined on compilation, therefore the imports of modules must not form a cycle. Move the \
+functions both modules need into a third module.",
+        example: Some("# a.lp\nFROM fs::b IMPORT g\n\n# b.lp\nFROM fs::a IMPORT f\n\n# main.lp\nFROM fs::a IMPORT f\n"),
+        fix: None,
+    },
+    Explanation {
+        id: "E007",
+        title: "function name collision",
+        description: "A name refers to more than one function in a module, because a function is \
+declared twice or a function is imported with the name of a function declared in the module. \
+Rename one of them, or import the function with another name using AS.",
+        example: Some("FN double(a) -> b DECL\n    b := a + a\nEND\n\nFN double(a) -> b DECL\n    b := a * 2\nEND\n"),
+        fix: Some("FN double(a) -> b DECL\n    b := a + a\nEND\n\nFN twice(a) -> b DECL\n    b := a * 2\nEND\n"),
+    },
+    Explanation {
+        id: "E008",
+        title: "multiple module candidates (retired)",
+        description: "This code is no longer emitted. It was reported when a module name matched \
+more than one file in the lib folder, modules are now looked up by their path in the embedded \
+standard library or next to the main file.",
+        example: None,
+        fix: None,
+    },
+    Explanation {
+        id: "E009",
+        title: "unexpected expression",
+        description: "The compiler expected an identifier, like the name, a parameter or the \
+return value of a function, but found another kind of expression. This is usually caused by a \
+program tree which has not been created by the parser.",
+        example: None,
+        fix: None,
+    },
+    Explanation {
+        id: "E010",
+        title: "recursion detected",
+        description: "A function calls itself, directly or over other functions. Functions are \
+inlined on compilation, a recursive function would never stop to expand. Write the function \
+with LOOP or WHILE instead.",
+        example: Some("FN a(b) -> c DECL\n    c := a(b)\nEND\n\nx := a(2)\n"),
+        fix: Some("FN a(b) -> c DECL\n    c := b + 1\nEND\n\nx := a(2)\n"),
+    },
+    Explanation {
+        id: "E011",
+        title: "wrong number of arguments",
+        description: "A function is called with more or fewer arguments than it has parameters.",
+        example: Some("FN add(a, b) -> c DECL\n    c := a + b\nEND\n\nx := add(1)\n"),
+        fix: Some("FN add(a, b) -> c DECL\n    c := a + b\nEND\n\nx := add(1, 2)\n"),
+    },
+    Explanation {
+        id: "E012",
+        title: "strict mode violation",
+        description: "A strict flag forbids a part of the language: strct_no_macro forbids macros \
+like IF or x := y + z, strct_no_func forbids functions and strct_no_lplwr forbids to lower LOOP \
+into WHILE if only WHILE is enabled. Write the program with the plain statements of the language \
+or compile it without the flag.",
+        example: Some("# compiled with loop,strct_no_macro\nx := y + z\n"),
+        fix: Some(
+            "# compiled with loop,strct_no_macro\nx := y + 0\nLOOP z DO\n    x := x + 1\nEND\n",
+        ),
+    },
+    Explanation {
+        id: "E013",
+        title: "assignment to a constant",
+        description: "Constants declared with CONST cannot be changed after their declaration. \
+With cnf_const the builtin constants are protected as well. Assign the value to a variable first \
+and change the variable instead.",
+        example: Some("CONST LIMIT = 3\n\nLIMIT := LIMIT + 1\n"),
+        fix: Some("CONST LIMIT = 3\n\nx := LIMIT + 1\n"),
+    },
+    Explanation {
+        id: "E014",
+        title: "LOOP is not enabled",
+        description: "LOOP can only be used if LOOP or WHILE are enabled, if only WHILE is enabled \
+it is lowered into WHILE.",
+        example: Some("# compiled without loop and while\nLOOP x DO\n    y := y + 1\nEND\n"),
+        fix: Some("# compiled with loop\nLOOP x DO\n    y := y + 1\nEND\n"),
+    },
+    Explanation {
+        id: "E015",
+        title: "WHILE is not enabled",
+        description: "WHILE can only be used if WHILE is enabled, LOOP programs cannot be \
+rewritten to express it. Enable WHILE or use LOOP with a bound for the number of iterations.",
+        example: Some("# compiled with loop\nWHILE x != 0 DO\n    x := x - 1\nEND\n"),
+        fix: Some("# compiled with loop\nLOOP x DO\n    x := x - 1\nEND\n"),
+    },
+    Explanation {
+        id: "E016",
+        title: "io error",
+        description: "A file could not be read, for example a module next to the main file. \
+Check that the file exists and is readable.",
+        example: None,
+        fix: None,
+    },
+    Explanation {
+        id: "E017",
+        title: "no macro matches",
+        description: "The statement is neither a statement of the language nor an invocation of \
+a macro in scope. Macros are only visible in the module which declares them and in the modules \
+which import them with MACRO IMPORT.",
+        example: Some("x := 2\nx++\n"),
+        fix: Some("FROM std::macros::inline MACRO IMPORT *\n\nx := 2\nx++\n"),
+    },
+    Explanation {
+        id: "E018",
+        title: "ambiguous macro invocation",
+        description: "The statement matches multiple macros with the same specificity, the \
+compiler cannot decide which one to expand. Remove one of the macros, do not import both or make \
+one of the patterns more specific, for example by using a value (%n.n) instead of a primitive \
+(%n.p).",
+        example: Some(
+            "@macro double\n    %1.i *= 2\n@sub\n    %1.i := %1.i + %1.i\n@end\n\n@macro twice\n    %1.i *= 2\n@sub\n    %1.i := %1.i * 2\n@end\n\nx := 2\nx *= 2\n",
+        ),
+        fix: Some("@macro double\n    %1.i *= 2\n@sub\n    %1.i := %1.i + %1.i\n@end\n\nx := 2\nx *= 2\n"),
+    },
+    Explanation {
+        id: "E019",
+        title: "macro recursion limit",
+        description: "The expansion of a macro contains an invocation of itself, directly or over \
+other macros, and never reaches statements of the language. Every expansion has to get closer to \
+plain statements.",
+        example: Some("@macro grow\n    %1.i++\n@sub\n    %1.i++\n@end\n\nx++\n"),
+        fix: Some("@macro grow\n    %1.i++\n@sub\n    %1.i := %1.i + 1\n@end\n\nx++\n"),
+    },
+    Explanation {
+        id: "E020",
+        title: "macro module not found",
+        description: "A MACRO IMPORT refers to a module which does not exist. The macros of the \
+standard library are in std::macros::assign, std::macros::ifelse and std::macros::inline.",
+        example: Some("FROM std::macros::inlined MACRO IMPORT *\n\nx++\n"),
+        fix: Some("FROM std::macros::inline MACRO IMPORT *\n\nx++\n"),
+    },
+    Explanation {
+        id: "E021",
+        title: "macro not found",
+        description: "A MACRO IMPORT names a macro which is not declared in or imported into the \
+module.",
+        example: Some("FROM std::macros::inline MACRO IMPORT inlineIncr\n\nx++\n"),
+        fix: Some("FROM std::macros::inline MACRO IMPORT inlineIncrement\n\nx++\n"),
+    },
+    Explanation {
+        id: "E022",
+        title: "macro name collision",
+        description: "A name refers to more than one macro in a module, because a macro is \
+declared twice or a macro is imported with the name of a macro declared in the module. Rename one \
+of them, or import the macro with another name using AS.",
+        example: Some(
+            "FROM std::macros::inline MACRO IMPORT inlineIncrement\n\n@macro inlineIncrement\n    INCR %1.i\n@sub\n    %1.i := %1.i + 1\n@end\n",
+        ),
+        fix: Some(
+            "FROM std::macros::inline MACRO IMPORT inlineIncrement\n\n@macro incr\n    INCR %1.i\n@sub\n    %1.i := %1.i + 1\n@end\n",
+        ),
+    },
+    Explanation {
+        id: "E023",
+        title: "circular macro import",
+        description: "Modules import macros from each other, directly or over other modules. \
+Move the macros both modules need into a third module.",
+        example: Some("# a.lp\nFROM fs::b MACRO IMPORT *\n\n# b.lp\nFROM fs::a MACRO IMPORT *\n\n# main.lp\nFROM fs::a MACRO IMPORT *\n"),
+        fix: None,
+    },
+    Explanation {
+        id: "E024",
+        title: "misplaced placeholder",
+        description: "Placeholders starting with $ introduce new variables of the substitution, \
+they cannot be used in the pattern or in the condition of @if.",
+        example: Some("@macro reset\n    RESET %1.i\n@sub\n    @if $2.i == 0\n        %1.i := 0\n    @end\n@end\n"),
+        fix: Some("@macro reset\n    RESET %1.i\n@sub\n    %1.i := 0\n@end\n"),
+    },
+    Explanation {
+        id: "E025",
+        title: "undefined placeholder",
+        description: "A placeholder starting with % is used in the substitution but not declared \
+in the pattern, or a placeholder starting with $ is read before a value is assigned to it.",
+        example: Some("@macro copy\n    %1.i <- %2.i\n@sub\n    %1.i := %3.i + 0\n@end\n"),
+        fix: Some("@macro copy\n    %1.i <- %2.i\n@sub\n    %1.i := %2.i + 0\n@end\n"),
+    },
+    Explanation {
+        id: "E026",
+        title: "ungrouped placeholder",
+        description: "A placeholder declared in a repetition @( ... @) of the pattern matches \
+multiple tokens, it can only be used in a repetition of the substitution.",
+        example: Some("@macro clear\n    CLEAR @( %1.i @),+\n@sub\n    %1.i := 0\n@end\n"),
+        fix: Some("@macro clear\n    CLEAR @( %1.i @),+\n@sub\n    @( %1.i := 0 @);\n@end\n"),
+    },
+    Explanation {
+        id: "E027",
+        title: "invalid repetition",
+        description: "A repetition @( ... @) of the substitution has to use the placeholders of \
+exactly one repetition of the pattern and repetitions cannot be nested.",
+        example: Some(
+            "@macro copy\n    COPY @( %1.i @),+ TO @( %2.i @),+\n@sub\n    @( %2.i := %1.i + 0 @);\n@end\n",
+        ),
+        fix: Some("@macro copy\n    COPY %1.i TO @( %2.i @),+\n@sub\n    @( %2.i := %1.i + 0 @);\n@end\n"),
+    },
+    Explanation {
+        id: "E028",
+        title: "inconsistent placeholder",
+        description: "A placeholder is used with another type than it has been declared with. \
+The type follows the dot: i for identifiers, n for numbers, p for either of them, o for \
+operators and c for comparisons.",
+        example: Some("@macro copy\n    %1.i <- %2.i\n@sub\n    %1.i := %2.n + 0\n@end\n"),
+        fix: Some("@macro copy\n    %1.i <- %2.i\n@sub\n    %1.i := %2.i + 0\n@end\n"),
+    },
+    Explanation {
+        id: "E029",
+        title: "unexpected END",
+        description: "An END is found where no block is open, usually because a block has been \
+closed twice. Every LOOP, WHILE, IF and function declaration is closed by exactly one END.",
+        example: Some("LOOP x DO\n    y := y + 1\nEND\nEND\n"),
+        fix: Some("LOOP x DO\n    y := y + 1\nEND\n"),
+    },
+    Explanation {
+        id: "E030",
+        title: "unclosed block",
+        description: "A block is opened with DO, DECL or THEN, or by a macro which ends with END, \
+but the file ends before the block is closed. The error points to the token which opens the \
+block.",
+        example: Some("LOOP x DO\n    y := y + 1\n"),
+        fix: Some("LOOP x DO\n    y := y + 1\nEND\n"),
+    },
+    Explanation {
+        id: "E031",
+        title: "misplaced statement",
+        description: "The parts of a file have a fixed order: the imports come first, followed by \
+the constants, the macro declarations, the function declarations and at last the code. Move the \
+statement above the parts which need to come after it.",
+        example: Some("x := 2\n\nFROM std::math IMPORT max\n\ny := max(x, 3)\n"),
+        fix: Some("FROM std::math IMPORT max\n\nx := 2\ny := max(x, 3)\n"),
+    },
+    Explanation {
+        id: "E032",
+        title: "unsupported operator",
+        description: "Only the operators +, - and * are part of the language. Division and the \
+other operations are written as functions, which compute them with LOOP or WHILE.",
+        example: None,
+        fix: None,
+    },
+    Explanation {
+        id: "E033",
+        title: "unsupported comparison",
+        description: "Only the comparisons ==, !=, <, <=, > and >= are part of the language, other \
+conditions are written with IF and the comparisons of the language.",
+        example: None,
+        fix: None,
+    },
+];
+
+// accepts the identifier in any case and without leading zeros, like e13 or 13
+pub fn explain(id: &str) -> Option<&'static Explanation> {
+    let id = id.trim();
+    let number = id
+        .strip_prefix(['E', 'e'])
+        .unwrap_or(id)
+        .parse::<usize>()
+        .ok()?;
+
+    EXPLANATIONS
+        .iter()
+        .find(|explanation| explanation.id[1..].parse() == Ok(number))
+}
+
+//region Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::hir::func::fs::Directory;
+    use crate::build::Builder;
+    use crate::errors::{Error, ErrorCode, StrictModeViolation};
+    use crate::flags::CompileFlags;
+    use std::collections::HashSet;
+    use variants::err::{ErrorKind, ErrorKindMacro, ErrorKindParse, ErrorKindUnsupported};
+
+    fn ids(source: &str, flags: CompileFlags, fs: Option<Directory>) -> Vec<&'static str> {
+        Builder::ext_all(source, Some(flags), None, fs)
+            .err()
+            .unwrap_or_else(|| panic!("expected an error in {}", source))
+            .iter()
+            .map(Error::id)
+            .collect()
+    }
+
+    #[test]
+    fn test_explain_lookup() {
+        assert_eq!(explain("E013").unwrap().title, "assignment to a constant");
+        assert_eq!(explain("e13").unwrap().id, "E013");
+        assert_eq!(explain(" 1 ").unwrap().id, "E001");
+        assert_eq!(explain("E999"), None);
+        assert_eq!(explain("X1"), None);
+
+        // retired codes keep their entry, so the identifier is not reused
+        assert!(explain("E002").unwrap().title.ends_with("(retired)"));
+        assert!(explain("E008").unwrap().title.ends_with("(retired)"));
+
+        let ids: HashSet<_> = EXPLANATIONS
+            .iter()
+            .map(|explanation| explanation.id)
+            .collect();
+        assert_eq!(ids.len(), EXPLANATIONS.len());
+
+        // every code has an explanation
+        let codes = vec![
+            ErrorCode::LoopNotEnabled,
+            ErrorCode::WhileNotEnabled,
+            ErrorCode::StrictModeViolation {
+                violation: StrictModeViolation::MacroForbidden,
+            },
+        ];
+        for code in codes {
+            assert!(explain(code.id()).is_some());
+        }
+        assert!(explain(Error::new_from_io(std::io::ErrorKind::NotFound.into()).id()).is_some());
+
+        // every error of the macro resolution has its own code
+        let (module, ident, placeholder) = (String::new(), String::new(), String::new());
+        let kinds = vec![
+            ErrorKindMacro::NoMatch,
+            ErrorKindMacro::Ambiguous { candidates: vec![] },
+            ErrorKindMacro::RecursionLimit {
+                ident: ident.clone(),
+            },
+            ErrorKindMacro::ModuleNotFound {
+                module: module.clone(),
+            },
+            ErrorKindMacro::NotFound {
+                module: module.clone(),
+                ident: ident.clone(),
+            },
+            ErrorKindMacro::Collision {
+                module: module.clone(),
+                ident: ident.clone(),
+            },
+            ErrorKindMacro::CircularImport {
+                module,
+                history: vec![],
+            },
+            ErrorKindMacro::MisplacedPlaceholder {
+                ident: ident.clone(),
+                placeholder: placeholder.clone(),
+            },
+            ErrorKindMacro::UndefinedPlaceholder {
+                ident: ident.clone(),
+                placeholder: placeholder.clone(),
+            },
+            ErrorKindMacro::UngroupedPlaceholder {
+                ident: ident.clone(),
+                placeholder: placeholder.clone(),
+            },
+            ErrorKindMacro::InvalidGroup {
+                ident: ident.clone(),
+            },
+            ErrorKindMacro::InconsistentPlaceholder {
+                ident,
+                placeholder,
+                expected: String::new(),
+            },
+        ];
+        let count = kinds.len();
+        let ids: HashSet<_> = kinds
+            .into_iter()
+            .map(|kind| ErrorCode::from(&ErrorKind::Macro(kind)).id())
+            .collect();
+        assert_eq!(ids.len(), count);
+        for id in ids {
+            assert!(explain(id).is_some());
+        }
+
+        // every error of the combine parser has its own code
+        let excerpt = String::new();
+        let kinds = [
+            ErrorKind::Parse(ErrorKindParse::Syntax {
+                excerpt: excerpt.clone(),
+            }),
+            ErrorKind::Parse(ErrorKindParse::UnexpectedEnd),
+            ErrorKind::Parse(ErrorKindParse::Unclosed {
+                excerpt: excerpt.clone(),
+            }),
+            ErrorKind::Parse(ErrorKindParse::Misplaced { excerpt }),
+            ErrorKind::Unsupported(ErrorKindUnsupported::BinOp),
+            ErrorKind::Unsupported(ErrorKindUnsupported::Comp),
+        ];
+        let count = kinds.len();
+        let ids: HashSet<_> = kinds
+            .iter()
+            .map(|kind| ErrorCode::from(kind).id())
+            .collect();
+        assert_eq!(ids.len(), count);
+        for id in ids {
+            assert!(explain(id).is_some());
+        }
+    }
+
+    #[test]
+    fn test_explain_examples() {
+        let default = CompileFlags::default();
+
+        assert_eq!(
+            ids(explain("E001").unwrap().example.unwrap(), default, None),
+            vec!["E001"]
+        );
+        assert_eq!(
+            ids(explain("E004").unwrap().example.unwrap(), default, None),
+            vec!["E004"]
+        );
+        assert_eq!(
+            ids(explain("E010").unwrap().example.unwrap(), default, None),
+            vec!["E010"]
+        );
+        assert_eq!(
+            ids(explain("E011").unwrap().example.unwrap(), default, None),
+            vec!["E011"]
+        );
+        assert_eq!(
+            ids(explain("E013").unwrap().example.unwrap(), default, None),
+            vec!["E013"]
+        );
+        assert_eq!(
+            ids(
+                explain("E012").unwrap().example.unwrap(),
+                CompileFlags::LOOP | CompileFlags::STRCT_NO_MACRO,
+                None
+            ),
+            vec!["E012"]
+        );
+        assert_eq!(
+            ids(
+                explain("E014").unwrap().example.unwrap(),
+                CompileFlags::empty(),
+                None
+            ),
+            vec!["E014"]
+        );
+        assert_eq!(
+            ids(
+                explain("E015").unwrap().example.unwrap(),
+                CompileFlags::LOOP,
+                None
+            ),
+            vec!["E015"]
+        );
+
+        let mut fs = Directory::new();
+        fs.insert(
+            "a".to_string(),
+            "PUB FN c(c) -> e DECL\n    e := c + 2\nEND\n\nFN d(d) -> e DECL\n    e := d + 1\nEND\n"
+                .to_string()
+                .into(),
+        );
+        assert_eq!(
+            ids("FROM fs::a IMPORT d\n\nx := d(2)\n", default, Some(fs)),
+            vec!["E005"]
+        );
+
+        // the corrected examples compile
+        for id in ["E004", "E010", "E011", "E013"] {
+            let fix = explain(id).unwrap().fix.unwrap();
+            assert!(Builder::ext_all(fix, None, None, None).is_ok(), "{}", id);
+        }
+        let fix = explain("E012").unwrap().fix.unwrap();
+        let flags = CompileFlags::LOOP | CompileFlags::STRCT_NO_MACRO;
+        assert!(Builder::ext_all(fix, Some(flags), None, None).is_ok());
+
+        // the macros are expanded by the combine frontend
+        let combine = default | CompileFlags::CNF_COMBINE;
+        let macros = [
+            "E017", "E018", "E019", "E020", "E021", "E022", "E024", "E025", "E026", "E027", "E028",
+            "E029", "E030", "E031",
+        ];
+        for id in macros {
+            let explanation = explain(id).unwrap();
+            assert_eq!(ids(explanation.example.unwrap(), combine, None), vec![id]);
+
+            let fix = explanation.fix.unwrap();
+            assert!(
+                Builder::ext_all(fix, Some(combine), None, None).is_ok(),
+                "{}",
+                id
+            );
+        }
+
+        let mut fs = Directory::new();
+        fs.insert(
+            "a".to_string(),
+            "FROM fs::b MACRO IMPORT *\n".to_string().into(),
+        );
+        fs.insert(
+            "b".to_string(),
+            "FROM fs::a MACRO IMPORT *\n".to_string().into(),
+        );
+        assert_eq!(
+            ids("FROM fs::a MACRO IMPORT *\n", combine, Some(fs)),
+            vec!["E023"]
+        );
+    }
+}
+//endregion
@@ -12,6 +12,8 @@ bitflags! {
         const CNF_RETAIN_LNO = 0b0001 << 4;
         // enable const variables (Assignment to CONST var is forbidden)
         const CNF_CONST      = 0b0010 << 4;
+        // parse the source with the combine parser instead of the pest grammar
+        const CNF_COMBINE    = 0b0100 << 4;
 
         //-- Optimization Features --//
         // enable dedicated zero variable (needs const conf enabled)
@@ -44,3 +46,99 @@ impl Default for CompileFlags {
         CompileFlags::LOOP | CompileFlags::WHILE
     }
 }
+
+// the names of the flags on the command line, compound flags are written out
+#[cfg(feature = "cli")]
+const NAMES: [(&str, CompileFlags); 10] = [
+    ("loop", CompileFlags::LOOP),
+    ("while", CompileFlags::WHILE),
+    ("cnf_retain_lno", CompileFlags::CNF_RETAIN_LNO),
+    ("cnf_const", CompileFlags::CNF_CONST),
+    ("cnf_combine", CompileFlags::CNF_COMBINE),
+    ("opt_zero", CompileFlags::OPT_ZERO),
+    ("strct_no_macro", CompileFlags::STRCT_NO_MACRO),
+    ("strct_no_func", CompileFlags::STRCT_NO_FUNC),
+    ("strct_no_lplwr", CompileFlags::STRCT_NO_LPLWR),
+    ("strct", CompileFlags::STRCT),
+];
+
+// flags are separated by commas or |, like loop,while,cnf_const
+#[cfg(feature = "cli")]
+impl std::str::FromStr for CompileFlags {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut flags = CompileFlags::empty();
+
+        for name in s
+            .split([',', '|'])
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            match NAMES
+                .iter()
+                .find(|(flag, _)| flag.eq_ignore_ascii_case(name))
+            {
+                Some((_, flag)) => flags |= *flag,
+                None => {
+                    let names: Vec<_> = NAMES.iter().map(|(flag, _)| *flag).collect();
+
+                    return Err(format!(
+                        "unknown flag {}, expected one of {}",
+                        name,
+                        names.join(", ")
+                    ));
+                }
+            }
+        }
+
+        Ok(flags)
+    }
+}
+
+// the inverse of from_str, flags included in a compound flag are not repeated
+#[cfg(feature = "cli")]
+impl std::fmt::Display for CompileFlags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<_> = NAMES
+            .iter()
+            .filter(|(_, flag)| self.contains(*flag))
+            .filter(|(_, flag)| {
+                !NAMES.iter().any(|(_, other)| {
+                    other != flag && self.contains(*other) && other.contains(*flag)
+                })
+            })
+            .map(|(name, _)| *name)
+            .collect();
+
+        write!(f, "{}", names.join(","))
+    }
+}
+
+#[cfg(all(test, feature = "cli"))]
+mod tests {
+    use crate::flags::CompileFlags;
+
+    #[test]
+    fn test_flags_from_str() {
+        assert_eq!("loop,while".parse(), Ok(CompileFlags::default()));
+        assert_eq!(
+            "WHILE | cnf_const".parse(),
+            Ok(CompileFlags::WHILE | CompileFlags::CNF_CONST)
+        );
+        assert_eq!("".parse(), Ok(CompileFlags::empty()));
+        assert!("loop,for".parse::<CompileFlags>().is_err());
+    }
+
+    #[test]
+    fn test_flags_display() {
+        assert_eq!(CompileFlags::default().to_string(), "loop,while");
+        assert_eq!(
+            (CompileFlags::STRCT | CompileFlags::OPT_ZERO).to_string(),
+            "opt_zero,strct"
+        );
+
+        let flags = CompileFlags::WHILE | CompileFlags::STRCT_NO_FUNC;
+        assert_eq!(flags.to_string().parse(), Ok(flags));
+    }
+}
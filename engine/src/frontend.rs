@@ -0,0 +1,736 @@
+use combine_parser::Openers;
+use either::Either;
+use lexer::{Keyword, Kind, Token};
+use module::mcr::Delimiter;
+use num_bigint::BigUint;
+use resolve::dir::Directory;
+use resolve::macros::scope::Scopes;
+#[cfg(feature = "cli")]
+use resolve::macros::trace::Trace;
+use resolve::macros::Resolver;
+
+use crate::ast::control::Control;
+use crate::ast::expr::Expr;
+use crate::ast::hir::func;
+use crate::ast::hir::func::decl::{ConstDecl, FuncDecl};
+use crate::ast::hir::func::imp::{Imp, ImpFunc, ImpModule, ImpWildcard};
+use crate::ast::hir::func::stdlib::stdlib;
+use crate::ast::hir::func::{Func, FuncCall};
+use crate::ast::hir::macros::{Macro, MacroAssign};
+use crate::ast::hir::Hir;
+use crate::ast::module::Module;
+use crate::ast::variant::UInt;
+use crate::ast::verbs::{ComparisonVerb, OperatorVerb};
+use crate::errors::{Error, ErrorCode, StdResult};
+use crate::types::{LineIndex, LineNo};
+
+// The parser used by Builder::parse_with, both result in the same module.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum Frontend {
+    // the grammar in grammar.pest
+    #[default]
+    Pest,
+    // the combine parser of crates/parser, declared and imported macros are expanded while
+    // converting, the macros of the language itself (assignments, function calls and
+    // conditionals) are recognized after parsing.
+    Combine,
+}
+
+fn is_word(token: &Token, word: &str) -> bool {
+    matches!(&token.kind, Kind::Ident(ident) if ident.eq_ignore_ascii_case(word))
+}
+
+fn is_sep(token: &Token) -> bool {
+    matches!(token.kind, Kind::Newline | Kind::Semicolon)
+}
+
+fn is_ident(token: &Token) -> bool {
+    matches!(token.kind, Kind::Ident(_))
+}
+
+fn is_atom(token: &Token) -> bool {
+    matches!(token.kind, Kind::Ident(_) | Kind::Number(_))
+}
+
+fn atom(token: &Token) -> Expr {
+    match &token.kind {
+        Kind::Number(value) => Expr::NaturalNumber(UInt(BigUint::clone(value))),
+        _ => Expr::Ident(token.content.clone()),
+    }
+}
+
+fn ident(value: &str) -> Expr {
+    Expr::Ident(value.to_string())
+}
+
+fn primitive(primitive: &expr::Primitive) -> Expr {
+    match primitive {
+        expr::Primitive::Ident { value, .. } => ident(value),
+        expr::Primitive::Number { value, .. } => Expr::NaturalNumber(UInt(BigUint::clone(value))),
+    }
+}
+
+fn path(path: &[String]) -> Vec<Expr> {
+    path.iter().map(|segment| ident(segment)).collect()
+}
+
+// the macros of the language itself
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Builtin {
+    Conditional,
+    Call,
+    BinaryOp,
+    Assign,
+}
+
+fn recognize(tokens: &[Token]) -> Option<Builtin> {
+    if tokens.first().is_some_and(|token| is_word(token, "if")) {
+        return Some(Builtin::Conditional);
+    }
+
+    match tokens {
+        // x := f(a, b) | x := m::f(a, b)
+        [lhs, assign, func, lparen, ..]
+            if is_ident(lhs)
+                && matches!(assign.kind, Kind::Assign)
+                && matches!(func.kind, Kind::Ident(_) | Kind::Path(_))
+                && matches!(lparen.kind, Kind::Paren(lexer::Pair::Left)) =>
+        {
+            Some(Builtin::Call)
+        }
+        // x := y * n | x := y op z, x := y + n is parsed as assignment
+        [lhs, assign, a, op, b, ..]
+            if is_ident(lhs)
+                && matches!(assign.kind, Kind::Assign)
+                && is_ident(a)
+                && match op.kind {
+                    Kind::Op(lexer::Op::Star) => is_atom(b),
+                    Kind::Op(lexer::Op::Plus | lexer::Op::Minus) => is_ident(b),
+                    _ => false,
+                } =>
+        {
+            Some(Builtin::BinaryOp)
+        }
+        // x := y | x := 0 | x := n
+        [lhs, assign, rhs, ..]
+            if is_ident(lhs) && matches!(assign.kind, Kind::Assign) && is_atom(rhs) =>
+        {
+            Some(Builtin::Assign)
+        }
+        _ => None,
+    }
+}
+
+// the position of ELSE and END of a conditional, nested constructs are closed by their own END
+fn branches(tokens: &[Token]) -> (Option<usize>, Option<usize>) {
+    let mut depth = 0;
+    let mut otherwise = None;
+
+    for (index, token) in tokens.iter().enumerate().skip(1) {
+        match &token.kind {
+            Kind::Keyword(Keyword::Do) => depth += 1,
+            _ if is_word(token, "if") => depth += 1,
+            _ if depth == 0 && is_word(token, "else") && otherwise.is_none() => {
+                otherwise = Some(index)
+            }
+            Kind::Keyword(Keyword::End) if depth == 0 => return (otherwise, Some(index)),
+            Kind::Keyword(Keyword::End) => depth -= 1,
+            _ => {}
+        }
+    }
+
+    (otherwise, None)
+}
+
+// the number of tokens of the statement of the language at the start of the tokens,
+// the resolver leaves them to the converter if no macro matches.
+fn builtin(tokens: &[Token]) -> Option<usize> {
+    match recognize(tokens)? {
+        Builtin::Conditional => branches(tokens).1.map(|end| end + 1),
+        Builtin::Call => tokens
+            .iter()
+            .position(|token| matches!(token.kind, Kind::Paren(lexer::Pair::Right)))
+            .map(|end| end + 1),
+        Builtin::BinaryOp => Some(5),
+        Builtin::Assign => Some(3),
+    }
+}
+
+struct Converter<'a> {
+    lno: Option<LineNo>,
+    openers: Openers,
+    index: LineIndex<'a>,
+
+    // expands the declared and imported macros
+    resolver: Resolver,
+
+    // the errors of the terms left out while recovering, None stops at the first error
+    recovered: Option<Vec<Error>>,
+}
+
+impl<'a> Converter<'a> {
+    fn new(source: &'a str, lno: Option<LineNo>) -> Self {
+        Self {
+            lno,
+            openers: openers(),
+            index: LineIndex::new(source),
+            resolver: Resolver::new(Scopes::default()).with_builtin(builtin),
+            recovered: None,
+        }
+    }
+
+    // the lexer counts rows from 0 and columns in bytes
+    fn lno(&self, lno: &variants::LineNo) -> LineNo {
+        let position = |row: u32, col: u32| self.index.column(row as usize, col as usize);
+
+        self.lno.unwrap_or_else(|| {
+            LineNo::new(
+                position(lno.row.start().into(), lno.col.start().into()),
+                position(lno.row.end().into(), lno.col.end().into()),
+            )
+        })
+    }
+
+    fn errors(&self, errors: variants::Errors) -> Vec<Error> {
+        errors
+            .iter()
+            .map(|error| {
+                Error::new_from_code(
+                    Some(
+                        error
+                            .lno
+                            .map_or(self.lno.unwrap_or_default(), |lno| self.lno(&lno)),
+                    ),
+                    ErrorCode::from(&error.kind),
+                )
+            })
+            .collect()
+    }
+
+    fn unexpected(&self, token: Option<&Token>, expected: &str) -> Vec<Error> {
+        let (lno, found) = match token {
+            Some(token) => (self.lno(&token.lno), format!("`{}`", token.content)),
+            None => (self.lno.unwrap_or_default(), "end of input".to_string()),
+        };
+
+        vec![Error::new_from_code(
+            Some(lno),
+            ErrorCode::UnexpectedToken {
+                expected: expected.to_string(),
+                found,
+            },
+        )]
+    }
+
+    // the macros have already been loaded by the resolver
+    fn module(&mut self, module: module::Module) -> StdResult<Module> {
+        let imp = module
+            .imp
+            .func
+            .iter()
+            .map(|stmt| Imp {
+                lno: self.lno(&stmt.lno),
+                public: stmt.public,
+
+                path: path(&stmt.path),
+                funcs: match &stmt.imports {
+                    Either::Left(aliases) => Either::Left(
+                        aliases
+                            .iter()
+                            .map(|alias| ImpFunc {
+                                ident: Box::new(ident(&alias.ident)),
+                                alias: alias.alias.as_deref().map(|alias| Box::new(ident(alias))),
+                            })
+                            .collect(),
+                    ),
+                    Either::Right(_) => Either::Right(ImpWildcard {}),
+                },
+            })
+            .collect();
+
+        let imp_mod = module
+            .imp
+            .namespace
+            .iter()
+            .map(|namespace| ImpModule {
+                lno: self.lno(&namespace.lno),
+
+                path: path(&namespace.path),
+                alias: namespace
+                    .alias
+                    .as_deref()
+                    .map(|alias| Box::new(ident(alias))),
+            })
+            .collect();
+
+        let consts = module
+            .decl
+            .consts
+            .iter()
+            .map(|decl| ConstDecl {
+                lno: self.lno(&decl.lno),
+                public: decl.public,
+
+                ident: Box::new(primitive(&decl.ident)),
+                value: Box::new(primitive(&decl.value)),
+            })
+            .collect();
+
+        let decl = module
+            .decl
+            .func
+            .into_iter()
+            .map(|decl| {
+                Ok(FuncDecl {
+                    lno: self.lno(&decl.lno),
+                    public: decl.public,
+
+                    ident: Box::new(primitive(&decl.ident)),
+                    params: decl.params.iter().map(primitive).collect(),
+                    ret: Box::new(primitive(&decl.ret)),
+
+                    terms: Box::new(self.resolve(*decl.terms)?),
+                })
+            })
+            .collect::<StdResult<_>>()?;
+
+        Ok(Module {
+            imp,
+            imp_mod,
+            consts,
+            decl,
+            code: self.resolve(module.code)?,
+        })
+    }
+
+    fn resolve(&mut self, hir: hir::Hir) -> StdResult<Hir> {
+        let hir = match self.recovered {
+            Some(_) => self.recovering(hir),
+            None => self
+                .resolver
+                .resolve(hir)
+                .map_err(|errors| self.errors(errors))?,
+        };
+
+        self.hir(hir)
+    }
+
+    // resolve the terms one by one, a term which cannot be resolved is left out of its block
+    fn recovering(&mut self, hir: hir::Hir) -> hir::Hir {
+        match hir {
+            hir::Hir::Control(ctrl::Control::Block { terms }) => {
+                let mut block = vec![];
+
+                for term in terms {
+                    let unknown = matches!(term, hir::Hir::Unknown(_));
+
+                    match self.recovering(term) {
+                        // splice the expansion into the surrounding block, like the resolver
+                        hir::Hir::Control(ctrl::Control::Block { terms }) if unknown => {
+                            block.extend(terms)
+                        }
+                        term => block.push(term),
+                    }
+                }
+
+                hir::Hir::Control(ctrl::Control::Block { terms: block })
+            }
+            hir::Hir::Control(ctrl::Control::Loop { lno, ident, terms }) => {
+                hir::Hir::Control(ctrl::Control::Loop {
+                    lno,
+                    ident,
+                    terms: Box::new(self.recovering(*terms)),
+                })
+            }
+            hir::Hir::Control(ctrl::Control::While { lno, comp, terms }) => {
+                hir::Hir::Control(ctrl::Control::While {
+                    lno,
+                    comp,
+                    terms: Box::new(self.recovering(*terms)),
+                })
+            }
+            hir => self.resolver.resolve(hir).unwrap_or_else(|errors| {
+                let errors = self.errors(errors);
+                self.recover(errors);
+
+                hir::Hir::Error(vec![])
+            }),
+        }
+    }
+
+    fn recover(&mut self, errors: Vec<Error>) {
+        if let Some(recovered) = &mut self.recovered {
+            recovered.extend(errors);
+        }
+    }
+
+    fn hir(&mut self, hir: hir::Hir) -> StdResult<Hir> {
+        match hir {
+            hir::Hir::NoOp => Ok(Hir::NoOp),
+            hir::Hir::Expr(expr::Expr::Assign(assign)) => match *assign.rhs {
+                expr::Expr::BinOp(binop) => Ok(Hir::Expr(Expr::Assign {
+                    lno: self.lno(&assign.lno),
+                    lhs: Box::new(primitive(&assign.lhs)),
+                    rhs: Box::new(Expr::BinaryOp {
+                        lhs: Box::new(primitive(&binop.lhs)),
+                        verb: OperatorVerb::from(&binop.verb.to_string()),
+                        rhs: Box::new(primitive(&binop.rhs)),
+                    }),
+                })),
+                _ => Err(self.unexpected(None, "binary operation")),
+            },
+            hir::Hir::Expr(_) => Err(self.unexpected(None, "assignment")),
+            hir::Hir::Func(fnc::Func::BoundCall(call)) => Ok(Hir::Function(Func::Call {
+                lno: self.lno(&call.lno),
+                lhs: Box::new(primitive(&call.lhs)),
+                rhs: FuncCall {
+                    ident: Box::new(primitive(&call.rhs.ident)),
+                    args: call.rhs.args.iter().map(primitive).collect(),
+                },
+            })),
+            hir::Hir::Control(ctrl::Control::Block { terms }) => {
+                let mut block = vec![];
+
+                for term in terms {
+                    let converted = match term {
+                        // reported by the parser or the resolver already
+                        hir::Hir::Error(_) if self.recovered.is_some() => continue,
+                        hir::Hir::Unknown(mcr::Unknown::Token(token)) => self.statements(&[token]),
+                        hir::Hir::Unknown(mcr::Unknown::Tokens(tokens)) => self.statements(&tokens),
+                        term => self.hir(term).map(|hir| vec![hir]),
+                    };
+
+                    match converted {
+                        Ok(hir) => block.extend(hir),
+                        Err(errors) if self.recovered.is_some() => self.recover(errors),
+                        Err(errors) => return Err(errors),
+                    }
+                }
+
+                Ok(Hir::Control(Control::Terms(block)))
+            }
+            hir::Hir::Control(ctrl::Control::Loop { lno, ident, terms }) => {
+                Ok(Hir::Control(Control::Loop {
+                    lno: self.lno(&lno),
+                    ident: Box::new(Hir::Expr(primitive(&ident))),
+                    terms: Box::new(self.hir(*terms)?),
+                }))
+            }
+            hir::Hir::Control(ctrl::Control::While { lno, comp, terms }) => {
+                Ok(Hir::Control(Control::While {
+                    lno: self.lno(&lno),
+                    comp: Box::new(Hir::Expr(Expr::Comparison {
+                        lhs: Box::new(primitive(&comp.lhs)),
+                        verb: ComparisonVerb::from(&comp.verb.to_string()),
+                        rhs: Box::new(primitive(&comp.rhs)),
+                    })),
+                    terms: Box::new(self.hir(*terms)?),
+                }))
+            }
+            hir::Hir::Unknown(mcr::Unknown::Token(token)) => {
+                Ok(Hir::Control(Control::Terms(self.statements(&[token])?)))
+            }
+            hir::Hir::Unknown(mcr::Unknown::Tokens(tokens)) => {
+                Ok(Hir::Control(Control::Terms(self.statements(&tokens)?)))
+            }
+            hir::Hir::Error(tokens) => Err(self.unexpected(tokens.first(), "a statement")),
+        }
+    }
+
+    // the separators between unknown tokens are not kept by the parser,
+    // the statements are therefore taken one after another.
+    fn statements(&mut self, tokens: &[Token]) -> StdResult<Vec<Hir>> {
+        let mut statements = vec![];
+        let mut rest = tokens;
+
+        loop {
+            while rest.first().is_some_and(is_sep) {
+                rest = &rest[1..];
+            }
+
+            if rest.is_empty() {
+                return Ok(statements);
+            }
+
+            let (statement, consumed) = self.statement(rest)?;
+            statements.push(statement);
+            rest = &rest[consumed..];
+        }
+    }
+
+    // a single macro of the language, return the statement and the number of tokens it spans
+    fn statement(&mut self, tokens: &[Token]) -> StdResult<(Hir, usize)> {
+        let lno = |end: &Token| self.lno(&tokens[0].lno.end_at(&end.lno));
+
+        match (recognize(tokens), tokens) {
+            (Some(Builtin::Conditional), _) => self.conditional(tokens),
+            (Some(Builtin::Call), [lhs, _, func, ..]) => {
+                let mut args = vec![];
+                let mut index = 4;
+
+                loop {
+                    match tokens.get(index) {
+                        Some(arg) if is_atom(arg) => args.push(atom(arg)),
+                        token => return Err(self.unexpected(token, "identifier or value")),
+                    }
+
+                    match tokens.get(index + 1) {
+                        Some(token) if matches!(token.kind, Kind::Comma) => index += 2,
+                        Some(token) if matches!(token.kind, Kind::Paren(lexer::Pair::Right)) => {
+                            break
+                        }
+                        token => return Err(self.unexpected(token, "`,` or `)`")),
+                    }
+                }
+
+                let end = &tokens[index + 1];
+                let hir = Hir::Function(Func::Call {
+                    lno: lno(end),
+                    lhs: Box::new(atom(lhs)),
+                    rhs: FuncCall {
+                        ident: Box::new(ident(&func.content)),
+                        args,
+                    },
+                });
+
+                Ok((hir, index + 2))
+            }
+            (Some(Builtin::BinaryOp), [lhs, _, a, op, b, ..]) => {
+                let rhs = MacroAssign {
+                    lhs: Box::new(atom(a)),
+                    verb: OperatorVerb::from(&op.content),
+                    rhs: Box::new(atom(b)),
+                };
+
+                let hir = match (&op.kind, &b.kind) {
+                    (Kind::Op(lexer::Op::Star), Kind::Number(_)) => {
+                        Macro::AssignToIdentExtBinOpValue {
+                            lno: lno(b),
+                            lhs: Box::new(atom(lhs)),
+                            rhs,
+                        }
+                    }
+                    _ => Macro::AssignToIdentBinOpIdent {
+                        lno: lno(b),
+                        lhs: Box::new(atom(lhs)),
+                        rhs,
+                    },
+                };
+
+                Ok((Hir::Macro(hir), 5))
+            }
+            (Some(Builtin::Assign), [lhs, _, rhs, ..]) => {
+                let hir = match &rhs.kind {
+                    Kind::Ident(_) => Macro::AssignToIdent {
+                        lno: lno(rhs),
+                        lhs: Box::new(atom(lhs)),
+                        rhs: Box::new(atom(rhs)),
+                    },
+                    _ if rhs.content == "0" => Macro::AssignToZero {
+                        lno: lno(rhs),
+                        lhs: Box::new(atom(lhs)),
+                    },
+                    _ => Macro::AssignToValue {
+                        lno: lno(rhs),
+                        lhs: Box::new(atom(lhs)),
+                        rhs: Box::new(atom(rhs)),
+                    },
+                };
+
+                Ok((Hir::Macro(hir), 3))
+            }
+            _ => Err(self.unexpected(tokens.first(), "statement")),
+        }
+    }
+
+    // the terms of a branch, which can contain macros as well
+    fn terms(&mut self, tokens: &[Token]) -> StdResult<Hir> {
+        let hir = combine_parser::parse_terms(tokens.to_vec(), self.openers.clone())
+            .map_err(|errors| self.errors(errors))?;
+
+        self.resolve(hir)
+    }
+
+    // IF <atom> <comp> <atom> THEN <terms> [ELSE <terms>] END
+    fn conditional(&mut self, tokens: &[Token]) -> StdResult<(Hir, usize)> {
+        let comp = match tokens {
+            [_, lhs, verb, rhs, then, ..]
+                if is_atom(lhs)
+                    && (matches!(verb.kind, Kind::Comp(_)) || verb.content == "=")
+                    && is_atom(rhs)
+                    && is_word(then, "then") =>
+            {
+                Expr::Comparison {
+                    lhs: Box::new(atom(lhs)),
+                    verb: ComparisonVerb::from(&verb.content),
+                    rhs: Box::new(atom(rhs)),
+                }
+            }
+            _ => return Err(self.unexpected(tokens.get(1), "comparison")),
+        };
+
+        let (otherwise, end) = match branches(tokens) {
+            (otherwise, Some(end)) => (otherwise, end),
+            (_, None) => return Err(self.unexpected(None, "`END`")),
+        };
+
+        let if_terms = self.terms(&tokens[5..otherwise.unwrap_or(end)])?;
+        let else_terms = match otherwise {
+            Some(otherwise) => Some(self.terms(&tokens[otherwise + 1..end])?),
+            None => None,
+        };
+
+        let hir = Hir::Macro(Macro::Conditional {
+            lno: self.lno(&tokens[0].lno.end_at(&tokens[end].lno)),
+            comp: Box::new(comp),
+            if_terms: Box::new(if_terms),
+            else_terms: Box::new(else_terms),
+        });
+
+        Ok((hir, end + 1))
+    }
+}
+
+// the conditional is the only construct of the grammar spanning multiple lines which is not
+// started by a keyword
+pub(crate) fn openers() -> Openers {
+    Openers::default().with(Kind::Ident("IF".to_string()), Delimiter::End, true)
+}
+
+// every module macros can be imported from: the standard library and the files next to the
+// main file, which are prefixed with fs.
+pub(crate) fn directory(fs: Option<&func::fs::Directory>) -> Directory {
+    let mut directory = Directory::new();
+    for (path, contents) in stdlib().walk() {
+        let path: Vec<_> = path.iter().map(String::as_str).collect();
+        directory.insert(&path, &contents);
+    }
+    for (path, contents) in fs.map(func::fs::Directory::walk).into_iter().flatten() {
+        let path: Vec<_> = ["fs"]
+            .iter()
+            .copied()
+            .chain(path.iter().map(String::as_str))
+            .collect();
+        directory.insert(&path, &contents);
+    }
+
+    directory
+}
+
+// the resolver of the macros declared in and imported into the header of a file
+pub(crate) fn resolver(
+    header: &module::Module,
+    fs: Option<&func::fs::Directory>,
+) -> Result<Resolver, variants::Errors> {
+    let scopes = Scopes::load(header, &directory(fs))?;
+
+    Ok(Resolver::new(scopes).with_builtin(builtin))
+}
+
+// parse the source with the combine parser and convert it into the module of the pest grammar,
+// macros are imported from the standard library and fs.
+pub(crate) fn parse(
+    source: &str,
+    lno: Option<LineNo>,
+    fs: Option<&func::fs::Directory>,
+) -> StdResult<Module> {
+    let mut converter = converter(source, lno, fs)?;
+
+    convert(&mut converter, source)
+}
+
+// the converter of the source, with every macro declared in and imported into its header
+fn converter<'a>(
+    source: &'a str,
+    lno: Option<LineNo>,
+    fs: Option<&func::fs::Directory>,
+) -> StdResult<Converter<'a>> {
+    let mut converter = Converter::new(source, lno);
+
+    // the openers of every visible macro are needed to delimit their invocations,
+    // an invalid header is reported while parsing the whole file.
+    if let Ok((header, _)) = combine_parser::parse_header(source) {
+        converter.resolver = resolver(&header, fs).map_err(|errors| converter.errors(errors))?;
+        converter.openers = openers().merge(&converter.resolver.openers());
+    }
+
+    Ok(converter)
+}
+
+fn convert(converter: &mut Converter, source: &str) -> StdResult<Module> {
+    // every syntax error of the file is reported at once
+    let (module, errors) = combine_parser::parse_partial(source, converter.openers.clone());
+    if !errors.is_empty() {
+        return Err(converter.errors(errors));
+    }
+
+    converter.module(module)
+}
+
+// the trace of the macros tried for the terms of the source, the errors of the resolution are
+// located in the source like the ones of parse.
+#[cfg(feature = "cli")]
+pub(crate) fn trace(source: &str, fs: Option<&func::fs::Directory>) -> (Trace, StdResult<()>) {
+    let converter = Converter::new(source, None);
+
+    let mut resolver = None;
+    let result = combine_parser::parse_header(source).and_then(|(module, tokens)| {
+        let resolver = resolver.insert(self::resolver(&module, fs)?.with_trace());
+
+        let openers = openers().merge(&resolver.openers());
+        let hir = combine_parser::parse_terms(tokens, openers)?;
+        resolver.resolve(hir).map(|_| ())
+    });
+
+    let trace = resolver
+        .and_then(|resolver| resolver.trace().cloned())
+        .unwrap_or_default();
+
+    (trace, result.map_err(|errors| converter.errors(errors)))
+}
+
+// parse the source like parse, but keep the module recovered from the errors. Broken statements
+// and the terms which cannot be resolved are left out of the module, editors analyse the rest.
+#[cfg(feature = "cli")]
+pub(crate) fn parse_recovering(
+    source: &str,
+    fs: Option<&func::fs::Directory>,
+) -> (Module, Vec<Error>) {
+    let (mut converter, mut errors) = match converter(source, None, fs) {
+        Ok(converter) => (converter, vec![]),
+        // the statements are still converted, only the macros of the header are missing
+        Err(errors) => (Converter::new(source, None), errors),
+    };
+
+    let (module, parsed) = combine_parser::parse_partial(source, converter.openers.clone());
+    errors.extend(converter.errors(parsed));
+
+    converter.recovered = Some(vec![]);
+    let module = converter.module(module);
+    errors.extend(converter.recovered.take().unwrap_or_default());
+
+    match module {
+        Ok(module) => (module, errors),
+        Err(rest) => {
+            let module = Module {
+                imp: vec![],
+                imp_mod: vec![],
+                consts: vec![],
+                decl: vec![],
+                code: Hir::NoOp,
+            };
+
+            (module, errors.into_iter().chain(rest).collect())
+        }
+    }
+}
+
+// only valid sources are formatted, every statement needs to be one of the language or be matched
+// by a macro. The combine parser alone accepts any statement as the invocation of a macro.
+pub(crate) fn fmt(source: &str, fs: Option<&func::fs::Directory>) -> StdResult<String> {
+    let mut converter = converter(source, None, fs)?;
+    convert(&mut converter, source)?;
+
+    combine_parser::fmt(source, converter.openers.clone())
+        .map_err(|errors| converter.errors(errors))
+}
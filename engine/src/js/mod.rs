@@ -3,12 +3,17 @@ use wasm_bindgen::{JsCast, JsValue, UnwrapThrowExt};
 
 use crate::ast::expr::Expr;
 use crate::build::Builder;
+use crate::diagnostic::Diagnostic;
 use crate::eval::exec::Exec;
+use crate::explain::explain;
 use crate::flags::CompileFlags;
+use crate::frontend::Frontend;
+use crate::lint::{lint, Lints, Warning};
 use crate::runtime::Runtime;
 use crate::utils::set_panic_hook;
 
 use crate::ast::hir::func::fs::Directory;
+use crate::ast::hir::func::module::session::Session;
 use crate::ast::module::Module;
 use crate::errors::Error;
 use js_sys::Map;
@@ -18,10 +23,17 @@ use std::collections::HashMap;
 
 #[wasm_bindgen(typescript_custom_section)]
 const TS_APPEND_CONTENT: &'static str = r#"
-import {Expr, Hir, Exec, Module, Path, ExecutionResult} from "./schema";
+import {Expr, Hir, Exec, Module, Path, ExecutionResult, Diagnostic, Warning} from "./schema";
 
 // sadly we need to do this one manually
 type Directory = { [key: string]: Path };
+type Explanation = {
+  id: string;
+  title: string;
+  description: string;
+  example: string | null;
+  fix: string | null;
+};
 "#;
 
 #[wasm_bindgen(start)]
@@ -55,9 +67,18 @@ extern "C" {
     #[wasm_bindgen(typescript_type = "Error[]")]
     pub type IErrors;
 
+    #[wasm_bindgen(typescript_type = "Diagnostic[]")]
+    pub type IDiagnostics;
+
+    #[wasm_bindgen(typescript_type = "Warning[]")]
+    pub type IWarnings;
+
     #[wasm_bindgen(typescript_type = "Directory")]
     pub type IDirectory;
 
+    #[wasm_bindgen(typescript_type = "Explanation | undefined")]
+    pub type IExplanation;
+
     #[wasm_bindgen(typescript_type = "ExecutionResult")]
     pub type IExecutionResult;
 }
@@ -67,6 +88,24 @@ extern "C" {
     fn convertVariables(variables: IVariables) -> IVariablesBigInt;
 }
 
+fn convert_lints(lints: JsValue) -> Lints {
+    lints
+        .as_f64()
+        .and_then(|lints| Lints::from_bits(lints as u16))
+        .unwrap_or_default()
+}
+
+fn convert_flags(flags: JsValue) -> Option<CompileFlags> {
+    if flags.is_undefined() {
+        None
+    } else {
+        flags
+            .as_f64()
+            .map(|flags| CompileFlags::from_bits(flags as u16))
+    }
+    .flatten()
+}
+
 #[wasm_bindgen(js_name = Runtime)]
 #[derive(Serialize, Deserialize)]
 pub struct JavaScriptRuntime {
@@ -150,31 +189,35 @@ pub struct JavaScriptBuilder {
 
 #[wasm_bindgen(js_class = Builder)]
 impl JavaScriptBuilder {
-    pub fn parse(source: &str) -> Result<IModule, JsValue> {
-        Builder::parse(source, None)
+    // combine parses with the combine parser instead of the pest grammar
+    pub fn parse(source: &str, combine: Option<bool>) -> Result<IModule, JsValue> {
+        let frontend = if combine.unwrap_or(false) {
+            Frontend::Combine
+        } else {
+            Frontend::Pest
+        };
+
+        Builder::parse_with(source, None, frontend)
             .map(|val| JsValue::from_serde(&val).unwrap().unchecked_into())
-            .map_err(Error::new_from_parse)
-            .map_err(|err| vec![err])
             .map_err(|err| JsValue::from_serde(&err).unwrap())
     }
 
+    pub fn fmt(source: &str) -> Result<String, JsValue> {
+        Builder::fmt(source, None).map_err(|err| JsValue::from_serde(&err).unwrap())
+    }
+
     pub fn compile(
         module: &IModule,
         flags: JsValue,
         fs: Option<IDirectory>,
+        lib: Option<IDirectory>,
     ) -> Result<IExpr, JsValue> {
         let mut module: Module = module.into_serde().unwrap();
         let fs: Option<Directory> = fs.map(|fs| fs.into_serde().unwrap());
-        let flags = if flags.is_undefined() {
-            None
-        } else {
-            flags
-                .as_f64()
-                .map(|flags| CompileFlags::from_bits(flags as u16))
-        }
-        .flatten();
+        let lib: Option<Directory> = lib.map(|lib| lib.into_serde().unwrap());
+        let flags = convert_flags(flags);
 
-        let result = Builder::compile(&mut module, flags, fs)
+        let result = Builder::compile_with_lib(&mut module, flags, fs, lib)
             .map_err(|err| JsValue::from_serde(&err).unwrap())?;
 
         Ok(JsValue::from_serde(&result).unwrap().unchecked_into())
@@ -194,6 +237,73 @@ impl JavaScriptBuilder {
         Ok(JsValue::from_serde(&exec).unwrap().unchecked_into())
     }
 
+    // resolves the errors of parse or compile against the source of the file
+    pub fn diagnostics(
+        errors: IErrors,
+        source: &str,
+        file: Option<String>,
+    ) -> Result<IDiagnostics, JsValue> {
+        let errors: Vec<Error> = errors
+            .into_serde()
+            .map_err(|err| JsValue::from_str(format!("{}", err).as_str()))?;
+
+        let diagnostics: Vec<_> = errors
+            .iter()
+            .map(|error| Diagnostic::new(error, file.as_deref(), source))
+            .collect();
+
+        Ok(JsValue::from_serde(&diagnostics).unwrap().unchecked_into())
+    }
+
+    // the diagnostics rendered as plain text, like on the command line
+    pub fn report(errors: IErrors, source: &str, file: Option<String>) -> Result<String, JsValue> {
+        let errors: Vec<Error> = errors
+            .into_serde()
+            .map_err(|err| JsValue::from_str(format!("{}", err).as_str()))?;
+
+        Ok(errors
+            .iter()
+            .map(|error| Diagnostic::new(error, file.as_deref(), source).render(source))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+
+    // the warnings of the enabled lints, every lint is enabled if lints is undefined
+    pub fn lint(module: &IModule, lints: JsValue) -> Result<IWarnings, JsValue> {
+        let module: Module = module
+            .into_serde()
+            .map_err(|err| JsValue::from_str(format!("{}", err).as_str()))?;
+
+        let warnings = lint(&module, convert_lints(lints));
+
+        Ok(JsValue::from_serde(&warnings).unwrap().unchecked_into())
+    }
+
+    // resolves the warnings of lint against the source of the file, like diagnostics
+    pub fn warnings(
+        warnings: IWarnings,
+        source: &str,
+        file: Option<String>,
+    ) -> Result<IDiagnostics, JsValue> {
+        let warnings: Vec<Warning> = warnings
+            .into_serde()
+            .map_err(|err| JsValue::from_str(format!("{}", err).as_str()))?;
+
+        let diagnostics: Vec<_> = warnings
+            .iter()
+            .map(|warning| Diagnostic::from_warning(warning, file.as_deref(), source))
+            .collect();
+
+        Ok(JsValue::from_serde(&diagnostics).unwrap().unchecked_into())
+    }
+
+    // the explanation of an error code, like the code of a diagnostic
+    pub fn explain(code: &str) -> IExplanation {
+        explain(code)
+            .map(|explanation| JsValue::from_serde(explanation).unwrap().unchecked_into())
+            .unwrap_or_else(|| JsValue::UNDEFINED.unchecked_into())
+    }
+
     pub fn display(expr: &IExpr, indent: u8) -> Result<String, JsValue> {
         let expr: Expr = expr
             .into_serde()
@@ -201,4 +311,49 @@ impl JavaScriptBuilder {
 
         Ok(expr.display(indent, None))
     }
+
+    // the compiled program with the macro expansions reverted, as source
+    pub fn decompile(expr: &IExpr, indent: u8) -> Result<String, JsValue> {
+        let expr: Expr = expr
+            .into_serde()
+            .map_err(|err| JsValue::from_str(format!("{}", err).as_str()))?;
+
+        Ok(Builder::decompile(&expr).display(indent, None))
+    }
+}
+
+// Session keeps everything between compiles that is still valid,
+// use this instead of Builder.compile() when recompiling on every change.
+#[wasm_bindgen(js_name = Session)]
+pub struct JavaScriptSession {
+    session: Session,
+}
+
+#[wasm_bindgen(js_class = Session)]
+impl JavaScriptSession {
+    #[wasm_bindgen(constructor)]
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> JavaScriptSession {
+        JavaScriptSession {
+            session: Session::new(),
+        }
+    }
+
+    pub fn compile(
+        &mut self,
+        module: &IModule,
+        flags: JsValue,
+        fs: Option<IDirectory>,
+        lib: Option<IDirectory>,
+    ) -> Result<IExpr, JsValue> {
+        let mut module: Module = module.into_serde().unwrap();
+        let fs: Option<Directory> = fs.map(|fs| fs.into_serde().unwrap());
+        let lib: Option<Directory> = lib.map(|lib| lib.into_serde().unwrap());
+        let flags = convert_flags(flags);
+
+        let result = Builder::compile_in_session(&mut module, flags, fs, lib, &mut self.session)
+            .map_err(|err| JsValue::from_serde(&err).unwrap())?;
+
+        Ok(JsValue::from_serde(&result).unwrap().unchecked_into())
+    }
 }
@@ -16,10 +16,14 @@ extern crate sum_type;
 
 mod ast;
 mod build;
+mod diagnostic;
 mod errors;
 mod eval;
+mod explain;
 mod flags;
+mod frontend;
 mod js;
+mod lint;
 mod parser;
 mod runtime;
 mod types;
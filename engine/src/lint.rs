@@ -0,0 +1,668 @@
+// Lints report programs which compile, but most likely do not do what they are meant to do.
+// They run on the module before it is lowered, so that every warning points to the
+// statement in the source, not to its expansion.
+
+use crate::ast::control::Control;
+use crate::ast::expr::Expr;
+use crate::ast::hir::func::decl::FuncDecl;
+use crate::ast::hir::func::Func;
+use crate::ast::hir::macros::Macro;
+use crate::ast::hir::Hir;
+use crate::ast::module::Module;
+use crate::ast::variant::UInt;
+use crate::ast::verbs::ComparisonVerb;
+use crate::types::LineNo;
+use either::Either;
+use num_traits::Zero;
+#[cfg(feature = "cli")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+bitflags! {
+    #[wasm_bindgen]
+    pub struct Lints: u16 {
+        // variables read before any assignment, they are 0 unless given as input
+        const UNINITIALIZED      = 0b0000_0001;
+        // variables of functions assigned but never read
+        const UNUSED_VARIABLES   = 0b0000_0010;
+        // LOOP x bodies assigning to x, which does not change the number of iterations
+        const LOOP_COUNTER       = 0b0000_0100;
+        // IF conditions which are always true or always false
+        const CONSTANT_CONDITION = 0b0000_1000;
+        const UNUSED_IMPORTS     = 0b0001_0000;
+        const UNUSED_FUNCTIONS   = 0b0010_0000;
+    }
+}
+
+impl Default for Lints {
+    fn default() -> Self {
+        Lints::all()
+    }
+}
+
+// the names of the lints on the command line
+pub const NAMES: [(&str, Lints); 6] = [
+    ("uninitialized", Lints::UNINITIALIZED),
+    ("unused_variables", Lints::UNUSED_VARIABLES),
+    ("loop_counter", Lints::LOOP_COUNTER),
+    ("constant_condition", Lints::CONSTANT_CONDITION),
+    ("unused_imports", Lints::UNUSED_IMPORTS),
+    ("unused_functions", Lints::UNUSED_FUNCTIONS),
+];
+
+// lints are separated by commas or |, like unused_imports,unused_functions
+#[cfg(feature = "cli")]
+impl std::str::FromStr for Lints {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lints = Lints::empty();
+
+        for name in s
+            .split([',', '|'])
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            match NAMES
+                .iter()
+                .find(|(lint, _)| lint.eq_ignore_ascii_case(name))
+            {
+                Some((_, lint)) => lints |= *lint,
+                None => {
+                    let names: Vec<_> = NAMES.iter().map(|(lint, _)| *lint).collect();
+
+                    return Err(format!(
+                        "unknown lint {}, expected one of {}",
+                        name,
+                        names.join(", ")
+                    ));
+                }
+            }
+        }
+
+        Ok(lints)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "cli", derive(JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct Warning {
+    pub lno: LineNo,
+    // the name of the lint, like unused_imports
+    pub lint: String,
+    pub message: String,
+}
+
+impl Warning {
+    fn new(lno: LineNo, lint: Lints, message: String) -> Self {
+        let lint = NAMES
+            .iter()
+            .find(|(_, flag)| *flag == lint)
+            .map(|(name, _)| name.to_string())
+            .unwrap_or_default();
+
+        Warning { lno, lint, message }
+    }
+}
+
+// the first row of a statement, for warnings about the head of a block
+fn head(lno: LineNo) -> LineNo {
+    LineNo::rows(lno.row.0, lno.row.0)
+}
+
+fn ident(expr: &Expr) -> Option<&str> {
+    match expr {
+        Expr::Ident(ident) => Some(ident),
+        _ => None,
+    }
+}
+
+// Every identifier used in the node, including the names of called functions.
+fn idents(hir: &Hir) -> HashSet<String> {
+    match hir {
+        Hir::Expr(expr) => expr.idents(),
+        Hir::Macro(Macro::AssignToIdent { lhs, rhs, .. })
+        | Hir::Macro(Macro::AssignToValue { lhs, rhs, .. }) => {
+            lhs.idents().union(&rhs.idents()).cloned().collect()
+        }
+        Hir::Macro(Macro::AssignToZero { lhs, .. }) => lhs.idents(),
+        Hir::Macro(Macro::AssignToIdentBinOpIdent { lhs, rhs, .. })
+        | Hir::Macro(Macro::AssignToIdentExtBinOpValue { lhs, rhs, .. }) => lhs
+            .idents()
+            .into_iter()
+            .chain(rhs.lhs.idents())
+            .chain(rhs.rhs.idents())
+            .collect(),
+        Hir::Macro(Macro::Conditional {
+            comp,
+            if_terms,
+            else_terms,
+            ..
+        }) => comp
+            .idents()
+            .into_iter()
+            .chain(idents(if_terms))
+            .chain(else_terms.iter().flat_map(idents))
+            .collect(),
+        Hir::Function(Func::Call { lhs, rhs, .. }) => lhs
+            .idents()
+            .into_iter()
+            .chain(rhs.ident.idents())
+            .chain(rhs.args.iter().flat_map(Expr::idents))
+            .collect(),
+        Hir::NoOp => HashSet::new(),
+        Hir::Control(Control::Terms(terms)) => terms.iter().flat_map(idents).collect(),
+        Hir::Control(Control::Loop { ident, terms, .. }) => {
+            idents(ident).union(&idents(terms)).cloned().collect()
+        }
+        Hir::Control(Control::While { comp, terms, .. }) => {
+            idents(comp).union(&idents(terms)).cloned().collect()
+        }
+    }
+}
+
+// the value of comparisons which do not depend on the value of their variables
+fn constant(comp: &Expr) -> Option<bool> {
+    let (lhs, verb, rhs) = match comp {
+        Expr::Comparison { lhs, verb, rhs } => (&**lhs, verb, &**rhs),
+        _ => return None,
+    };
+
+    match (lhs, rhs) {
+        (Expr::NaturalNumber(UInt(lhs)), Expr::NaturalNumber(UInt(rhs))) => Some(match verb {
+            ComparisonVerb::Equal => lhs == rhs,
+            ComparisonVerb::NotEqual => lhs != rhs,
+            ComparisonVerb::GreaterThan => lhs > rhs,
+            ComparisonVerb::GreaterThanEqual => lhs >= rhs,
+            ComparisonVerb::LessThan => lhs < rhs,
+            ComparisonVerb::LessThanEqual => lhs <= rhs,
+        }),
+        (Expr::Ident(lhs), Expr::Ident(rhs)) if lhs == rhs => Some(matches!(
+            verb,
+            ComparisonVerb::Equal
+                | ComparisonVerb::GreaterThanEqual
+                | ComparisonVerb::LessThanEqual
+        )),
+        // variables are natural numbers, they are never smaller than 0
+        (Expr::Ident(_), Expr::NaturalNumber(UInt(zero))) if zero.is_zero() => match verb {
+            ComparisonVerb::GreaterThanEqual => Some(true),
+            ComparisonVerb::LessThan => Some(false),
+            _ => None,
+        },
+        (Expr::NaturalNumber(UInt(zero)), Expr::Ident(_)) if zero.is_zero() => match verb {
+            ComparisonVerb::LessThanEqual => Some(true),
+            ComparisonVerb::GreaterThan => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// The variables of the main code or of a function body, visited in the order of the source.
+#[derive(Default)]
+struct Scope {
+    assigned: HashSet<String>,
+    read: HashSet<String>,
+    // the first assignment of every variable, in order of the source
+    written: Vec<(String, LineNo)>,
+    // the variables of the enclosing LOOPs
+    counters: Vec<String>,
+    // variables reported as uninitialized, they are only reported once
+    reported: HashSet<String>,
+}
+
+struct Linter {
+    lints: Lints,
+    // constants are never uninitialized
+    consts: HashSet<String>,
+    warnings: Vec<Warning>,
+}
+
+impl Linter {
+    fn warn(&mut self, lint: Lints, lno: LineNo, message: String) {
+        if self.lints.contains(lint) {
+            self.warnings.push(Warning::new(lno, lint, message));
+        }
+    }
+
+    fn read(&mut self, scope: &mut Scope, lno: LineNo, idents: HashSet<String>) {
+        let mut idents: Vec<_> = idents.into_iter().collect();
+        idents.sort();
+
+        for ident in idents {
+            if !scope.assigned.contains(&ident)
+                && !self.consts.contains(&ident)
+                && scope.reported.insert(ident.clone())
+            {
+                self.warn(
+                    Lints::UNINITIALIZED,
+                    lno,
+                    format!(
+                        "{} is read before it is assigned, it is 0 unless it is given as input",
+                        ident
+                    ),
+                );
+            }
+
+            scope.read.insert(ident);
+        }
+    }
+
+    fn write(&mut self, scope: &mut Scope, lno: LineNo, lhs: &Expr) {
+        let ident = match ident(lhs) {
+            Some(ident) => ident.to_string(),
+            None => return,
+        };
+
+        if scope.counters.contains(&ident) {
+            self.warn(
+                Lints::LOOP_COUNTER,
+                lno,
+                format!(
+                    "{} is modified in the body of LOOP {}, the number of iterations is fixed when the loop starts",
+                    ident, ident
+                ),
+            );
+        }
+
+        if scope.assigned.insert(ident.clone()) {
+            scope.written.push((ident, lno));
+        }
+    }
+
+    fn expr(&mut self, scope: &mut Scope, expr: &Expr) {
+        match expr {
+            Expr::Assign { lno, lhs, rhs } => {
+                self.read(scope, *lno, rhs.idents());
+                self.write(scope, *lno, lhs);
+            }
+            Expr::Control(Control::Terms(terms)) => {
+                terms.iter().for_each(|term| self.expr(scope, term));
+            }
+            Expr::Control(Control::Loop {
+                lno,
+                ident: counter,
+                terms,
+            }) => {
+                self.read(scope, head(*lno), counter.idents());
+                self.counter(scope, ident(counter), |linter, scope| {
+                    linter.expr(scope, terms)
+                });
+            }
+            Expr::Control(Control::While { lno, comp, terms }) => {
+                self.read(scope, head(*lno), comp.idents());
+                self.expr(scope, terms);
+            }
+            _ => {}
+        }
+    }
+
+    fn counter(
+        &mut self,
+        scope: &mut Scope,
+        counter: Option<&str>,
+        body: impl FnOnce(&mut Self, &mut Scope),
+    ) {
+        scope.counters.extend(counter.map(str::to_string));
+        body(self, scope);
+        if counter.is_some() {
+            scope.counters.pop();
+        }
+    }
+
+    fn hir(&mut self, scope: &mut Scope, hir: &Hir) {
+        match hir {
+            Hir::Expr(expr) => self.expr(scope, expr),
+            Hir::Macro(Macro::AssignToIdent { lno, lhs, rhs })
+            | Hir::Macro(Macro::AssignToValue { lno, lhs, rhs }) => {
+                self.read(scope, *lno, rhs.idents());
+                self.write(scope, *lno, lhs);
+            }
+            Hir::Macro(Macro::AssignToZero { lno, lhs }) => self.write(scope, *lno, lhs),
+            Hir::Macro(Macro::AssignToIdentBinOpIdent { lno, lhs, rhs })
+            | Hir::Macro(Macro::AssignToIdentExtBinOpValue { lno, lhs, rhs }) => {
+                let idents = rhs.lhs.idents().union(&rhs.rhs.idents()).cloned().collect();
+                self.read(scope, *lno, idents);
+                self.write(scope, *lno, lhs);
+            }
+            Hir::Macro(Macro::Conditional {
+                lno,
+                comp,
+                if_terms,
+                else_terms,
+            }) => {
+                self.read(scope, head(*lno), comp.idents());
+                self.condition(*lno, comp, else_terms.is_some());
+
+                self.hir(scope, if_terms);
+                if let Some(else_terms) = &**else_terms {
+                    self.hir(scope, else_terms);
+                }
+            }
+            Hir::Function(Func::Call { lno, lhs, rhs }) => {
+                let idents = rhs.args.iter().flat_map(Expr::idents).collect();
+                self.read(scope, *lno, idents);
+                self.write(scope, *lno, lhs);
+            }
+            Hir::NoOp => {}
+            Hir::Control(Control::Terms(terms)) => {
+                terms.iter().for_each(|term| self.hir(scope, term));
+            }
+            Hir::Control(Control::Loop { lno, ident, terms }) => {
+                let counter = match &**ident {
+                    Hir::Expr(expr) => self::ident(expr).map(str::to_string),
+                    _ => None,
+                };
+
+                self.read(scope, head(*lno), idents(ident));
+                self.counter(scope, counter.as_deref(), |linter, scope| {
+                    linter.hir(scope, terms)
+                });
+            }
+            Hir::Control(Control::While { lno, comp, terms }) => {
+                self.read(scope, head(*lno), idents(comp));
+                self.hir(scope, terms);
+            }
+        }
+    }
+
+    fn condition(&mut self, lno: LineNo, comp: &Expr, has_else: bool) {
+        let message = match (constant(comp), has_else) {
+            (Some(true), true) => "the condition is always true, the ELSE branch is never executed",
+            (Some(true), false) => "the condition is always true",
+            (Some(false), _) => "the condition is always false, the THEN branch is never executed",
+            (None, _) => return,
+        };
+
+        self.warn(Lints::CONSTANT_CONDITION, head(lno), message.to_string());
+    }
+
+    fn function(&mut self, decl: &FuncDecl) {
+        let mut scope = Scope::default();
+        scope
+            .assigned
+            .extend(decl.params.iter().filter_map(ident).map(str::to_string));
+
+        self.hir(&mut scope, &decl.terms);
+
+        let ret = ident(&decl.ret);
+        for (variable, lno) in &scope.written {
+            if !scope.read.contains(variable) && Some(variable.as_str()) != ret {
+                self.warn(
+                    Lints::UNUSED_VARIABLES,
+                    *lno,
+                    format!("{} is assigned but never read", variable),
+                );
+            }
+        }
+    }
+
+    fn unused(&mut self, module: &Module) {
+        let code = idents(&module.code);
+        let bodies: Vec<_> = module.decl.iter().map(|decl| idents(&decl.terms)).collect();
+        let used: HashSet<_> = code.iter().chain(bodies.iter().flatten()).collect();
+
+        for imp in module.imp.iter().filter(|imp| !imp.public) {
+            let funcs = match &imp.funcs {
+                Either::Left(funcs) => funcs,
+                // we cannot know which names a wildcard import provides without resolving it
+                Either::Right(_) => continue,
+            };
+
+            for func in funcs {
+                let name = ident(func.alias.as_ref().unwrap_or(&func.ident)).unwrap_or_default();
+
+                if !used.contains(&name.to_string()) {
+                    self.warn(
+                        Lints::UNUSED_IMPORTS,
+                        imp.lno,
+                        format!("{} is imported but never used", name),
+                    );
+                }
+            }
+        }
+
+        for imp in &module.imp_mod {
+            let namespace = format!("{}::", imp.namespace());
+
+            if !used.iter().any(|name| name.starts_with(&namespace)) {
+                self.warn(
+                    Lints::UNUSED_IMPORTS,
+                    imp.lno,
+                    format!("{} is imported but never used", imp.namespace()),
+                );
+            }
+        }
+
+        // functions calling themselves are not used by that
+        for (index, decl) in module.decl.iter().enumerate() {
+            let name = match ident(&decl.ident) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            let called = code.contains(&name)
+                || bodies
+                    .iter()
+                    .enumerate()
+                    .any(|(other, body)| other != index && body.contains(&name));
+
+            if !decl.public && !called {
+                self.warn(
+                    Lints::UNUSED_FUNCTIONS,
+                    head(decl.lno),
+                    format!("{} is declared but never called", name),
+                );
+            }
+        }
+    }
+}
+
+// Lints a parsed module, the warnings are sorted by their location.
+pub fn lint(module: &Module, lints: Lints) -> Vec<Warning> {
+    let mut linter = Linter {
+        lints,
+        consts: module
+            .consts
+            .iter()
+            .filter_map(|decl| ident(&decl.ident))
+            .map(str::to_string)
+            .collect(),
+        warnings: vec![],
+    };
+
+    module.decl.iter().for_each(|decl| linter.function(decl));
+    // the main code has no unused variables, all of them are the result of the program
+    linter.hir(&mut Scope::default(), &module.code);
+    linter.unused(module);
+
+    let mut warnings = linter.warnings;
+    warnings.sort_by_key(|warning| warning.lno);
+    warnings
+}
+
+//region Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::Builder;
+    use indoc::indoc;
+
+    fn warnings(source: &str, lints: Lints) -> Vec<(usize, String)> {
+        let module = Builder::parse(source, None).expect("expected the source to parse");
+
+        lint(&module, lints)
+            .into_iter()
+            .map(|warning| (warning.lno.row.0, warning.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_lint_uninitialized() {
+        let source = indoc! {"
+            CONST LIMIT = 3
+
+            y := x + 1
+            LOOP y DO
+                z := z + LIMIT
+            END
+            x := y + 0
+            w := x + 0
+        "};
+
+        assert_eq!(
+            warnings(source, Lints::UNINITIALIZED),
+            vec![
+                (
+                    3,
+                    "x is read before it is assigned, it is 0 unless it is given as input"
+                        .to_string()
+                ),
+                (
+                    5,
+                    "z is read before it is assigned, it is 0 unless it is given as input"
+                        .to_string()
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_unused_variables() {
+        let source = indoc! {"
+            FN add(a, b) -> c DECL
+                tmp := a + 1
+                unused := tmp + 2
+                c := a + b
+            END
+
+            x := add(1, 2)
+            y := x + 1
+        "};
+
+        assert_eq!(
+            warnings(source, Lints::UNUSED_VARIABLES),
+            vec![(3, "unused is assigned but never read".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_lint_loop_counter() {
+        let source = indoc! {"
+            x := 3
+            LOOP x DO
+                y := y + 1
+                LOOP y DO
+                    x := x - 1
+                END
+            END
+        "};
+
+        let lints = warnings(source, Lints::LOOP_COUNTER);
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].0, 5);
+        assert!(lints[0]
+            .1
+            .starts_with("x is modified in the body of LOOP x"));
+    }
+
+    #[test]
+    fn test_lint_constant_condition() {
+        let source = indoc! {"
+            IF x >= 0 THEN
+                y := 1
+            ELSE
+                y := 2
+            END
+            IF 3 < 2 THEN
+                y := 3
+            END
+            IF x == x THEN
+                y := 4
+            END
+            IF x > 2 THEN
+                y := 5
+            END
+        "};
+
+        assert_eq!(
+            warnings(source, Lints::CONSTANT_CONDITION),
+            vec![
+                (
+                    1,
+                    "the condition is always true, the ELSE branch is never executed".to_string()
+                ),
+                (
+                    6,
+                    "the condition is always false, the THEN branch is never executed".to_string()
+                ),
+                (9, "the condition is always true".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_unused() {
+        let source = indoc! {"
+            FROM std::prelude IMPORT max
+            FROM std::prelude IMPORT min AS smaller
+            IMPORT std::math AS m
+
+            FN double(a) -> b DECL
+                b := a + a
+            END
+
+            FN triple(a) -> b DECL
+                b := double(a)
+                b := b + a
+            END
+
+            PUB FN half(a) -> b DECL
+                b := a + 0
+            END
+
+            x := smaller(1, 2)
+        "};
+
+        assert_eq!(
+            warnings(source, Lints::UNUSED_IMPORTS | Lints::UNUSED_FUNCTIONS),
+            vec![
+                (1, "max is imported but never used".to_string()),
+                (3, "m is imported but never used".to_string()),
+                (9, "triple is declared but never called".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_toggle() {
+        let source = indoc! {"
+            FN double(a) -> b DECL
+                b := a + a
+            END
+
+            y := x + 1
+        "};
+
+        assert_eq!(warnings(source, Lints::empty()), vec![]);
+        assert_eq!(warnings(source, Lints::all()).len(), 2);
+        assert_eq!(
+            warnings(source, Lints::all() - Lints::UNINITIALIZED),
+            vec![(1, "double is declared but never called".to_string())]
+        );
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_lints_from_str() {
+        assert_eq!(
+            "unused_imports, UNUSED_FUNCTIONS".parse(),
+            Ok(Lints::UNUSED_IMPORTS | Lints::UNUSED_FUNCTIONS)
+        );
+        assert_eq!("".parse(), Ok(Lints::empty()));
+        assert!("unused".parse::<Lints>().is_err());
+    }
+}
+//endregion
@@ -0,0 +1,802 @@
+// This file should only be loaded if the cli feature is enabled.
+//
+// Language server, which communicates over stdio. Every open document is the main module
+// (fs::main), the directory it is in is the filesystem for fs:: imports, unsaved changes of other
+// open documents take precedence over the files on disk. Documents are analysed again on
+// every change, broken statements are left out of the analysis. Navigation uses the last
+// analysis which succeeded.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use lsp_server::{Connection, ErrorCode, ExtractError, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+    Notification as NotificationTrait, PublishDiagnostics,
+};
+use lsp_types::request::{Completion, DocumentSymbolRequest, GotoDefinition, HoverRequest};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionResponse, Diagnostic,
+    DiagnosticSeverity, DocumentSymbol, DocumentSymbolResponse, GotoDefinitionResponse, Hover,
+    HoverContents, HoverProviderCapability, Location, MarkupContent, MarkupKind, NumberOrString,
+    OneOf, Position, PublishDiagnosticsParams, Range, ServerCapabilities, SymbolKind,
+    TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+
+use crate::ast::hir::func::decl::{ConstDecl, FuncDecl};
+use crate::ast::hir::func::fs::{is_module, Directory, EXTENSIONS};
+use crate::ast::hir::func::module::map::ModuleMap;
+use crate::ast::hir::func::module::session::Session;
+use crate::ast::hir::func::stdlib::stdlib;
+use crate::ast::hir::func::structs::funcname::FuncName;
+use crate::ast::hir::func::structs::modname::ModuleName;
+use crate::ast::hir::func::structs::FuncContext;
+use crate::ast::module::Module;
+use crate::build::Builder;
+use crate::errors::Error;
+use crate::frontend::Frontend;
+use crate::types::LineNo;
+use lexer::{Kind, Lexer, Token};
+
+type ServerResult<T> = Result<T, Box<dyn std::error::Error + Sync + Send>>;
+
+//region Positions
+// positions of the protocol count UTF-16 code units
+fn offset(text: &str, position: Position) -> Option<usize> {
+    let start: usize = text
+        .split_inclusive('\n')
+        .take(position.line as usize)
+        .map(str::len)
+        .sum();
+    let line = text.get(start..)?.split('\n').next()?;
+
+    let mut units = 0;
+    for (index, char) in line.char_indices() {
+        if units >= position.character as usize {
+            return Some(start + index);
+        }
+
+        units += char.len_utf16();
+    }
+
+    Some(start + line.len())
+}
+
+fn position(text: &str, offset: usize) -> Position {
+    let before = &text[..offset];
+    let start = before.rfind('\n').map_or(0, |index| index + 1);
+
+    Position::new(
+        before.matches('\n').count() as u32,
+        before[start..].encode_utf16().count() as u32,
+    )
+}
+
+fn span(text: &str, token: &Token) -> Range {
+    Range::new(
+        position(text, token.span.start().into()),
+        position(text, token.span.end().into()),
+    )
+}
+
+// the range of a location, rows of 0 are an unknown location and locations without columns
+// span their whole rows
+fn range(text: &str, lno: LineNo) -> Range {
+    let start = lno.row.0.saturating_sub(1);
+    let end = lno.row.1.saturating_sub(1).max(start);
+
+    let line = |row: usize| text.lines().nth(row).unwrap_or_default();
+    // columns count characters, the protocol counts UTF-16 code units
+    let units = |row: usize, col: usize| -> usize {
+        line(row)
+            .chars()
+            .take(col.saturating_sub(1))
+            .map(char::len_utf16)
+            .sum()
+    };
+
+    let (from, to) = match lno.col {
+        (0, _) | (_, 0) => (0, line(end).encode_utf16().count()),
+        (from, to) => (units(start, from), units(end, to)),
+    };
+
+    Range::new(
+        Position::new(start as u32, from as u32),
+        Position::new(end as u32, to as u32),
+    )
+}
+
+// the identifier of a declaration on its first line
+fn ident(text: &str, row: usize, name: &str) -> Range {
+    Lexer::new(text)
+        .find(|token| u32::from(token.lno.row.start()) as usize + 1 == row && token.content == name)
+        .map_or_else(
+            || range(text, LineNo::rows(row, row)),
+            |token| span(text, &token),
+        )
+}
+
+// the name of the function or constant under the cursor, namespaced names include the namespace
+fn word(text: &str, position: Position) -> Option<String> {
+    let offset = offset(text, position)?;
+
+    Lexer::new(text)
+        .filter(|token| matches!(token.kind, Kind::Ident(_) | Kind::Path(_)))
+        .find(|token| {
+            usize::from(token.span.start()) <= offset && offset <= usize::from(token.span.end())
+        })
+        .map(|token| token.content)
+}
+//endregion
+
+fn signature(decl: &FuncDecl) -> String {
+    format!(
+        "fn {}({}) -> {}",
+        decl.ident.display(0, None),
+        decl.params
+            .iter()
+            .map(|param| param.display(0, None))
+            .collect::<Vec<_>>()
+            .join(", "),
+        decl.ret.display(0, None)
+    )
+}
+
+fn constant(decl: &ConstDecl) -> String {
+    format!(
+        "const {} = {}",
+        decl.ident.display(0, None),
+        decl.value.display(0, None)
+    )
+}
+
+// follows the imports of a name until it reaches the module it is declared in
+fn resolve(
+    map: &ModuleMap,
+    module: &ModuleName,
+    name: &FuncName,
+) -> Option<(ModuleName, FuncName, FuncContext)> {
+    match map.get(module)?.get(name)? {
+        FuncContext::Import(imp) => resolve(map, &imp.module, &imp.ident),
+        ctx => Some((module.clone(), name.clone(), ctx.clone())),
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Analysis {
+    module: Module,
+    map: ModuleMap,
+}
+
+impl Analysis {
+    fn resolve(&self, name: &str) -> Option<(ModuleName, FuncName, FuncContext)> {
+        let (qual, _) = self
+            .map
+            .lookup(None, &ModuleName::main(), &name.into())
+            .ok()?;
+
+        resolve(&self.map, qual.module(), qual.func())
+    }
+
+    fn hover(&self, name: &str) -> Option<String> {
+        let (module, _, ctx) = self.resolve(name)?;
+        let code = match ctx {
+            FuncContext::Func(decl) => signature(&decl),
+            FuncContext::Const(decl) => constant(&decl),
+            _ => return None,
+        };
+
+        let mut contents = format!("```\n{}\n```", code);
+        if module != ModuleName::main() {
+            contents.push_str(&format!("\n\ndeclared in `{}`", module.to_string()));
+        }
+
+        Some(contents)
+    }
+
+    fn completion(&self) -> Vec<CompletionItem> {
+        let mut items: Vec<_> = self
+            .map
+            .get(&ModuleName::main())
+            .into_iter()
+            .flat_map(|ctx| ctx.iter())
+            .flat_map(|(name, ctx)| match ctx {
+                // the functions of a namespaced import are completed with the namespace
+                FuncContext::Namespace(namespace) => namespace
+                    .exports
+                    .iter()
+                    .map(|export| format!("{}::{}", name.to_string(), export.to_string()))
+                    .collect(),
+                _ => vec![name.to_string()],
+            })
+            .filter_map(|name| {
+                let (kind, detail) = match self.resolve(&name)?.2 {
+                    FuncContext::Func(decl) => (CompletionItemKind::FUNCTION, signature(&decl)),
+                    FuncContext::Const(decl) => (CompletionItemKind::CONSTANT, constant(&decl)),
+                    _ => return None,
+                };
+
+                Some(CompletionItem {
+                    label: name,
+                    kind: Some(kind),
+                    detail: Some(detail),
+                    ..CompletionItem::default()
+                })
+            })
+            .collect();
+
+        items.sort_by(|a, b| a.label.cmp(&b.label));
+        items
+    }
+
+    #[allow(deprecated)]
+    fn symbols(&self, text: &str) -> Vec<DocumentSymbol> {
+        let symbol = |name: String, detail, kind, lno: LineNo| DocumentSymbol {
+            selection_range: ident(text, lno.row.0, &name),
+            name,
+            detail: Some(detail),
+            kind,
+            tags: None,
+            deprecated: None,
+            range: range(text, lno),
+            children: None,
+        };
+
+        let consts = self.module.consts.iter().map(|decl| {
+            symbol(
+                decl.ident.display(0, None),
+                constant(decl),
+                SymbolKind::CONSTANT,
+                decl.lno,
+            )
+        });
+        let funcs = self.module.decl.iter().map(|decl| {
+            symbol(
+                decl.ident.display(0, None),
+                signature(decl),
+                SymbolKind::FUNCTION,
+                decl.lno,
+            )
+        });
+
+        consts.chain(funcs).collect()
+    }
+}
+
+#[derive(Default)]
+struct Document {
+    text: String,
+    analysis: Option<Analysis>,
+
+    // the module map is resolved without inlining, while the session of the compilation
+    // replaces functions with their inlined version.
+    resolution: Session,
+    compilation: Session,
+}
+
+impl Document {
+    // analyses the document and returns every error
+    fn analyse(&mut self, fs: Directory) -> Vec<Error> {
+        // a broken document is analysed without its broken statements
+        let (mut module, mut errors) =
+            match Builder::parse_checked(&self.text, Frontend::Pest, Some(&fs)) {
+                Ok(module) => (module, vec![]),
+                Err(_) => Builder::parse_recovering(&self.text, Some(&fs)),
+            };
+
+        let map = match ModuleMap::from_session(
+            module.clone(),
+            fs.clone(),
+            stdlib(),
+            &mut self.resolution,
+        ) {
+            Ok(map) => map,
+            Err(rest) => return errors.into_iter().chain(rest).collect(),
+        };
+
+        self.analysis = Some(Analysis {
+            module: module.clone(),
+            map,
+        });
+
+        errors.extend(
+            Builder::compile_in_session(&mut module, None, Some(fs), None, &mut self.compilation)
+                .err()
+                .unwrap_or_default(),
+        );
+
+        errors
+    }
+}
+
+fn diagnostics(text: &str, errors: &[Error]) -> Vec<Diagnostic> {
+    errors
+        .iter()
+        .map(|error| Diagnostic {
+            range: range(text, error.lno),
+            severity: Some(DiagnosticSeverity::ERROR),
+            code: Some(NumberOrString::String(error.id().to_string())),
+            source: Some("lit".to_string()),
+            message: error.message(),
+            ..Diagnostic::default()
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct Server {
+    documents: HashMap<Url, Document>,
+}
+
+impl Server {
+    fn capabilities() -> ServerCapabilities {
+        ServerCapabilities {
+            text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+            definition_provider: Some(OneOf::Left(true)),
+            hover_provider: Some(HoverProviderCapability::Simple(true)),
+            completion_provider: Some(CompletionOptions::default()),
+            document_symbol_provider: Some(OneOf::Left(true)),
+            ..ServerCapabilities::default()
+        }
+    }
+
+    // the directory of the document, documents which are not files have no directory
+    fn root(uri: &Url) -> Option<PathBuf> {
+        uri.to_file_path()
+            .ok()
+            .and_then(|path| path.parent().map(Path::to_path_buf))
+    }
+
+    fn directory(&self, uri: &Url) -> Directory {
+        let root = match Self::root(uri) {
+            Some(root) => root,
+            None => return Directory::new(),
+        };
+
+        // the document itself is fs::main
+        let mut directory = uri
+            .to_file_path()
+            .ok()
+            .and_then(|path| Directory::siblings(&path).ok())
+            .unwrap_or_default();
+
+        for (other, document) in &self.documents {
+            let relative = match other.to_file_path() {
+                Ok(path) if other != uri && is_module(&path) => match path.strip_prefix(&root) {
+                    Ok(relative) => relative.with_extension(""),
+                    Err(_) => continue,
+                },
+                _ => continue,
+            };
+
+            let segments: Vec<_> = relative
+                .iter()
+                .map(|segment| segment.to_string_lossy().to_string())
+                .collect();
+            directory.insert_file(&segments, document.text.clone());
+        }
+
+        directory
+    }
+
+    // the location and contents of a module under fs::, the open document is preferred
+    fn module(&self, uri: &Url, module: &ModuleName) -> Option<(Url, String)> {
+        if *module == ModuleName::main() {
+            return Some((uri.clone(), self.documents.get(uri)?.text.clone()));
+        }
+
+        let (name, dirs) = match module.split_first() {
+            Some((fs, path)) if fs == "fs" => path.split_last()?,
+            _ => return None,
+        };
+
+        let mut path = Self::root(uri)?;
+        path.extend(dirs);
+
+        EXTENSIONS.iter().find_map(|ext| {
+            let path = path.join(format!("{}.{}", name, ext));
+            let uri = Url::from_file_path(&path).ok()?;
+
+            match self.documents.get(&uri) {
+                Some(document) => Some((uri, document.text.clone())),
+                None => std::fs::read_to_string(&path).ok().map(|text| (uri, text)),
+            }
+        })
+    }
+
+    fn update(&mut self, uri: Url, text: String) -> Notification {
+        let fs = self.directory(&uri);
+
+        let document = self.documents.entry(uri.clone()).or_default();
+        document.text = text;
+        let errors = document.analyse(fs);
+
+        let params = PublishDiagnosticsParams {
+            uri,
+            diagnostics: diagnostics(&document.text, &errors),
+            version: None,
+        };
+
+        Notification::new(PublishDiagnostics::METHOD.to_string(), params)
+    }
+
+    fn close(&mut self, uri: Url) -> Notification {
+        self.documents.remove(&uri);
+
+        let params = PublishDiagnosticsParams {
+            uri,
+            diagnostics: vec![],
+            version: None,
+        };
+
+        Notification::new(PublishDiagnostics::METHOD.to_string(), params)
+    }
+
+    // the document and its analysis, together with the name under the cursor
+    fn lookup(&self, uri: &Url, position: Position) -> Option<(&Analysis, String)> {
+        let document = self.documents.get(uri)?;
+
+        Some((document.analysis.as_ref()?, word(&document.text, position)?))
+    }
+
+    fn definition(&self, uri: &Url, position: Position) -> Option<Location> {
+        let (analysis, name) = self.lookup(uri, position)?;
+        let (module, name, ctx) = analysis.resolve(&name)?;
+
+        let lno = match ctx {
+            FuncContext::Func(decl) => decl.lno,
+            FuncContext::Const(decl) => decl.lno,
+            _ => return None,
+        };
+
+        let (uri, text) = self.module(uri, &module)?;
+        Some(Location::new(uri, ident(&text, lno.row.0, &name)))
+    }
+
+    fn hover(&self, uri: &Url, position: Position) -> Option<Hover> {
+        let (analysis, name) = self.lookup(uri, position)?;
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: analysis.hover(&name)?,
+            }),
+            range: None,
+        })
+    }
+
+    fn completion(&self, uri: &Url) -> Option<Vec<CompletionItem>> {
+        Some(self.documents.get(uri)?.analysis.as_ref()?.completion())
+    }
+
+    fn symbols(&self, uri: &Url) -> Option<Vec<DocumentSymbol>> {
+        let document = self.documents.get(uri)?;
+
+        Some(document.analysis.as_ref()?.symbols(&document.text))
+    }
+
+    fn notification(&mut self, notification: Notification) -> Option<Notification> {
+        let notification = match notification
+            .extract::<lsp_types::DidOpenTextDocumentParams>(DidOpenTextDocument::METHOD)
+        {
+            Ok(params) => {
+                return Some(self.update(params.text_document.uri, params.text_document.text))
+            }
+            Err(ExtractError::MethodMismatch(notification)) => notification,
+            Err(ExtractError::JsonError { .. }) => return None,
+        };
+
+        let notification = match notification
+            .extract::<lsp_types::DidChangeTextDocumentParams>(DidChangeTextDocument::METHOD)
+        {
+            // the whole document is sent on every change
+            Ok(mut params) => {
+                let change = params.content_changes.pop()?;
+                return Some(self.update(params.text_document.uri, change.text));
+            }
+            Err(ExtractError::MethodMismatch(notification)) => notification,
+            Err(ExtractError::JsonError { .. }) => return None,
+        };
+
+        match notification
+            .extract::<lsp_types::DidCloseTextDocumentParams>(DidCloseTextDocument::METHOD)
+        {
+            Ok(params) => Some(self.close(params.text_document.uri)),
+            Err(_) => None,
+        }
+    }
+
+    fn request(&mut self, request: Request) -> Response {
+        let request = match dispatch::<GotoDefinition>(request, |params| {
+            let params = params.text_document_position_params;
+
+            self.definition(&params.text_document.uri, params.position)
+                .map(GotoDefinitionResponse::Scalar)
+        }) {
+            Ok(response) => return response,
+            Err(request) => request,
+        };
+
+        let request = match dispatch::<HoverRequest>(request, |params| {
+            let params = params.text_document_position_params;
+
+            self.hover(&params.text_document.uri, params.position)
+        }) {
+            Ok(response) => return response,
+            Err(request) => request,
+        };
+
+        let request = match dispatch::<Completion>(request, |params| {
+            self.completion(&params.text_document_position.text_document.uri)
+                .map(CompletionResponse::Array)
+        }) {
+            Ok(response) => return response,
+            Err(request) => request,
+        };
+
+        let request = match dispatch::<DocumentSymbolRequest>(request, |params| {
+            self.symbols(&params.text_document.uri)
+                .map(DocumentSymbolResponse::Nested)
+        }) {
+            Ok(response) => return response,
+            Err(request) => request,
+        };
+
+        Response::new_err(
+            request.id,
+            ErrorCode::MethodNotFound as i32,
+            format!("unsupported request {}", request.method),
+        )
+    }
+}
+
+// handles the request if it is of type R, otherwise the request is given back
+fn dispatch<R>(
+    request: Request,
+    handler: impl FnOnce(R::Params) -> R::Result,
+) -> Result<Response, Request>
+where
+    R: lsp_types::request::Request,
+{
+    let id = request.id.clone();
+
+    match request.extract::<R::Params>(R::METHOD) {
+        Ok((id, params)) => Ok(Response::new_ok(id, handler(params))),
+        Err(ExtractError::MethodMismatch(request)) => Err(request),
+        Err(ExtractError::JsonError { error, .. }) => Ok(Response::new_err(
+            id,
+            ErrorCode::InvalidParams as i32,
+            error.to_string(),
+        )),
+    }
+}
+
+pub(crate) fn serve() -> ServerResult<()> {
+    let (connection, threads) = Connection::stdio();
+    connection.initialize(serde_json::to_value(Server::capabilities())?)?;
+
+    let mut server = Server::default();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    break;
+                }
+
+                let response = server.request(request);
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => {
+                if let Some(notification) = server.notification(notification) {
+                    connection
+                        .sender
+                        .send(Message::Notification(notification))?;
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+
+    drop(connection);
+    threads.join()?;
+
+    Ok(())
+}
+
+//region Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    fn uri(name: &str) -> Url {
+        let root = std::env::temp_dir().join(format!("lit-lsp-{}", std::process::id()));
+        Url::from_file_path(root.join(name)).unwrap()
+    }
+
+    fn open(server: &mut Server, name: &str, text: &str) -> Vec<Diagnostic> {
+        let notification = server.update(uri(name), text.to_string());
+        let params: PublishDiagnosticsParams = serde_json::from_value(notification.params).unwrap();
+
+        params.diagnostics
+    }
+
+    fn server() -> Server {
+        let mut server = Server::default();
+        open(
+            &mut server,
+            "util.lp",
+            indoc! {"
+            const two = 2
+
+            fn inc(a) -> b decl
+                b := a + 1
+            end
+            "},
+        );
+
+        server
+    }
+
+    const MAIN: &str = indoc! {"
+    from fs::util import (inc, two)
+    from std::math import max
+    import std::math as m
+
+    fn double(a) -> b decl
+        b := a * two
+    end
+
+    x := inc(y)
+    z := m::min(x, y)
+    w := double(x)
+    v := max(x, z)
+    "};
+
+    #[test]
+    fn test_lsp_positions() {
+        let text = "ab\nçd€f\n";
+
+        assert_eq!(offset(text, Position::new(1, 2)), Some(6));
+        assert_eq!(offset(text, Position::new(1, 3)), Some(9));
+        assert_eq!(offset(text, Position::new(1, 10)), Some(10));
+        assert_eq!(position(text, 9), Position::new(1, 3));
+        assert_eq!(
+            word("x := inc(y)", Position::new(0, 8)),
+            Some("inc".to_string())
+        );
+        assert_eq!(
+            word("z := m::min(x)", Position::new(0, 6)),
+            Some("m::min".to_string())
+        );
+    }
+
+    #[test]
+    fn test_lsp_diagnostics() {
+        let mut server = server();
+        assert_eq!(open(&mut server, "main.lp", MAIN), vec![]);
+
+        let diagnostics = open(
+            &mut server,
+            "main.lp",
+            "x := 1\nLOOP x DO\nEND\nEND\ny := 2 +\n",
+        );
+        assert!(!diagnostics.is_empty());
+        assert!(diagnostics.iter().all(|d| d.range.start.line >= 3));
+
+        let diagnostics = open(&mut server, "main.lp", "from fs::util import dec\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start.line, 0);
+        assert!(diagnostics[0].message.contains("dec"));
+    }
+
+    #[test]
+    fn test_lsp_shared_wildcard() {
+        let mut server = server();
+        let module = "from std::math import *\n";
+        open(
+            &mut server,
+            "a.lp",
+            &format!("{}fn a(x) -> y decl\n    y := max(x, 1)\nend\n", module),
+        );
+        open(
+            &mut server,
+            "b.lp",
+            &format!("{}fn b(x) -> y decl\n    y := min(x, 1)\nend\n", module),
+        );
+
+        // both modules resolve the same wildcard import, the second one from the cache
+        let diagnostics = open(
+            &mut server,
+            "main.lp",
+            "from fs::a import a\nfrom fs::b import b\n\nx := a(y)\nz := b(y)\n",
+        );
+        assert_eq!(diagnostics, vec![]);
+    }
+
+    #[test]
+    fn test_lsp_navigation() {
+        let mut server = server();
+        open(&mut server, "main.lp", MAIN);
+        let main = uri("main.lp");
+
+        // the declaration in the document itself
+        let location = server.definition(&main, Position::new(10, 6)).unwrap();
+        assert_eq!(location.uri, main);
+        assert_eq!(location.range.start, Position::new(4, 3));
+
+        // through the import of another open document
+        let location = server.definition(&main, Position::new(8, 5)).unwrap();
+        assert_eq!(location.uri, uri("util.lp"));
+        assert_eq!(location.range.start, Position::new(2, 3));
+
+        let location = server.definition(&main, Position::new(5, 14)).unwrap();
+        assert_eq!(location.range.start, Position::new(0, 6));
+
+        // the standard library has no file
+        assert_eq!(server.definition(&main, Position::new(11, 6)), None);
+
+        let hover = server.hover(&main, Position::new(9, 6)).unwrap();
+        match hover.contents {
+            HoverContents::Markup(markup) => {
+                assert!(
+                    markup.value.contains("fn min(a, b) -> c"),
+                    "{}",
+                    markup.value
+                );
+                assert!(markup.value.contains("std::math"));
+            }
+            _ => unreachable!(),
+        }
+
+        // navigation keeps working while the document is broken
+        open(
+            &mut server,
+            "main.lp",
+            &MAIN.replace("x := inc(y)", "x := inc(y"),
+        );
+        assert!(server.hover(&main, Position::new(8, 5)).is_some());
+    }
+
+    #[test]
+    fn test_lsp_recovery() {
+        let mut server = server();
+        let main = uri("main.lp");
+
+        // a document which was never valid is analysed without its broken statement
+        let text = MAIN.replace("x := inc(y)", "x := inc(y");
+        let diagnostics = open(&mut server, "main.lp", &text);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start.line, 8);
+
+        let location = server.definition(&main, Position::new(10, 6)).unwrap();
+        assert_eq!(location.range.start, Position::new(4, 3));
+        assert_eq!(server.symbols(&main).unwrap()[0].name, "double");
+    }
+
+    #[test]
+    fn test_lsp_completion_and_symbols() {
+        let mut server = server();
+        open(&mut server, "main.lp", MAIN);
+        let main = uri("main.lp");
+
+        let labels: Vec<_> = server
+            .completion(&main)
+            .unwrap()
+            .into_iter()
+            .map(|item| item.label)
+            .collect();
+        for label in ["double", "inc", "two", "max", "m::min"] {
+            assert!(labels.contains(&label.to_string()), "{:?}", labels);
+        }
+
+        let symbols = server.symbols(&main).unwrap();
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].name, "double");
+        assert_eq!(symbols[0].kind, SymbolKind::FUNCTION);
+        assert_eq!(symbols[0].range.start.line, 4);
+        assert_eq!(symbols[0].range.end.line, 6);
+        assert_eq!(symbols[0].selection_range.start, Position::new(4, 3));
+    }
+}
+//endregion
@@ -12,10 +12,14 @@ extern crate sum_type;
 
 mod ast;
 mod build;
+mod diagnostic;
 mod errors;
 mod eval;
+mod explain;
 mod flags;
+mod frontend;
 mod js;
+mod lint;
 mod parser;
 mod runtime;
 mod types;
@@ -27,6 +31,12 @@ mod tests;
 #[cfg(feature = "cli")]
 mod cli;
 #[cfg(feature = "cli")]
+mod debug;
+#[cfg(feature = "cli")]
+mod lsp;
+#[cfg(feature = "cli")]
+mod repl;
+#[cfg(feature = "cli")]
 use crate::cli::app;
 
 #[cfg(feature = "cli")]
@@ -1,4 +1,5 @@
 use either::Either;
+use itertools::Itertools;
 use num_bigint::BigUint;
 use num_traits::Zero;
 use pest_consume::match_nodes;
@@ -7,8 +8,8 @@ use pest_consume::Parser;
 
 use crate::ast::control::Control;
 use crate::ast::expr::Expr;
-use crate::ast::hir::func::decl::FuncDecl;
-use crate::ast::hir::func::imp::{Imp, ImpFunc, ImpWildcard};
+use crate::ast::hir::func::decl::{ConstDecl, FuncDecl};
+use crate::ast::hir::func::imp::{Imp, ImpFunc, ImpModule, ImpWildcard};
 use crate::ast::hir::func::{Func, FuncCall};
 use crate::ast::hir::macros::{Macro, MacroAssign};
 use crate::ast::hir::Hir;
@@ -39,7 +40,7 @@ impl LoopParserHelpers {
 
         settings
             .lno
-            .unwrap_or((span.start_pos().line_col().0, span.end_pos().line_col().0))
+            .unwrap_or_else(|| LineNo::new(span.start_pos().line_col(), span.end_pos().line_col()))
     }
 
     fn parse_comp(input: ParseNode) -> ParseResult<Expr> {
@@ -57,6 +58,8 @@ impl LoopParserHelpers {
 
 #[pest_consume::parser]
 #[allow(clippy::upper_case_acronyms)]
+// the methods generated for aliases (anyImport) do not keep attributes of the aliased rule fns
+#[allow(non_snake_case)]
 impl LoopParser {
     // Terminal Values
     #[allow(non_snake_case, clippy::upper_case_acronyms)]
@@ -77,6 +80,12 @@ impl LoopParser {
         Ok(Expr::Ident(input.as_str().to_string()))
     }
 
+    #[alias(atom)]
+    #[allow(non_snake_case, clippy::upper_case_acronyms)]
+    fn PATH(input: ParseNode) -> ParseResult<Expr> {
+        Ok(Expr::Ident(input.as_str().to_string()))
+    }
+
     #[alias(atom)]
     #[allow(non_snake_case, clippy::upper_case_acronyms)]
     fn VALUE(input: ParseNode) -> ParseResult<Expr> {
@@ -351,14 +360,16 @@ impl LoopParser {
     #[allow(non_snake_case)]
     fn funcDef(input: ParseNode) -> ParseResult<FuncDecl> {
         let lno = LoopParserHelpers::lno(input.clone());
-        let (atoms, terms): (Vec<Expr>, Hir) = match_nodes!(input.clone().into_children();
-            [atom(atoms).., expr(terms)] => (atoms.collect(), terms)
+        let (public, atoms, terms): (bool, Vec<Expr>, Hir) = match_nodes!(input.clone().into_children();
+            [PUB(_), atom(atoms).., expr(terms)] => (true, atoms.collect(), terms),
+            [atom(atoms).., expr(terms)] => (false, atoms.collect(), terms)
         );
 
         if let Some((ident, params)) = atoms.split_first() {
             if let Some((ret, params)) = params.split_last() {
                 let decl = FuncDecl {
                     lno,
+                    public,
 
                     ident: Box::new(ident.clone()),
                     params: params.to_vec(),
@@ -384,6 +395,32 @@ impl LoopParser {
         Ok(funcs)
     }
 
+    // Constant Definition
+    #[allow(non_snake_case)]
+    fn constDecl(input: ParseNode) -> ParseResult<ConstDecl> {
+        let lno = LoopParserHelpers::lno(input.clone());
+        let (public, ident, value) = match_nodes!(input.into_children();
+            [PUB(_), atom(ident), atom(value)] => (true, ident, value),
+            [atom(ident), atom(value)] => (false, ident, value)
+        );
+
+        Ok(ConstDecl {
+            lno,
+            public,
+
+            ident: Box::new(ident),
+            value: Box::new(value),
+        })
+    }
+
+    fn constants(input: ParseNode) -> ParseResult<Vec<ConstDecl>> {
+        let consts: Vec<ConstDecl> = match_nodes!(input.into_children();
+            [constDecl(consts)..] => consts.collect()
+        );
+
+        Ok(consts)
+    }
+
     #[allow(non_snake_case)]
     fn importFunc(input: ParseNode) -> ParseResult<ImpFunc> {
         let (ident, alias): (Expr, Option<Expr>) = match_nodes!(input.into_children();
@@ -409,26 +446,56 @@ impl LoopParser {
         Ok(stmt)
     }
 
-    fn import(input: ParseNode) -> ParseResult<Imp> {
+    #[alias(anyImport)]
+    fn import(input: ParseNode) -> ParseResult<Either<Imp, ImpModule>> {
         let lno = LoopParserHelpers::lno(input.clone());
 
-        let (path, stmt): (Vec<Expr>, Either<Vec<ImpFunc>, ImpWildcard>) = match_nodes!(input.into_children();
-            [atom(path).., importStmt(stmt)] => (path.collect(), stmt)
+        let (public, path, stmt): (bool, Vec<Expr>, Either<Vec<ImpFunc>, ImpWildcard>) = match_nodes!(input.into_children();
+            [PUB(_), atom(path).., importStmt(stmt)] => (true, path.collect(), stmt),
+            [atom(path).., importStmt(stmt)] => (false, path.collect(), stmt)
         );
 
         let import = Imp {
             lno,
+            public,
 
             path,
             funcs: stmt,
         };
 
-        Ok(import)
+        Ok(Either::Left(import))
+    }
+
+    #[allow(non_snake_case)]
+    fn importAlias(input: ParseNode) -> ParseResult<Expr> {
+        Ok(match_nodes!(input.into_children();
+            [atom(alias)] => alias
+        ))
+    }
+
+    #[alias(anyImport)]
+    #[allow(non_snake_case)]
+    fn importModule(input: ParseNode) -> ParseResult<Either<Imp, ImpModule>> {
+        let lno = LoopParserHelpers::lno(input.clone());
+
+        let (path, alias): (Vec<Expr>, Option<Expr>) = match_nodes!(input.into_children();
+            [atom(path).., importAlias(alias)] => (path.collect(), Some(alias)),
+            [atom(path)..] => (path.collect(), None)
+        );
+
+        let import = ImpModule {
+            lno,
+
+            path,
+            alias: alias.map(Box::new),
+        };
+
+        Ok(Either::Right(import))
     }
 
-    fn imports(input: ParseNode) -> ParseResult<Vec<Imp>> {
-        let imports: Vec<Imp> = match_nodes!(input.into_children();
-            [import(imports)..] => imports.collect()
+    fn imports(input: ParseNode) -> ParseResult<(Vec<Imp>, Vec<ImpModule>)> {
+        let imports: (Vec<Imp>, Vec<ImpModule>) = match_nodes!(input.into_children();
+            [anyImport(imports)..] => imports.partition_map(|imp| imp)
         );
 
         Ok(imports)
@@ -436,24 +503,31 @@ impl LoopParser {
 
     // Initialization Rule
     pub(crate) fn grammar(input: ParseNode) -> ParseResult<Module> {
-        let (imp, decl, code): (Option<Vec<Imp>>, Option<Vec<FuncDecl>>, Hir) = match_nodes!(input.into_children();
-            // Full Module
-            [imports(i), functions(f), expr(t), EOI(_)] => (Some(i), Some(f), t),
-            // Mixed Module
-            [imports(i), functions(f), EOI(_)] => (Some(i), Some(f), Hir::NoOp),
-            [functions(f), expr(t), EOI(_)] => (None, Some(f), t),
-            [imports(i), expr(t), EOI(_)] => (Some(i), None, t),
-            // Single Purpose Module
-            [functions(f), EOI(_)] => (None, Some(f), Hir::NoOp),
-            [imports(i), EOI(_)] => (Some(i), None, Hir::NoOp),
-            [expr(t), EOI(_)] => (None, None, t)
-        );
+        let mut module = Module {
+            imp: vec![],
+            imp_mod: vec![],
+            consts: vec![],
+            decl: vec![],
+            code: Hir::NoOp,
+        };
 
-        Ok(Module {
-            imp: imp.unwrap_or_default(),
-            decl: decl.unwrap_or_default(),
-            code,
-        })
+        // every section of a module is optional, but their order is fixed by the grammar:
+        // imports, constants, functions and then the actual code
+        for node in input.into_children() {
+            match node.as_rule() {
+                Rule::imports => {
+                    let (imp, imp_mod) = Self::imports(node)?;
+                    module.imp = imp;
+                    module.imp_mod = imp_mod;
+                }
+                Rule::constants => module.consts = Self::constants(node)?,
+                Rule::functions => module.decl = Self::functions(node)?,
+                Rule::EOI => {}
+                _ => module.code = Self::expr(node)?,
+            }
+        }
+
+        Ok(module)
     }
 
     // Make the parser happy, these always error out.
@@ -497,4 +571,8 @@ impl LoopParser {
     fn WILDCARD(input: ParseNode) -> ParseResult<()> {
         Ok(())
     }
+    #[allow(non_snake_case, clippy::upper_case_acronyms)]
+    fn PUB(input: ParseNode) -> ParseResult<()> {
+        Ok(())
+    }
 }
@@ -0,0 +1,408 @@
+// This file should only be loaded if the cli feature is enabled.
+//
+// Interactive session. Imports, constants and function declarations are accumulated, every other
+// entry is compiled against them and executed with the variables of the previous entries.
+// Entries which open a block (DO, DECL or IF) continue until the block is closed.
+
+use std::fs::read_to_string;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use indoc::indoc;
+use lexer::{Keyword, Kind, Lexer, Token};
+
+use crate::ast::hir::func::fs::Directory;
+use crate::build::Builder;
+use crate::cli::visible;
+use crate::errors::Error;
+use crate::eval::types::Variables;
+use crate::flags::CompileFlags;
+
+const HELP: &str = indoc! {"
+    :vars            print every variable
+    :reset           forget every variable and declaration
+    :expand <stmt>   print the lowered statement, without executing it
+    :flags [flags]   print or set the compile flags, like :flags loop,while
+    :load <file>     enter every line of a file
+    :help            print this message
+    :quit            exit the session, as does Ctrl-D
+"};
+
+// the output of an entry, errors are written to stderr
+type Output = Result<String, String>;
+
+fn is_word(token: &Token, word: &str) -> bool {
+    matches!(&token.kind, Kind::Ident(ident) if ident.eq_ignore_ascii_case(word))
+}
+
+// the number of blocks opened by the tokens which have not been closed yet
+fn depth(tokens: &[Token]) -> isize {
+    tokens
+        .iter()
+        .map(|token| match token.kind {
+            Kind::Keyword(Keyword::Do | Keyword::Decl) => 1,
+            Kind::Keyword(Keyword::End) => -1,
+            _ if is_word(token, "if") => 1,
+            _ => 0,
+        })
+        .sum()
+}
+
+#[derive(Copy, Clone)]
+enum Section {
+    Imports,
+    Consts,
+    Funcs,
+}
+
+// the declaration section of the entry and the name it declares, None for statements
+fn section(tokens: &[Token]) -> Option<(Section, Option<String>)> {
+    let mut tokens = tokens.iter().skip_while(|token| is_word(token, "pub"));
+    let first = tokens.next()?;
+    let name = tokens.next().map(|token| token.content.clone());
+
+    match first.kind {
+        Kind::Keyword(Keyword::From | Keyword::Import) => Some((Section::Imports, None)),
+        Kind::Keyword(Keyword::Fn) => Some((Section::Funcs, name)),
+        _ if is_word(first, "const") => Some((Section::Consts, name)),
+        _ => None,
+    }
+}
+
+pub(crate) struct Repl {
+    flags: CompileFlags,
+    fs: Directory,
+
+    // declarations together with the name they declare, a new declaration replaces the old one
+    imports: Vec<String>,
+    consts: Vec<(Option<String>, String)>,
+    funcs: Vec<(Option<String>, String)>,
+
+    variables: Variables,
+    // the lines of an entry which is not complete yet
+    pending: String,
+}
+
+impl Repl {
+    pub(crate) fn new(flags: CompileFlags, fs: Directory) -> Self {
+        Self {
+            flags,
+            fs,
+            imports: vec![],
+            consts: vec![],
+            funcs: vec![],
+            variables: Variables::new(),
+            pending: String::new(),
+        }
+    }
+
+    fn prompt(&self) -> &'static str {
+        if self.pending.is_empty() {
+            ">>> "
+        } else {
+            "... "
+        }
+    }
+
+    // the declarations in the order they need to appear in
+    fn header(&self) -> String {
+        self.imports
+            .iter()
+            .chain(self.consts.iter().map(|(_, decl)| decl))
+            .chain(self.funcs.iter().map(|(_, decl)| decl))
+            .map(|decl| format!("{}\n", decl))
+            .collect()
+    }
+
+    fn errors(&self, entry: &str, errors: &[Error]) -> String {
+        let offset = self.header().lines().count();
+        let multiline = entry.trim().lines().count() > 1;
+
+        errors
+            .iter()
+            .map(|error| match error.lno.row.0.checked_sub(offset) {
+                Some(row) if row > 0 && multiline => {
+                    format!("error[{}]: line {}: {}", error.id(), row, error.message())
+                }
+                _ => format!("error[{}]: {}", error.id(), error.message()),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Feeds a line of input, the output is returned once the entry is complete.
+    pub(crate) fn feed(&mut self, line: &str) -> Option<Output> {
+        if self.pending.is_empty() {
+            if let Some(command) = line.trim().strip_prefix(':') {
+                return Some(self.command(command));
+            }
+        }
+
+        self.pending.push_str(line);
+        self.pending.push('\n');
+
+        let tokens: Vec<_> = Lexer::new(&self.pending)
+            .filter(|token| !token.kind.is_trivia() && !matches!(token.kind, Kind::Newline))
+            .collect();
+        if depth(&tokens) > 0 {
+            return None;
+        }
+
+        let entry = std::mem::take(&mut self.pending);
+        if tokens.is_empty() {
+            return Some(Ok(String::new()));
+        }
+
+        Some(match section(&tokens) {
+            Some((section, name)) => self.declare(section, name, entry.trim_end()),
+            None => self.execute(&entry),
+        })
+    }
+
+    fn declare(&mut self, section: Section, name: Option<String>, entry: &str) -> Output {
+        let previous = (
+            self.imports.clone(),
+            self.consts.clone(),
+            self.funcs.clone(),
+        );
+
+        match section {
+            Section::Imports => self.imports.push(entry.to_string()),
+            Section::Consts | Section::Funcs => {
+                let decls = match section {
+                    Section::Consts => &mut self.consts,
+                    _ => &mut self.funcs,
+                };
+
+                decls.retain(|(declared, _)| name.is_none() || *declared != name);
+                decls.push((name, entry.to_string()));
+            }
+        }
+
+        // the declarations are only kept if they still compile
+        let header = self.header();
+        match Builder::parse_and_compile(&header, Some(self.flags), Some(self.fs.clone())) {
+            Ok(_) => Ok(String::new()),
+            Err(errors) => {
+                let output = self.errors(entry, &errors);
+                (self.imports, self.consts, self.funcs) = previous;
+
+                Err(output)
+            }
+        }
+    }
+
+    fn execute(&mut self, entry: &str) -> Output {
+        let source = self.header() + entry;
+        let mut runtime = Builder::ext_all(
+            &source,
+            Some(self.flags),
+            Some(self.variables.clone()),
+            Some(self.fs.clone()),
+        )
+        .map_err(|errors| self.errors(entry, &errors))?;
+
+        while runtime.is_running() {
+            runtime.step();
+        }
+
+        // only the variables changed by the entry are printed
+        let previous = std::mem::replace(&mut self.variables, runtime.context());
+        Ok(visible(self.variables.clone())
+            .into_iter()
+            .filter(|(ident, value)| previous.get(ident) != Some(value))
+            .map(|(ident, value)| format!("{} = {}", ident, value))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+
+    fn command(&mut self, command: &str) -> Output {
+        let (name, argument) = command
+            .split_once(char::is_whitespace)
+            .map_or((command, ""), |(name, argument)| (name, argument.trim()));
+
+        match name {
+            "vars" => Ok(visible(self.variables.clone())
+                .into_iter()
+                .map(|(ident, value)| format!("{} = {}", ident, value))
+                .collect::<Vec<_>>()
+                .join("\n")),
+            "reset" => {
+                self.imports.clear();
+                self.consts.clear();
+                self.funcs.clear();
+                self.variables.clear();
+
+                Ok(String::new())
+            }
+            "expand" => {
+                let source = self.header() + argument;
+
+                Builder::parse_and_compile(&source, Some(self.flags), Some(self.fs.clone()))
+                    .map(|expr| expr.display(4, None).trim_matches('\n').to_string())
+                    .map_err(|errors| self.errors(argument, &errors))
+            }
+            "flags" if argument.is_empty() => Ok(self.flags.to_string()),
+            "flags" => {
+                self.flags = argument.parse()?;
+                Ok(self.flags.to_string())
+            }
+            "load" => self.load(Path::new(argument)),
+            "help" => Ok(HELP.trim_end().to_string()),
+            _ => Err(format!("unknown command :{}, see :help", name)),
+        }
+    }
+
+    // every line of the file is entered, stops at the first error
+    fn load(&mut self, file: &Path) -> Output {
+        let source = read_to_string(file)
+            .map_err(|err| format!("could not read {}: {}", file.display(), err))?;
+        let mut output = vec![];
+
+        for line in source.lines() {
+            match self.feed(line) {
+                Some(Ok(text)) if !text.is_empty() => output.push(text),
+                Some(Err(error)) => {
+                    output.push(error);
+                    return Err(output.join("\n"));
+                }
+                _ => {}
+            }
+        }
+
+        if !self.pending.is_empty() {
+            self.pending.clear();
+            output.push("error: a block is never closed by END".to_string());
+
+            return Err(output.join("\n"));
+        }
+
+        Ok(output.join("\n"))
+    }
+}
+
+pub(crate) fn start(flags: CompileFlags) -> io::Result<()> {
+    let fs = Directory::read(Path::new("."))?;
+    let mut repl = Repl::new(flags, fs);
+
+    println!("Enter :help for a list of commands");
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("{}", repl.prompt());
+        io::stdout().flush()?;
+
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+
+        if repl.pending.is_empty() && line.trim() == ":quit" {
+            break;
+        }
+
+        match repl.feed(&line) {
+            Some(Ok(output)) if !output.is_empty() => println!("{}", output),
+            Some(Err(error)) => eprintln!("{}", error),
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+//region Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(repl: &mut Repl, lines: &str) -> Option<Output> {
+        lines.lines().map(|line| repl.feed(line)).last().flatten()
+    }
+
+    #[track_caller]
+    fn ok(repl: &mut Repl, lines: &str) -> String {
+        feed(repl, lines).unwrap().unwrap()
+    }
+
+    fn repl() -> Repl {
+        let mut fs = Directory::new();
+        fs.insert(
+            "util".to_string(),
+            "fn inc(a) -> b decl\n    b := a + 1\nend\n".into(),
+        );
+
+        Repl::new(CompileFlags::default(), fs)
+    }
+
+    #[test]
+    fn test_repl_statements() {
+        let mut repl = repl();
+
+        assert_eq!(ok(&mut repl, "x := 2"), "x = 2");
+        assert_eq!(ok(&mut repl, "y := x + 3; z := 1"), "y = 5\nz = 1");
+
+        assert_eq!(repl.feed("LOOP x DO"), None);
+        assert_eq!(repl.prompt(), "... ");
+        assert_eq!(repl.feed("    y := y + 1 # comment"), None);
+        assert_eq!(ok(&mut repl, "END"), "y = 7");
+
+        // unchanged variables are not printed
+        assert_eq!(ok(&mut repl, "x := 2"), "");
+        assert_eq!(ok(&mut repl, "# only a comment"), "");
+        assert_eq!(ok(&mut repl, ":vars"), "x = 2\ny = 7\nz = 1");
+
+        assert!(feed(&mut repl, "x := ").unwrap().is_err());
+        assert_eq!(ok(&mut repl, "x := x + 1"), "x = 3");
+    }
+
+    #[test]
+    fn test_repl_declarations() {
+        let mut repl = repl();
+
+        assert_eq!(ok(&mut repl, "from fs::util import inc"), "");
+        assert_eq!(repl.feed("fn double(a) -> b decl"), None);
+        assert_eq!(ok(&mut repl, "    b := a + a\nend"), "");
+        assert_eq!(ok(&mut repl, "x := inc(2)"), "x = 3");
+        assert_eq!(ok(&mut repl, "x := double(x)"), "x = 6");
+
+        // a function is replaced by a new declaration with the same name
+        ok(&mut repl, "fn double(a) -> b decl\n    b := a + 2\nend");
+        assert_eq!(ok(&mut repl, "x := double(1)"), "x = 3");
+
+        // declarations which do not compile are not kept
+        let error = feed(&mut repl, "from fs::util import dec").unwrap();
+        assert!(error.unwrap_err().contains("dec"));
+        assert_eq!(ok(&mut repl, "y := inc(x)"), "y = 4");
+
+        assert_eq!(ok(&mut repl, ":reset"), "");
+        assert!(feed(&mut repl, "y := inc(x)").unwrap().is_err());
+        assert_eq!(ok(&mut repl, ":vars"), "");
+    }
+
+    #[test]
+    fn test_repl_commands() {
+        let mut repl = repl();
+
+        assert_eq!(ok(&mut repl, ":flags"), "loop,while");
+        assert_eq!(ok(&mut repl, ":flags while"), "while");
+        assert!(ok(&mut repl, ":expand LOOP x DO; y := y + 1; END").contains("WHILE"));
+        assert!(feed(&mut repl, ":flags for").unwrap().is_err());
+        assert!(feed(&mut repl, ":unknown").unwrap().is_err());
+        assert!(ok(&mut repl, ":help").contains(":load"));
+
+        let file = std::env::temp_dir().join(format!("lit-repl-{}.lp", std::process::id()));
+        std::fs::write(
+            &file,
+            "fn dec(a) -> b decl\n    b := a - 1\nend\n\nx := 3\ny := dec(x)\n",
+        )
+        .unwrap();
+        let output = feed(&mut repl, &format!(":load {}", file.display()));
+        std::fs::remove_file(&file).unwrap();
+
+        assert_eq!(output.unwrap().unwrap(), "x = 3\ny = 2");
+        assert_eq!(ok(&mut repl, "z := dec(y)"), "z = 1");
+    }
+}
+//endregion
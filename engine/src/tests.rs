@@ -1,8 +1,13 @@
 use crate::ast::hir::func::fs::Directory;
 use crate::build::Builder;
+use crate::errors::Error;
 use crate::errors::ErrorCode::FunctionUnexpectedNumberOfArguments;
+use crate::eval::exec::Exec;
 use crate::eval::types::Variables;
 use crate::flags::CompileFlags;
+use crate::frontend::Frontend;
+use crate::runtime::Runtime;
+use crate::types::LineNo;
 
 use indoc::indoc;
 use num_bigint::BigUint;
@@ -208,7 +213,10 @@ fn test_decompile() {
             x := x + 1
             _0 := _0 - 1
         END"}
-    )
+    );
+
+    // the lowered LOOP is reverted by the decompiler
+    assert_eq!(Builder::decompile(&ast).display(4, None), snip.trim());
 }
 
 #[test]
@@ -641,7 +649,7 @@ fn test_call_param_error() {
 
     let expected = vec![
         crate::errors::Error {
-            lno: (6, 6),
+            lno: LineNo::new((6, 1), (6, 10)),
             variant: crate::errors::ErrorVariant::ErrorCode(FunctionUnexpectedNumberOfArguments {
                 module: "fs::main".to_string(),
                 func: "a".to_string(),
@@ -650,7 +658,7 @@ fn test_call_param_error() {
             }),
         },
         crate::errors::Error {
-            lno: (7, 7),
+            lno: LineNo::new((7, 1), (7, 16)),
             variant: crate::errors::ErrorVariant::ErrorCode(FunctionUnexpectedNumberOfArguments {
                 module: "fs::main".to_string(),
                 func: "a".to_string(),
@@ -802,6 +810,596 @@ fn test_inline_across_fs() {
     assert_is_int(z, 4);
 }
 
+#[test]
+fn test_const_decl() {
+    let snip = indoc! {"
+    const LIMIT = 3
+
+    x := LIMIT + 2
+    "};
+
+    let result = run(snip, Some(50), None, None, None);
+    assert_result_ok(&result);
+
+    let locals = result.ok().unwrap();
+    assert_is_int(locals.get("x"), 5);
+    assert_is_int(locals.get("LIMIT"), 3);
+}
+
+#[test]
+fn test_const_assign() {
+    let snip = indoc! {"
+    const LIMIT = 3
+
+    LIMIT := LIMIT + 1
+    "};
+
+    let errors = Builder::ext_all(snip, None, None, None)
+        .err()
+        .expect("Expected Error");
+
+    assert_eq!(
+        errors,
+        vec![crate::errors::Error {
+            lno: LineNo::new((3, 1), (3, 19)),
+            variant: crate::errors::ErrorVariant::ErrorCode(
+                crate::errors::ErrorCode::AssignmentToConstant {
+                    module: "fs::main".to_string(),
+                    ident: "LIMIT".to_string(),
+                }
+            ),
+        }]
+    );
+}
+
+#[test]
+fn test_const_import() {
+    let module_cfg = indoc! {"
+    const LIMIT = 4
+
+    fn addLimit(a) -> b decl
+        b := a + LIMIT
+    end
+    "};
+
+    let snip = indoc! {"
+    from fs::cfg import (LIMIT, addLimit)
+
+    x := addLimit(LIMIT)
+    "};
+
+    let mut dir = Directory::new();
+    dir.insert("cfg".into(), module_cfg.into());
+
+    let result = run(snip, Some(300), None, None, Some(dir));
+    assert_result_ok(&result);
+
+    let locals = result.ok().unwrap();
+    assert_is_int(locals.get("x"), 8);
+}
+
+#[test]
+fn test_private_helper() {
+    let module_a = indoc! {"
+    pub fn double(a) -> b decl
+        b := helper(a)
+    end
+
+    fn helper(a) -> b decl
+        b := a + a
+    end
+    "};
+
+    // helper is private in fs::a, so it does not collide with our own
+    let snip = indoc! {"
+    from fs::a import *
+
+    fn helper(a) -> b decl
+        b := a + 1
+    end
+
+    x := double(3)
+    y := helper(3)
+    "};
+
+    let mut dir = Directory::new();
+    dir.insert("a".into(), module_a.into());
+
+    let result = run(snip, Some(300), None, None, Some(dir));
+    assert_result_ok(&result);
+
+    let locals = result.ok().unwrap();
+    assert_is_int(locals.get("x"), 6);
+    assert_is_int(locals.get("y"), 4);
+}
+
+#[test]
+fn test_import_module() {
+    let snip = indoc! {"
+    import std::math as m
+    import std::math
+
+    z := m::max(x, y)
+    w := std::math::min(x, y)
+    "};
+
+    let mut locals = HashMap::new();
+    locals.insert("x".to_string(), BigUint::from(2u8));
+    locals.insert("y".to_string(), BigUint::from(4u8));
+
+    let result = run(snip, Some(300), Some(locals), None, None);
+    assert_result_ok(&result);
+
+    let locals = result.ok().unwrap();
+    assert_is_int(locals.get("z"), 4);
+    assert_is_int(locals.get("w"), 2);
+}
+
+#[test]
+fn test_import_module_missing_func() {
+    let snip = indoc! {"
+    import std::math as m
+
+    z := m::avg(x, y)
+    "};
+
+    let errors = Builder::ext_all(snip, None, None, None)
+        .err()
+        .expect("Expected Error");
+
+    assert_eq!(
+        errors,
+        vec![crate::errors::Error {
+            lno: LineNo::new((3, 1), (3, 18)),
+            variant: crate::errors::ErrorVariant::ErrorCode(
+                crate::errors::ErrorCode::CouldNotFindFunction {
+                    module: "std::math".to_string(),
+                    func: "avg".to_string(),
+                }
+            ),
+        }]
+    );
+
+    // only the exported functions are called through the namespace
+    let module_a = indoc! {"
+    pub fn double(a) -> b decl
+        b := a + a
+    end
+
+    fn triple(a) -> b decl
+        b := a * 3
+    end
+    "};
+    let snip = "import fs::a\n\nz := fs::a::double(x)\nw := fs::a::triple(x)\n";
+
+    let mut dir = Directory::new();
+    dir.insert("a".into(), module_a.into());
+
+    let errors = Builder::ext_all(snip, None, None, Some(dir))
+        .err()
+        .expect("Expected Error");
+    assert_eq!(
+        errors
+            .iter()
+            .map(|error| error.variant.clone())
+            .collect::<Vec<_>>(),
+        vec![crate::errors::ErrorVariant::ErrorCode(
+            crate::errors::ErrorCode::FunctionNotExported {
+                module: "fs::a".to_string(),
+                func: "triple".to_string(),
+            }
+        )]
+    );
+}
+
+#[test]
+fn test_reexport() {
+    let module_a = indoc! {"
+    pub from std::math import max
+    from std::math import min
+
+    pub fn double(a) -> b decl
+        b := a + a
+    end
+    "};
+
+    let snip = indoc! {"
+    from fs::a import *
+
+    fn min(a, b) -> c decl
+        c := a
+    end
+
+    z := max(x, y)
+    w := double(x)
+    "};
+
+    let mut locals = HashMap::new();
+    locals.insert("x".to_string(), BigUint::from(2u8));
+    locals.insert("y".to_string(), BigUint::from(4u8));
+
+    let mut dir = Directory::new();
+    dir.insert("a".into(), module_a.into());
+
+    let result = run(snip, Some(300), Some(locals), None, Some(dir));
+    assert_result_ok(&result);
+
+    let locals = result.ok().unwrap();
+    assert_is_int(locals.get("z"), 4);
+    assert_is_int(locals.get("w"), 4);
+}
+
+#[test]
+#[cfg(feature = "cli")]
+fn test_trace() {
+    let snip = indoc! {"
+    from std::macros::inline macro import *
+    from std::macros::assign macro import *
+    from fs::a macro import *
+
+    x++
+    y **= 2
+    "};
+    let module_a = indoc! {"
+    @macro square
+        %1.i **= 2
+    @sub
+        %1.i := %1.i * %1.i
+    @end
+    "};
+
+    let mut dir = Directory::new();
+    dir.insert("a".into(), module_a.into());
+
+    let (trace, result) = Builder::trace(snip, Some(dir));
+    assert!(result.is_ok(), "{:?}", result);
+
+    let regions: Vec<_> = trace
+        .regions
+        .iter()
+        .map(|region| {
+            region
+                .expansion
+                .as_ref()
+                .map(|expansion| expansion.ident.clone())
+        })
+        .collect();
+    assert_eq!(
+        regions,
+        vec![
+            Some("inlineIncrement".to_string()),
+            Some("square".to_string())
+        ]
+    );
+
+    // the errors are located in the source, the trace still shows what has been tried
+    let (trace, result) = Builder::trace("x := 1\nx +!= 2\n", None);
+    let errors = result.unwrap_err();
+    assert_eq!(
+        errors
+            .iter()
+            .map(|error| (error.id(), error.lno))
+            .collect::<Vec<_>>(),
+        vec![("E017", LineNo::new((2, 1), (2, 8)))]
+    );
+    assert!(!trace.regions.is_empty());
+}
+
+// every snippet of this file, dedented like indoc! does
+fn snippets() -> Vec<String> {
+    include_str!("tests.rs")
+        .split("indoc! {\"")
+        .skip(1)
+        .map(|rest| {
+            let snip = &rest[..rest.find("\"}").unwrap()];
+            let snip = snip.strip_prefix('\n').unwrap_or(snip);
+
+            let indent = snip
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| line.len() - line.trim_start().len())
+                .min()
+                .unwrap_or(0);
+
+            snip.lines()
+                .map(|line| line.get(indent..).unwrap_or(""))
+                .collect::<Vec<_>>()
+                .join("\n")
+                + if snip.ends_with('\n') { "\n" } else { "" }
+        })
+        .collect()
+}
+
+#[test]
+fn test_frontend_parity() {
+    let snippets = snippets();
+    assert!(!snippets.is_empty());
+
+    for snip in snippets {
+        let pest = Builder::parse_with(&snip, None, Frontend::Pest);
+        let combine = Builder::parse_with(&snip, None, Frontend::Combine);
+
+        // the pest grammar does not know macros, only the combine frontend expands them
+        let macros = snip.contains("@macro") || snip.to_lowercase().contains("macro import");
+
+        match (pest, combine) {
+            (Ok(pest), Ok(combine)) => assert_eq!(pest, combine, "{}", snip),
+            (Err(_), Err(_)) => {}
+            (Err(_), Ok(_)) if macros => {}
+            (pest, combine) => panic!(
+                "parsers disagree on:\n{}\npest: {:?}\ncombine: {:?}",
+                snip, pest, combine
+            ),
+        }
+    }
+}
+
+#[test]
+fn test_execution_lno() {
+    let snip = indoc! {"
+    x := 2
+    LOOP x DO
+        y := y + 1
+    END
+    "};
+
+    for frontend in [Frontend::Pest, Frontend::Combine] {
+        let mut module = Builder::parse_with(snip, None, frontend).unwrap();
+        let expr = Builder::compile(&mut module, None, None).unwrap();
+        let mut runtime = Runtime::new(Exec::new(expr), None);
+
+        let mut lnos = vec![];
+        while let Some(result) = runtime.step() {
+            lnos.push(result.0);
+        }
+
+        assert!(lnos.contains(&LineNo::new((2, 1), (4, 4))));
+        assert!(lnos.contains(&LineNo::new((3, 5), (3, 15))));
+    }
+}
+
+#[test]
+fn test_frontend_combine() {
+    let snip = indoc! {"
+    FROM fs::a IMPORT square
+    CONST two = 2
+
+    FN double(x) -> y DECL
+        y := x * two
+    END
+
+    a := double(3)
+    IF a > 5 THEN
+        b := square(a)
+    ELSE
+        b := 0
+    END
+    "};
+    let module_a = indoc! {"
+    FN square(x) -> y DECL
+        y := x * x
+    END
+    "};
+
+    let mut dir = Directory::new();
+    dir.insert("a".into(), module_a.into());
+
+    let flags = CompileFlags::default() | CompileFlags::CNF_CONST | CompileFlags::CNF_COMBINE;
+    let context = run(snip, None, None, Some(flags), Some(dir)).unwrap();
+
+    assert_is_int(context.get("a"), 6);
+    assert_is_int(context.get("b"), 36);
+}
+
+#[test]
+fn test_frontend_macros() {
+    let snip = indoc! {"
+    FROM std::macros::inline MACRO IMPORT *
+    FROM fs::a MACRO IMPORT square
+
+    @macro double
+        %1.i *= 2
+    @sub
+        %1.i := %1.i + %1.i
+    @end
+
+    x := 3
+    x *= 2
+    x++
+    y := x
+    y **= 2
+    IF y > 40 THEN
+        y--
+    END
+    "};
+    let module_a = indoc! {"
+    @macro square
+        %1.i **= 2
+    @sub
+        $2.i := %1.i + 0
+        %1.i := $2.i * $2.i
+    @end
+    "};
+
+    let mut dir = Directory::new();
+    dir.insert("a".into(), module_a.into());
+
+    let flags = CompileFlags::default() | CompileFlags::CNF_COMBINE;
+    let context = run(snip, None, None, Some(flags), Some(dir.clone())).unwrap();
+
+    assert_is_int(context.get("x"), 7);
+    assert_is_int(context.get("y"), 48);
+
+    // the pest grammar does not know macros, the combine frontend is used instead
+    assert!(Builder::parse(snip, None).is_err());
+    assert!(Builder::parse_checked(snip, Frontend::Pest, Some(&dir)).is_ok());
+
+    let errors = Builder::parse_checked("x++\n", Frontend::Pest, None).unwrap_err();
+    assert_eq!(
+        errors.iter().map(Error::id).collect::<Vec<_>>(),
+        vec!["E017"]
+    );
+}
+
+// the macros of the standard library imported explicitly, instead of the conditionals and
+// assignments of the language itself
+#[test]
+fn test_std_macros() {
+    let flags = CompileFlags::default() | CompileFlags::CNF_COMBINE;
+    let expected = |verb: &str, x: usize, y: usize| match verb {
+        "==" => x == y,
+        "!=" => x != y,
+        ">" => x > y,
+        ">=" => x >= y,
+        "<" => x < y,
+        _ => x <= y,
+    };
+
+    for verb in ["==", "!=", ">", ">=", "<", "<="] {
+        for (x, y) in [(0, 0), (2, 2), (1, 3), (3, 1), (0, 2), (2, 0)] {
+            let snip = format!(
+                "FROM std::macros::ifelse MACRO IMPORT *\n\
+                 x := {x}\ny := {y}\nw := 5\n\
+                 IF x {verb} y THEN\n    z := 1\nELSE\n    z := 2\nEND\n\
+                 IF x {verb} y THEN\n    w := 1\nEND\n\
+                 IF x != 0 THEN\n    v := 1\nELSE\n    v := 2\nEND\n",
+                x = x,
+                y = y,
+                verb = verb
+            );
+            let context = run(&snip, None, None, Some(flags), None).unwrap();
+
+            let value = |ident: &str| context.get(ident).cloned();
+            let (z, w) = if expected(verb, x, y) {
+                (1u8, 1u8)
+            } else {
+                (2, 5)
+            };
+            let v = if x != 0 { 1u8 } else { 2 };
+
+            let message = format!("{} {} {}", x, verb, y);
+            assert_eq!(value("z"), Some(BigUint::from(z)), "{}", message);
+            assert_eq!(value("w"), Some(BigUint::from(w)), "{}", message);
+            assert_eq!(value("v"), Some(BigUint::from(v)), "{}", message);
+        }
+    }
+
+    // the temporary of x += n is assigned on every iteration, it used to accumulate
+    let snip = "FROM std::macros::inline MACRO IMPORT *\n\
+                x := 1\nLOOP n DO\n    x += 2\nEND\ny := 5\ny -= x\n";
+    let mut locals: Variables = HashMap::new();
+    locals.insert("n".to_string(), BigUint::from(3u8));
+
+    let context = run(snip, None, Some(locals), Some(flags), None).unwrap();
+    assert_is_int(context.get("x"), 7);
+    assert_is_int(context.get("y"), 0);
+
+    let snip = "FROM std::macros::assign MACRO IMPORT *\n\
+                x := 4\ny := x * 3\nz := y - x\nw := z * x\n";
+    let context = run(snip, None, None, Some(flags), None).unwrap();
+    assert_is_int(context.get("y"), 12);
+    assert_is_int(context.get("z"), 8);
+    assert_is_int(context.get("w"), 32);
+}
+
+#[test]
+fn test_fmt() {
+    for snip in snippets() {
+        let formatted = match Builder::fmt(&snip, None) {
+            Ok(formatted) => formatted,
+            Err(_) => continue,
+        };
+
+        assert_eq!(
+            Builder::fmt(&formatted, None).unwrap(),
+            formatted,
+            "{}",
+            snip
+        );
+        assert_eq!(
+            Builder::parse(&snip, None).is_ok(),
+            Builder::parse(&formatted, None).is_ok(),
+            "{}",
+            formatted
+        );
+    }
+
+    let formatted = Builder::fmt("x:=0;loop y do x := x+1 end # count\n", None).unwrap();
+    assert_eq!(
+        formatted,
+        "x := 0\nLOOP y DO\n    x := x + 1\nEND # count\n"
+    );
+
+    let mut locals: Variables = HashMap::new();
+    locals.insert("y".to_string(), BigUint::from(3u8));
+
+    let context = run(&formatted, None, Some(locals), None, None).unwrap();
+    assert_is_int(context.get("x"), 3);
+
+    // the combine parser accepts every statement as the invocation of a macro
+    for snip in ["x := \n", "x := (\n", "x := y\ny +\n"] {
+        let errors = Builder::fmt(snip, None).unwrap_err();
+        assert_eq!(
+            errors.iter().map(Error::id).collect::<Vec<_>>(),
+            vec!["E017"],
+            "{}",
+            snip
+        );
+    }
+
+    // the macros of the header are known while formatting
+    let snip = "FROM std::macros::inline MACRO IMPORT *\nx++\nx  +=  2\n";
+    assert_eq!(
+        Builder::fmt(snip, None).unwrap(),
+        "FROM std::macros::inline MACRO IMPORT *\nx++\nx += 2\n"
+    );
+}
+
+#[test]
+#[cfg(feature = "cli")]
+fn test_parse_recovering() {
+    let snip = indoc! {"
+    fn inc(a) -> b decl
+        b := a + 1
+        b :=
+    end
+
+    x := inc(y
+    y := 2
+    LOOP y DO
+        z := y +
+        z := z + 1
+    END
+    w :!= 3
+    "};
+    let (module, errors) = Builder::parse_recovering(snip, None);
+
+    assert_eq!(
+        errors
+            .iter()
+            .map(|error| (error.id(), error.lno.row.0))
+            .collect::<Vec<_>>(),
+        vec![("E017", 3), ("E017", 6), ("E017", 9), ("E017", 12)]
+    );
+
+    // only the broken statements are left out, also in nested blocks
+    assert_eq!(module.decl.len(), 1);
+    assert_eq!(module.decl[0].terms.display(0, None), "b := a + 1");
+    assert_eq!(
+        module.code.display(0, None),
+        "y := 2\n\nLOOP y DO\nz := z + 1\nEND"
+    );
+
+    // valid sources are parsed like parse
+    let snip = "x := 1\ny := x + 2\n";
+    let (module, errors) = Builder::parse_recovering(snip, None);
+    assert_eq!(errors, vec![]);
+    assert_eq!(
+        module,
+        Builder::parse_with(snip, None, Frontend::Combine).unwrap()
+    );
+}
+
 // This is a special tests, that looks what the LIPS count is.
 #[test]
 #[ignore]
@@ -1 +1,87 @@
-pub type LineNo = (usize, usize);
+#[cfg(feature = "cli")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// The location of a node in the source. Rows are 1-based and inclusive, columns are 1-based
+// character offsets into the first and the last row, the end column is exclusive.
+// The default location (every value 0) is unknown, like for nodes generated on compilation.
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, Default, Eq, PartialEq, Hash, PartialOrd, Ord,
+)]
+#[cfg_attr(feature = "cli", derive(JsonSchema))]
+pub struct LineNo {
+    pub row: (usize, usize),
+    pub col: (usize, usize),
+}
+
+impl LineNo {
+    pub fn new(start: (usize, usize), end: (usize, usize)) -> Self {
+        LineNo {
+            row: (start.0, end.0),
+            col: (start.1, end.1),
+        }
+    }
+
+    // the whole rows, without columns
+    pub fn rows(start: usize, end: usize) -> Self {
+        LineNo {
+            row: (start, end),
+            col: (0, 0),
+        }
+    }
+}
+
+// Byte offsets of the start of every row, used to convert the byte columns of the lexer.
+pub struct LineIndex<'a> {
+    source: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+
+        LineIndex { source, starts }
+    }
+
+    // the row and column of a byte column in a row, rows of the lexer start at 0
+    pub fn column(&self, row: usize, col: usize) -> (usize, usize) {
+        let start = self.starts.get(row).copied().unwrap_or(self.source.len());
+        let col = match self.source.get(start..start + col) {
+            Some(text) => text.chars().count(),
+            None => col,
+        };
+
+        (row + 1, col + 1)
+    }
+}
+
+//region Tests
+#[cfg(test)]
+mod tests {
+    use crate::types::{LineIndex, LineNo};
+
+    #[test]
+    fn test_line_index() {
+        let index = LineIndex::new("x := 1\nÿ := x + 2\n");
+
+        assert_eq!(index.column(0, 0), (1, 1));
+        assert_eq!(index.column(0, 6), (1, 7));
+        // columns count characters, ÿ is two bytes long
+        assert_eq!(index.column(1, 3), (2, 3));
+        assert_eq!(index.column(2, 0), (3, 1));
+        // columns outside of the source are kept
+        assert_eq!(index.column(5, 2), (6, 3));
+
+        assert_eq!(
+            LineNo::new((2, 1), (2, 11)),
+            LineNo {
+                row: (2, 2),
+                col: (1, 11)
+            }
+        );
+    }
+}
+//endregion